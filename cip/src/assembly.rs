@@ -0,0 +1,459 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+//! Assembly Object (class 0x04), process data of I/O devices and drives
+//!
+//! The layout maps assembly bytes to named signals, by byte offset, type and bit.
+//!
+//! ```rust,ignore
+//! let layout = AssemblyLayout::new()
+//!     .bit("Running", 0, 0)
+//!     .bit("Faulted", 0, 1)
+//!     .field("Speed", 2, ElementaryType::Int)
+//!     .field("Current", 4, ElementaryType::Real);
+//! for signal in client.read_assembly_signals(100, &layout).await? {
+//!     println!("{} = {:?}", signal.name, signal.value);
+//! }
+//! client
+//!     .update_assembly(150, &layout, &[("Speed", FieldValue::Int(1500))])
+//!     .await?;
+//! ```
+
+use crate::{data_type::ElementaryType, epath::EPath, error::cip_error, service::*};
+use bytes::{BufMut, Bytes, BytesMut};
+use rseip_core::{
+    codec::{BytesHolder, Encode, Encoder},
+    Error,
+};
+
+/// Assembly Object
+pub const CLASS_ASSEMBLY: u16 = 0x04;
+
+const ATTR_DATA: u16 = 3;
+const ATTR_SIZE: u16 = 4;
+
+/// value of field
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldValue {
+    /// BOOL
+    Bool(bool),
+    /// SINT
+    Sint(i8),
+    /// INT
+    Int(i16),
+    /// DINT
+    Dint(i32),
+    /// LINT
+    Lint(i64),
+    /// USINT
+    Usint(u8),
+    /// UINT
+    Uint(u16),
+    /// UDINT
+    Udint(u32),
+    /// ULINT
+    Ulint(u64),
+    /// REAL
+    Real(f32),
+    /// LREAL
+    Lreal(f64),
+}
+
+impl FieldValue {
+    /// type of the value
+    #[inline]
+    pub fn field_type(&self) -> ElementaryType {
+        match self {
+            Self::Bool(_) => ElementaryType::Bool,
+            Self::Sint(_) => ElementaryType::Sint,
+            Self::Int(_) => ElementaryType::Int,
+            Self::Dint(_) => ElementaryType::Dint,
+            Self::Lint(_) => ElementaryType::Lint,
+            Self::Usint(_) => ElementaryType::Usint,
+            Self::Uint(_) => ElementaryType::Uint,
+            Self::Udint(_) => ElementaryType::Udint,
+            Self::Ulint(_) => ElementaryType::Ulint,
+            Self::Real(_) => ElementaryType::Real,
+            Self::Lreal(_) => ElementaryType::Lreal,
+        }
+    }
+
+    /// decode value of the type from little endian bytes, `None` if size mismatch
+    pub fn from_le_bytes(field_type: ElementaryType, buf: &[u8]) -> Option<Self> {
+        macro_rules! num {
+            ($ty:ty) => {
+                <$ty>::from_le_bytes(buf.try_into().ok()?)
+            };
+        }
+        let res = match field_type {
+            ElementaryType::Bool => Self::Bool(num!(u8) != 0),
+            ElementaryType::Sint => Self::Sint(num!(i8)),
+            ElementaryType::Int => Self::Int(num!(i16)),
+            ElementaryType::Dint => Self::Dint(num!(i32)),
+            ElementaryType::Lint => Self::Lint(num!(i64)),
+            ElementaryType::Usint => Self::Usint(num!(u8)),
+            ElementaryType::Uint => Self::Uint(num!(u16)),
+            ElementaryType::Udint => Self::Udint(num!(u32)),
+            ElementaryType::Ulint => Self::Ulint(num!(u64)),
+            ElementaryType::Real => Self::Real(num!(f32)),
+            ElementaryType::Lreal => Self::Lreal(num!(f64)),
+        };
+        Some(res)
+    }
+
+    /// numeric value, 1 or 0 for BOOL
+    #[inline]
+    pub fn as_f64(&self) -> f64 {
+        match *self {
+            Self::Bool(v) => v as u8 as f64,
+            Self::Sint(v) => v as f64,
+            Self::Int(v) => v as f64,
+            Self::Dint(v) => v as f64,
+            Self::Lint(v) => v as f64,
+            Self::Usint(v) => v as f64,
+            Self::Uint(v) => v as f64,
+            Self::Udint(v) => v as f64,
+            Self::Ulint(v) => v as f64,
+            Self::Real(v) => v as f64,
+            Self::Lreal(v) => v,
+        }
+    }
+
+    /// value of the type from numeric value; integers are rounded,
+    /// `None` if out of range of the type
+    #[inline]
+    pub fn from_f64(field_type: ElementaryType, v: f64) -> Option<Self> {
+        let i = v.round();
+        let res = match field_type {
+            ElementaryType::Bool => return Some(Self::Bool(v != 0.0)),
+            ElementaryType::Real => return Some(Self::Real(v as f32)),
+            ElementaryType::Lreal => return Some(Self::Lreal(v)),
+            ElementaryType::Sint => Self::Sint(i as i8),
+            ElementaryType::Int => Self::Int(i as i16),
+            ElementaryType::Dint => Self::Dint(i as i32),
+            ElementaryType::Lint => Self::Lint(i as i64),
+            ElementaryType::Usint => Self::Usint(i as u8),
+            ElementaryType::Uint => Self::Uint(i as u16),
+            ElementaryType::Udint => Self::Udint(i as u32),
+            ElementaryType::Ulint => Self::Ulint(i as u64),
+        };
+        // saturated
+        if res.as_f64() == i {
+            Some(res)
+        } else {
+            None
+        }
+    }
+}
+
+impl Encode for FieldValue {
+    #[inline]
+    fn encode_by_ref<A: Encoder>(
+        &self,
+        buf: &mut BytesMut,
+        _encoder: &mut A,
+    ) -> Result<(), A::Error> {
+        match *self {
+            Self::Bool(v) => buf.put_u8(if v { 1 } else { 0 }),
+            Self::Sint(v) => buf.put_i8(v),
+            Self::Int(v) => buf.put_i16_le(v),
+            Self::Dint(v) => buf.put_i32_le(v),
+            Self::Lint(v) => buf.put_i64_le(v),
+            Self::Usint(v) => buf.put_u8(v),
+            Self::Uint(v) => buf.put_u16_le(v),
+            Self::Udint(v) => buf.put_u32_le(v),
+            Self::Ulint(v) => buf.put_u64_le(v),
+            Self::Real(v) => buf.put_f32_le(v),
+            Self::Lreal(v) => buf.put_f64_le(v),
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn bytes_count(&self) -> usize {
+        self.field_type().size()
+    }
+}
+
+/// field of assembly data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    /// signal name
+    pub name: String,
+    /// byte offset
+    pub offset: usize,
+    /// field type
+    pub field_type: ElementaryType,
+    /// bit of BOOL field, counted from the byte offset
+    pub bit: Option<u16>,
+}
+
+impl Field {
+    /// byte offset and size of the field data
+    #[inline]
+    fn range(&self) -> (usize, usize) {
+        match self.bit {
+            Some(bit) => (self.offset + bit as usize / 8, 1),
+            None => (self.offset, self.field_type.size()),
+        }
+    }
+}
+
+/// named signal of assembly data
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signal {
+    /// signal name
+    pub name: String,
+    /// value
+    pub value: FieldValue,
+}
+
+/// declarative layout of assembly data
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AssemblyLayout {
+    fields: Vec<Field>,
+}
+
+impl AssemblyLayout {
+    /// empty layout
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// add a field of the type at the byte offset
+    #[inline]
+    pub fn field(
+        mut self,
+        name: impl Into<String>,
+        offset: usize,
+        field_type: ElementaryType,
+    ) -> Self {
+        self.fields.push(Field {
+            name: name.into(),
+            offset,
+            field_type,
+            bit: None,
+        });
+        self
+    }
+
+    /// add a BOOL field of the bit, counted from the byte offset
+    #[inline]
+    pub fn bit(mut self, name: impl Into<String>, offset: usize, bit: u16) -> Self {
+        self.fields.push(Field {
+            name: name.into(),
+            offset,
+            field_type: ElementaryType::Bool,
+            bit: Some(bit),
+        });
+        self
+    }
+
+    /// fields of the layout
+    #[inline]
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// min size of assembly data to hold all fields
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.fields
+            .iter()
+            .map(|f| {
+                let (offset, size) = f.range();
+                offset + size
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// decode all fields from assembly data
+    pub fn decode<E: Error>(&self, data: &[u8]) -> Result<Vec<Signal>, E> {
+        self.fields
+            .iter()
+            .map(|f| {
+                let value = get_field::<E>(f, data)?;
+                Ok(Signal {
+                    name: f.name.clone(),
+                    value,
+                })
+            })
+            .collect()
+    }
+
+    /// decode field of the name from assembly data
+    pub fn get<E: Error>(&self, data: &[u8], name: &str) -> Result<FieldValue, E> {
+        get_field(self.find::<E>(name)?, data)
+    }
+
+    /// encode the value of the field into assembly data
+    pub fn set<E: Error>(&self, data: &mut [u8], name: &str, value: FieldValue) -> Result<(), E> {
+        let field = self.find::<E>(name)?;
+        if field.field_type != value.field_type() {
+            return Err(cip_error(format_args!(
+                "assembly layout - expected {:?} for field {}",
+                field.field_type, field.name
+            )));
+        }
+        let buf = field_data_mut::<E>(field, data)?;
+        match (value, field.bit) {
+            (FieldValue::Bool(v), Some(bit)) => {
+                let mask = 1 << (bit % 8);
+                if v {
+                    buf[0] |= mask;
+                } else {
+                    buf[0] &= !mask;
+                }
+            }
+            (FieldValue::Bool(v), None) => buf[0] = if v { 1 } else { 0 },
+            (FieldValue::Sint(v), _) => buf.copy_from_slice(&v.to_le_bytes()),
+            (FieldValue::Int(v), _) => buf.copy_from_slice(&v.to_le_bytes()),
+            (FieldValue::Dint(v), _) => buf.copy_from_slice(&v.to_le_bytes()),
+            (FieldValue::Lint(v), _) => buf.copy_from_slice(&v.to_le_bytes()),
+            (FieldValue::Usint(v), _) => buf.copy_from_slice(&v.to_le_bytes()),
+            (FieldValue::Uint(v), _) => buf.copy_from_slice(&v.to_le_bytes()),
+            (FieldValue::Udint(v), _) => buf.copy_from_slice(&v.to_le_bytes()),
+            (FieldValue::Ulint(v), _) => buf.copy_from_slice(&v.to_le_bytes()),
+            (FieldValue::Real(v), _) => buf.copy_from_slice(&v.to_le_bytes()),
+            (FieldValue::Lreal(v), _) => buf.copy_from_slice(&v.to_le_bytes()),
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn find<E: Error>(&self, name: &str) -> Result<&Field, E> {
+        self.fields
+            .iter()
+            .find(|f| f.name == name)
+            .ok_or_else(|| cip_error(format_args!("assembly layout - unknown field {}", name)))
+    }
+}
+
+#[inline]
+fn out_of_range<E: Error>(field: &Field) -> E {
+    cip_error(format_args!(
+        "assembly layout - field {} out of range",
+        field.name
+    ))
+}
+
+fn get_field<E: Error>(field: &Field, data: &[u8]) -> Result<FieldValue, E> {
+    let (offset, size) = field.range();
+    let buf = data
+        .get(offset..offset + size)
+        .ok_or_else(|| out_of_range::<E>(field))?;
+    match field.bit {
+        Some(bit) => Ok(FieldValue::Bool(buf[0] & (1 << (bit % 8)) != 0)),
+        None => FieldValue::from_le_bytes(field.field_type, buf).ok_or_else(|| out_of_range(field)),
+    }
+}
+
+#[inline]
+fn field_data_mut<'a, E: Error>(field: &Field, data: &'a mut [u8]) -> Result<&'a mut [u8], E> {
+    let (offset, size) = field.range();
+    data.get_mut(offset..offset + size)
+        .ok_or_else(|| out_of_range::<E>(field))
+}
+
+/// Assembly Object services
+#[async_trait::async_trait]
+pub trait AssemblyService: MessageService {
+    /// read data of the assembly instance, attribute 3
+    async fn read_assembly(&mut self, instance: u16) -> Result<Bytes, Self::Error> {
+        let path = attribute_path(instance, ATTR_DATA);
+        let res: BytesHolder = send_and_extract(self, 0x0E, path, ()).await?;
+        Ok(res.into())
+    }
+
+    /// write data of the assembly instance, attribute 3
+    async fn write_assembly<D: Encode + Send + Sync>(
+        &mut self,
+        instance: u16,
+        data: D,
+    ) -> Result<(), Self::Error> {
+        let path = attribute_path(instance, ATTR_DATA);
+        send_and_extract(self, 0x10, path, data).await
+    }
+
+    /// read data size in bytes of the assembly instance, attribute 4
+    async fn assembly_size(&mut self, instance: u16) -> Result<u16, Self::Error> {
+        let path = attribute_path(instance, ATTR_SIZE);
+        send_and_extract(self, 0x0E, path, ()).await
+    }
+
+    /// read data of the assembly instance, and decode signals by the layout
+    async fn read_assembly_signals(
+        &mut self,
+        instance: u16,
+        layout: &AssemblyLayout,
+    ) -> Result<Vec<Signal>, Self::Error> {
+        let data = self.read_assembly(instance).await?;
+        layout.decode(&data)
+    }
+
+    /// read data of the assembly instance, set the signals by the layout, then write back
+    async fn update_assembly(
+        &mut self,
+        instance: u16,
+        layout: &AssemblyLayout,
+        values: &[(&str, FieldValue)],
+    ) -> Result<(), Self::Error> {
+        let data = self.read_assembly(instance).await?;
+        let mut data = data.to_vec();
+        for (name, value) in values {
+            layout.set::<Self::Error>(&mut data, name, *value)?;
+        }
+        self.write_assembly(instance, Bytes::from(data)).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: MessageService> AssemblyService for T {}
+
+#[inline]
+fn attribute_path(instance: u16, attribute: u16) -> EPath {
+    EPath::default()
+        .with_class(CLASS_ASSEMBLY)
+        .with_instance(instance)
+        .with_attribute(attribute)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rseip_core::tests::CodecError;
+
+    #[test]
+    fn test_layout() {
+        let layout = AssemblyLayout::new()
+            .bit("Running", 0, 0)
+            .bit("Faulted", 0, 9)
+            .field("Speed", 2, ElementaryType::Int)
+            .field("Current", 4, ElementaryType::Real);
+        assert_eq!(layout.size(), 8);
+        let mut data = vec![0x01, 0x02, 0xDC, 0x05, 0, 0, 0x20, 0x41];
+        let res = layout.decode::<CodecError>(&data).unwrap();
+        assert_eq!(res[0].value, FieldValue::Bool(true));
+        assert_eq!(res[1].value, FieldValue::Bool(true));
+        assert_eq!(res[2].value, FieldValue::Int(1500));
+        assert_eq!(res[3].value, FieldValue::Real(10.0));
+
+        layout
+            .set::<CodecError>(&mut data, "Faulted", FieldValue::Bool(false))
+            .unwrap();
+        layout
+            .set::<CodecError>(&mut data, "Speed", FieldValue::Int(-1))
+            .unwrap();
+        assert_eq!(&data[..4], &[0x01, 0x00, 0xFF, 0xFF]);
+        assert!(layout
+            .set::<CodecError>(&mut data, "Speed", FieldValue::Dint(1))
+            .is_err());
+        assert!(layout.get::<CodecError>(&data[..4], "Current").is_err());
+        assert!(layout.get::<CodecError>(&data, "Unknown").is_err());
+    }
+}
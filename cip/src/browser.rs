@@ -0,0 +1,382 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+//! browse objects of CIP devices, by the Message Router Object (class 0x02)
+//!
+//! Classes are listed from the object list of the Message Router. Instances are
+//! enumerated by Find_Next_Object_Instance if supported, otherwise probed up to
+//! the max instance of the class. Attributes are probed by Get_Attribute_Single,
+//! and kept as raw bytes.
+//!
+//! ```rust,ignore
+//! let tree = client.browse(BrowseOptions::default().max_instances(8)).await?;
+//! println!("{}", tree);
+//! ```
+
+use crate::{epath::EPath, service::*, Status};
+use bytes::Bytes;
+use core::fmt;
+use rseip_core::codec::{BytesHolder, Decode, Decoder};
+
+/// Message Router Object
+pub const CLASS_MESSAGE_ROUTER: u16 = 0x02;
+/// Find_Next_Object_Instance service
+pub const SERVICE_FIND_NEXT_OBJECT_INSTANCE: u8 = 0x11;
+
+/// max number of instances per Find_Next_Object_Instance request
+const FIND_NEXT_COUNT: u8 = 100;
+
+/// general status: path destination unknown
+const STATUS_PATH_DESTINATION_UNKNOWN: u8 = 0x05;
+/// general status: object does not exist
+const STATUS_OBJECT_NOT_EXIST: u8 = 0x16;
+
+/// options of browsing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrowseOptions {
+    /// max number of instances per class
+    pub max_instances: u16,
+    /// max id of instance attributes to probe
+    pub max_attributes: u16,
+}
+
+impl Default for BrowseOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            max_instances: 32,
+            max_attributes: 32,
+        }
+    }
+}
+
+impl BrowseOptions {
+    /// max number of instances per class, default 32
+    #[inline]
+    pub fn max_instances(mut self, val: u16) -> Self {
+        self.max_instances = val;
+        self
+    }
+
+    /// max id of instance attributes to probe, default 32
+    #[inline]
+    pub fn max_attributes(mut self, val: u16) -> Self {
+        self.max_attributes = val;
+        self
+    }
+}
+
+/// class attributes, `None` if not supported
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ClassInfo {
+    /// class id
+    pub class: u16,
+    /// attribute 1: revision
+    pub revision: Option<u16>,
+    /// attribute 2: max instance
+    pub max_instance: Option<u16>,
+    /// attribute 3: number of instances
+    pub instance_count: Option<u16>,
+    /// attribute 6: max id of class attributes
+    pub max_class_attribute: Option<u16>,
+    /// attribute 7: max id of instance attributes
+    pub max_instance_attribute: Option<u16>,
+}
+
+/// attribute of an instance
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectAttribute {
+    /// attribute id
+    pub id: u16,
+    /// raw data
+    pub data: Bytes,
+}
+
+/// instance of a class
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectInstance {
+    /// instance id
+    pub instance: u16,
+    /// supported attributes
+    pub attributes: Vec<ObjectAttribute>,
+}
+
+/// class of the device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectClass {
+    /// class attributes
+    pub info: ClassInfo,
+    /// instances
+    pub instances: Vec<ObjectInstance>,
+}
+
+/// objects of the device
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DeviceTree {
+    /// classes
+    pub classes: Vec<ObjectClass>,
+}
+
+impl fmt::Display for DeviceTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |v: Option<u16>| v.map(|v| v.to_string()).unwrap_or_else(|| "-".to_owned());
+        for class in self.classes.iter() {
+            let info = &class.info;
+            writeln!(
+                f,
+                "class {:#06x} revision {} max instance {} instances {}",
+                info.class,
+                show(info.revision),
+                show(info.max_instance),
+                show(info.instance_count)
+            )?;
+            for instance in class.instances.iter() {
+                writeln!(f, "  instance {}", instance.instance)?;
+                for attr in instance.attributes.iter() {
+                    write!(f, "    attribute {}:", attr.id)?;
+                    for b in attr.data.iter() {
+                        write!(f, " {:02x}", b)?;
+                    }
+                    writeln!(f)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// browse objects of the device
+#[async_trait::async_trait]
+pub trait BrowseService: MessageService {
+    /// supported classes, from the object list of the Message Router
+    async fn object_list(&mut self) -> Result<Vec<u16>, Self::Error> {
+        let path = EPath::default()
+            .with_class(CLASS_MESSAGE_ROUTER)
+            .with_instance(1)
+            .with_attribute(1);
+        let res: ObjectList = send_and_extract(self, 0x0E, path, ()).await?;
+        Ok(res.0)
+    }
+
+    /// read class attributes of the class
+    async fn class_info(&mut self, class: u16) -> Result<ClassInfo, Self::Error> {
+        let revision = class_attribute(self, class, 1).await?;
+        let max_instance = class_attribute(self, class, 2).await?;
+        let instance_count = class_attribute(self, class, 3).await?;
+        let max_class_attribute = class_attribute(self, class, 6).await?;
+        let max_instance_attribute = class_attribute(self, class, 7).await?;
+        Ok(ClassInfo {
+            class,
+            revision,
+            max_instance,
+            instance_count,
+            max_class_attribute,
+            max_instance_attribute,
+        })
+    }
+
+    /// instances of the class by Find_Next_Object_Instance, `None` if not supported
+    async fn find_instances(&mut self, class: u16) -> Result<Option<Vec<u16>>, Self::Error> {
+        let mut res: Vec<u16> = Vec::new();
+        let mut start = 0;
+        loop {
+            let path = EPath::default().with_class(class).with_instance(start);
+            let reply: Result<InstanceList, Status> = send_and_extract_status(
+                self,
+                SERVICE_FIND_NEXT_OBJECT_INSTANCE,
+                path,
+                FIND_NEXT_COUNT,
+            )
+            .await?;
+            let list = match reply {
+                Ok(v) => v.0,
+                Err(_) if res.is_empty() => return Ok(None),
+                Err(_) => break,
+            };
+            let count = list.len();
+            res.extend(list.into_iter().filter(|v| *v > start));
+            match res.last() {
+                Some(last) if *last > start && count >= FIND_NEXT_COUNT as usize => start = *last,
+                _ => break,
+            }
+        }
+        Ok(Some(res))
+    }
+
+    /// probe attributes `1..=max_attributes` of the instance by Get_Attribute_Single,
+    /// `None` if the instance does not exist
+    async fn instance_attributes(
+        &mut self,
+        class: u16,
+        instance: u16,
+        max_attributes: u16,
+    ) -> Result<Option<Vec<ObjectAttribute>>, Self::Error> {
+        let mut res = Vec::new();
+        let mut exists = false;
+        for id in 1..=max_attributes {
+            let path = EPath::default()
+                .with_class(class)
+                .with_instance(instance)
+                .with_attribute(id);
+            let reply: Result<BytesHolder, Status> =
+                send_and_extract_status(self, 0x0E, path, ()).await?;
+            match reply {
+                Ok(data) => {
+                    exists = true;
+                    res.push(ObjectAttribute {
+                        id,
+                        data: data.into(),
+                    });
+                }
+                Err(status) => exists |= !is_missing(&status),
+            }
+        }
+        Ok(if exists { Some(res) } else { None })
+    }
+
+    /// walk classes, instances and attributes of the device;
+    /// error replies of the device are skipped
+    async fn browse(&mut self, options: BrowseOptions) -> Result<DeviceTree, Self::Error> {
+        let mut tree = DeviceTree::default();
+        let classes = self.object_list().await?;
+        for class in classes {
+            let info = self.class_info(class).await?;
+            let max_attributes = info
+                .max_instance_attribute
+                .unwrap_or(options.max_attributes)
+                .min(options.max_attributes);
+            let found = self.find_instances(class).await?;
+            // without instance list, probe until the max instance or the first missing instance
+            let (candidates, probing) = match found {
+                Some(v) => (v, false),
+                None => {
+                    let max = info.max_instance.unwrap_or(options.max_instances);
+                    ((1..=max.min(options.max_instances)).collect(), true)
+                }
+            };
+            let mut instances = Vec::new();
+            for instance in candidates.into_iter().take(options.max_instances as usize) {
+                let attributes = self
+                    .instance_attributes(class, instance, max_attributes)
+                    .await?;
+                match attributes {
+                    Some(attributes) => instances.push(ObjectInstance {
+                        instance,
+                        attributes,
+                    }),
+                    None if probing && info.max_instance.is_none() => break,
+                    None => {}
+                }
+            }
+            tree.classes.push(ObjectClass { info, instances });
+        }
+        Ok(tree)
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: MessageService> BrowseService for T {}
+
+/// read class attribute of UINT, `None` on error replies
+#[inline]
+async fn class_attribute<S>(
+    service: &mut S,
+    class: u16,
+    attribute: u16,
+) -> Result<Option<u16>, S::Error>
+where
+    S: MessageService + ?Sized,
+{
+    let path = EPath::default()
+        .with_class(class)
+        .with_instance(0)
+        .with_attribute(attribute);
+    let res: Result<u16, Status> = send_and_extract_status(service, 0x0E, path, ()).await?;
+    Ok(res.ok())
+}
+
+/// the object does not exist?
+#[inline]
+fn is_missing(status: &Status) -> bool {
+    status.general == STATUS_OBJECT_NOT_EXIST || status.general == STATUS_PATH_DESTINATION_UNKNOWN
+}
+
+/// object list of Message Router, attribute 1
+struct ObjectList(Vec<u16>);
+
+impl<'de> Decode<'de> for ObjectList {
+    fn decode<D>(mut decoder: D) -> Result<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        decoder.ensure_size(2)?;
+        let count = decoder.decode_u16() as usize;
+        decoder.ensure_size(count * 2)?;
+        let res = (0..count).map(|_| decoder.decode_u16()).collect();
+        Ok(Self(res))
+    }
+}
+
+/// reply of Find_Next_Object_Instance
+struct InstanceList(Vec<u16>);
+
+impl<'de> Decode<'de> for InstanceList {
+    fn decode<D>(mut decoder: D) -> Result<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        decoder.ensure_size(1)?;
+        let count = decoder.decode_u8() as usize;
+        decoder.ensure_size(count * 2)?;
+        let res = (0..count).map(|_| decoder.decode_u16()).collect();
+        Ok(Self(res))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rseip_core::{codec::LittleEndianDecoder, tests::CodecError};
+
+    #[test]
+    fn test_decode_lists() {
+        let buf = Bytes::from_static(&[3, 0, 0x01, 0, 0x02, 0, 0xF5, 0]);
+        let res = ObjectList::decode(LittleEndianDecoder::<CodecError>::new(buf)).unwrap();
+        assert_eq!(res.0, vec![0x01, 0x02, 0xF5]);
+
+        let buf = Bytes::from_static(&[2, 1, 0, 3, 0]);
+        let res = InstanceList::decode(LittleEndianDecoder::<CodecError>::new(buf)).unwrap();
+        assert_eq!(res.0, vec![1, 3]);
+
+        let buf = Bytes::from_static(&[3, 0, 0x01, 0]);
+        assert!(ObjectList::decode(LittleEndianDecoder::<CodecError>::new(buf)).is_err());
+    }
+
+    #[test]
+    fn test_display_tree() {
+        let tree = DeviceTree {
+            classes: vec![ObjectClass {
+                info: ClassInfo {
+                    class: 1,
+                    revision: Some(1),
+                    max_instance: Some(1),
+                    ..Default::default()
+                },
+                instances: vec![ObjectInstance {
+                    instance: 1,
+                    attributes: vec![ObjectAttribute {
+                        id: 1,
+                        data: Bytes::from_static(&[1, 0]),
+                    }],
+                }],
+            }],
+        };
+        assert_eq!(
+            tree.to_string(),
+            "class 0x0001 revision 1 max instance 1 instances -\n  instance 1\n    attribute 1: 01 00\n"
+        );
+    }
+}
@@ -6,10 +6,16 @@
 
 pub mod message_reply;
 use crate::*;
-use crate::{identity::IdentityObject, socket::SocketAddr};
+use crate::{
+    identity::{Identity, IdentityAttributes, IdentityObject, IdentityStatus},
+    socket::SocketAddr,
+};
 use bytes::Buf;
 use core::{slice, str};
-use rseip_core::codec::{Decode, Decoder};
+use rseip_core::{
+    codec::{Decode, Decoder},
+    Error,
+};
 use std::borrow::Cow;
 
 impl<'de> Decode<'de> for IdentityObject<'de> {
@@ -53,6 +59,98 @@ impl<'de> Decode<'de> for IdentityObject<'de> {
     }
 }
 
+impl<'de> Decode<'de> for Identity {
+    fn decode<D>(mut decoder: D) -> Result<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        decoder.ensure_size(15)?;
+        let mut identity = Identity {
+            vendor_id: decoder.decode_u16(),
+            device_type: decoder.decode_u16(),
+            product_code: decoder.decode_u16(),
+            revision: Revision {
+                major: decoder.decode_u8(),
+                minor: decoder.decode_u8(),
+            },
+            status: IdentityStatus(decoder.decode_u16()),
+            serial_number: decoder.decode_u32(),
+            product_name: {
+                let name_len = decoder.decode_u8() as usize;
+                decoder.ensure_size(name_len)?;
+                let data = decoder.buf_mut().copy_to_bytes(name_len);
+                String::from_utf8_lossy(&data).into_owned()
+            },
+            state: None,
+        };
+        if decoder.has_remaining() {
+            identity.state = Some(decoder.decode_u8().into());
+        }
+        // other optional attributes are ignored
+        let remaining = decoder.remaining();
+        decoder.buf_mut().advance(remaining);
+        Ok(identity)
+    }
+}
+
+impl<'de> Decode<'de> for IdentityAttributes {
+    fn decode<D>(mut decoder: D) -> Result<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        decoder.ensure_size(2)?;
+        let count = decoder.decode_u16();
+        let mut identity = Identity {
+            vendor_id: 0,
+            device_type: 0,
+            product_code: 0,
+            revision: Revision { major: 0, minor: 0 },
+            status: IdentityStatus(0),
+            serial_number: 0,
+            product_name: String::new(),
+            state: None,
+        };
+        for _ in 0..count {
+            decoder.ensure_size(4)?;
+            let id = decoder.decode_u16();
+            let status = decoder.decode_u16();
+            if status != 0 {
+                return Err(Error::custom(format_args!(
+                    "identity - bad attribute[{}] status: {:#0x}",
+                    id, status
+                )));
+            }
+            match id {
+                1 => identity.vendor_id = decoder.decode_any()?,
+                2 => identity.device_type = decoder.decode_any()?,
+                3 => identity.product_code = decoder.decode_any()?,
+                4 => {
+                    decoder.ensure_size(2)?;
+                    identity.revision = Revision {
+                        major: decoder.decode_u8(),
+                        minor: decoder.decode_u8(),
+                    };
+                }
+                5 => identity.status = IdentityStatus(decoder.decode_any()?),
+                6 => identity.serial_number = decoder.decode_any()?,
+                7 => {
+                    decoder.ensure_size(1)?;
+                    let name_len = decoder.decode_u8() as usize;
+                    decoder.ensure_size(name_len)?;
+                    let data = decoder.buf_mut().copy_to_bytes(name_len);
+                    identity.product_name = String::from_utf8_lossy(&data).into_owned();
+                }
+                8 => {
+                    decoder.ensure_size(1)?;
+                    identity.state = Some(decoder.decode_u8().into());
+                }
+                v => return Err(Error::invalid_value(format_args!("attribute {}", v), "1-8")),
+            }
+        }
+        Ok(Self(identity))
+    }
+}
+
 impl<'de> Decode<'de> for ListServiceItem<'de> {
     fn decode<D>(mut decoder: D) -> Result<Self, D::Error>
     where
@@ -467,6 +467,29 @@ impl<P> OpenOptions<P> {
         self
     }
 
+    /// replace connection path, possibly of another type, eg. encoded bytes
+    pub fn with_connection_path<Q>(self, path: Q) -> OpenOptions<Q> {
+        OpenOptions {
+            o_t_connection_id: self.o_t_connection_id,
+            t_o_connection_id: self.t_o_connection_id,
+            priority_tick_time: self.priority_tick_time,
+            timeout_ticks: self.timeout_ticks,
+            connection_serial_number: self.connection_serial_number,
+            vendor_id: self.vendor_id,
+            originator_serial_number: self.originator_serial_number,
+            o_t_rpi: self.o_t_rpi,
+            t_o_rpi: self.t_o_rpi,
+            timeout_multiplier: self.timeout_multiplier,
+            connection_path: path,
+            o_t_params: self.o_t_params,
+            t_o_params: self.t_o_params,
+            transport_direction: self.transport_direction,
+            transport_class: self.transport_class,
+            transport_trigger: self.transport_trigger,
+            large_open: self.large_open,
+        }
+    }
+
     /// get transport class trigger
     pub(crate) fn transport_class_trigger(&self) -> u8 {
         let dir = self.transport_direction as u8;
@@ -0,0 +1,356 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+//! Connection Manager Object (class 0x06) diagnostics
+//!
+//! ```rust,ignore
+//! let stats = client.connection_statistics().await?;
+//! println!("open requests: {}, timeouts: {}", stats.open_requests, stats.connection_timeouts);
+//! for conn in client.connections().await? {
+//!     println!(
+//!         "#{} {:?} owner: vendor {:#06x} serial {:#010x}",
+//!         conn.connection_number, conn.state, conn.originator_vendor_id, conn.originator_serial_number
+//!     );
+//! }
+//! ```
+
+use crate::{epath::EPath, error::cip_error_status, service::*, Status};
+use bytes::Buf;
+use rseip_core::{
+    codec::{Decode, Decoder},
+    Error,
+};
+
+/// Connection Manager Object
+pub const CLASS_CONNECTION_MANAGER: u16 = 0x06;
+/// Get Connection Data service
+pub const SERVICE_GET_CONNECTION_DATA: u8 = 0x56;
+/// Search Connection Data service
+pub const SERVICE_SEARCH_CONNECTION_DATA: u8 = 0x57;
+/// general status: object does not exist
+const STATUS_OBJECT_DOES_NOT_EXIST: u8 = 0x16;
+
+/// statistics of Connection Manager Object, attributes 1-8,
+/// and connection entries of attributes 9-10 if available
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ConnectionStatistics {
+    /// number of Forward Open requests received
+    pub open_requests: u16,
+    /// number of Forward Open requests rejected because of bad format
+    pub open_format_rejects: u16,
+    /// number of Forward Open requests rejected because of lack of resources
+    pub open_resource_rejects: u16,
+    /// number of Forward Open requests rejected for other reasons
+    pub open_other_rejects: u16,
+    /// number of Forward Close requests received
+    pub close_requests: u16,
+    /// number of Forward Close requests rejected because of bad format
+    pub close_format_requests: u16,
+    /// number of Forward Close requests rejected for other reasons
+    pub close_other_requests: u16,
+    /// number of connection timeouts
+    pub connection_timeouts: u16,
+    /// number of connection entries, `None` if not reported
+    pub connection_entries: Option<u16>,
+    /// connection open bits, one bit per connection entry
+    pub connection_open_bits: Vec<u8>,
+}
+
+impl ConnectionStatistics {
+    /// connection numbers of open connections
+    pub fn open_connections(&self) -> impl Iterator<Item = u16> + '_ {
+        let entries = self.connection_entries.unwrap_or_default() as usize;
+        (0..entries)
+            .filter(move |i| {
+                self.connection_open_bits
+                    .get(i / 8)
+                    .map(|v| v & (1 << (i % 8)) != 0)
+                    .unwrap_or_default()
+            })
+            .map(|i| i as u16)
+    }
+}
+
+/// state of connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// 0: non-existent
+    NonExistent,
+    /// 1: configuring
+    Configuring,
+    /// 2: waiting for connection id
+    WaitingForConnectionId,
+    /// 3: established
+    Established,
+    /// 4: timed out
+    TimedOut,
+    /// 5: deferred delete
+    DeferredDelete,
+    /// 6: closing
+    Closing,
+    /// other values, reserved
+    Other(u16),
+}
+
+impl From<u16> for ConnectionState {
+    #[inline]
+    fn from(src: u16) -> Self {
+        match src {
+            0 => Self::NonExistent,
+            1 => Self::Configuring,
+            2 => Self::WaitingForConnectionId,
+            3 => Self::Established,
+            4 => Self::TimedOut,
+            5 => Self::DeferredDelete,
+            6 => Self::Closing,
+            v => Self::Other(v),
+        }
+    }
+}
+
+/// connection data, reply of Get Connection Data or Search Connection Data service
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionData {
+    /// connection number
+    pub connection_number: u16,
+    /// connection state
+    pub state: ConnectionState,
+    /// originator port
+    pub originator_port: u16,
+    /// target port
+    pub target_port: u16,
+    /// connection serial number
+    pub connection_serial_number: u16,
+    /// vendor id of the originator, the owner of the connection
+    pub originator_vendor_id: u16,
+    /// serial number of the originator, the owner of the connection
+    pub originator_serial_number: u32,
+    /// O->T connection id of the originator
+    pub originator_o_t_connection_id: u32,
+    /// O->T connection id of the target
+    pub target_o_t_connection_id: u32,
+    /// O->T connection timeout multiplier
+    pub o_t_timeout_multiplier: u8,
+    /// O->T requested packet interval of the originator, in microseconds
+    pub originator_o_t_rpi: u32,
+    /// O->T actual packet interval of the originator, in microseconds
+    pub originator_o_t_api: u32,
+    /// T->O connection id of the originator
+    pub originator_t_o_connection_id: u32,
+    /// T->O connection id of the target
+    pub target_t_o_connection_id: u32,
+    /// T->O connection timeout multiplier
+    pub t_o_timeout_multiplier: u8,
+    /// T->O requested packet interval of the originator, in microseconds
+    pub originator_t_o_rpi: u32,
+    /// T->O actual packet interval of the originator, in microseconds
+    pub originator_t_o_api: u32,
+}
+
+/// Connection Manager Object services
+#[async_trait::async_trait]
+pub trait ConnectionManagerService: MessageService {
+    /// read statistics and connection entries of the Connection Manager
+    async fn connection_statistics(&mut self) -> Result<ConnectionStatistics, Self::Error> {
+        send_and_extract(self, 0x01, instance_path(), ()).await
+    }
+
+    /// Get Connection Data service, read data of the connection by connection number
+    async fn connection_data(
+        &mut self,
+        connection_number: u16,
+    ) -> Result<ConnectionData, Self::Error> {
+        send_and_extract(
+            self,
+            SERVICE_GET_CONNECTION_DATA,
+            instance_path(),
+            connection_number,
+        )
+        .await
+    }
+
+    /// Search Connection Data service, find the connection by connection triad
+    async fn search_connection_data(
+        &mut self,
+        connection_serial_number: u16,
+        originator_vendor_id: u16,
+        originator_serial_number: u32,
+    ) -> Result<ConnectionData, Self::Error> {
+        send_and_extract(
+            self,
+            SERVICE_SEARCH_CONNECTION_DATA,
+            instance_path(),
+            (
+                connection_serial_number,
+                originator_vendor_id,
+                originator_serial_number,
+            ),
+        )
+        .await
+    }
+
+    /// list open connections of the device, by connection open bits of the Connection Manager;
+    /// connections closed while listing are skipped
+    async fn connections(&mut self) -> Result<Vec<ConnectionData>, Self::Error> {
+        let stats = self.connection_statistics().await?;
+        if stats.connection_entries.is_none() {
+            return Err(Error::custom(
+                "connection manager - connection entries not reported",
+            ));
+        }
+        let mut res = Vec::new();
+        for number in stats.open_connections() {
+            let data: Result<ConnectionData, Status> =
+                send_and_extract_status(self, SERVICE_GET_CONNECTION_DATA, instance_path(), number)
+                    .await?;
+            match data {
+                Ok(data) => res.push(data),
+                Err(status) if status.general == STATUS_OBJECT_DOES_NOT_EXIST => {}
+                Err(status) => return Err(cip_error_status(status)),
+            }
+        }
+        Ok(res)
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: MessageService> ConnectionManagerService for T {}
+
+#[inline]
+fn instance_path() -> EPath {
+    EPath::default()
+        .with_class(CLASS_CONNECTION_MANAGER)
+        .with_instance(0x01)
+}
+
+impl<'de> Decode<'de> for ConnectionStatistics {
+    fn decode<D>(mut decoder: D) -> Result<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        decoder.ensure_size(16)?;
+        let mut res = Self {
+            open_requests: decoder.decode_u16(),
+            open_format_rejects: decoder.decode_u16(),
+            open_resource_rejects: decoder.decode_u16(),
+            open_other_rejects: decoder.decode_u16(),
+            close_requests: decoder.decode_u16(),
+            close_format_requests: decoder.decode_u16(),
+            close_other_requests: decoder.decode_u16(),
+            connection_timeouts: decoder.decode_u16(),
+            ..Default::default()
+        };
+        if decoder.remaining() >= 2 {
+            let entries = decoder.decode_u16();
+            let len = (entries as usize).div_ceil(8);
+            decoder.ensure_size(len)?;
+            res.connection_entries = Some(entries);
+            res.connection_open_bits = decoder.buf_mut().copy_to_bytes(len).to_vec();
+        }
+        // other optional attributes are ignored
+        let remaining = decoder.remaining();
+        decoder.buf_mut().advance(remaining);
+        Ok(res)
+    }
+}
+
+impl<'de> Decode<'de> for ConnectionData {
+    fn decode<D>(mut decoder: D) -> Result<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        decoder.ensure_size(56)?;
+        let connection_number = decoder.decode_u16();
+        let state = decoder.decode_u16().into();
+        let originator_port = decoder.decode_u16();
+        let target_port = decoder.decode_u16();
+        let connection_serial_number = decoder.decode_u16();
+        let originator_vendor_id = decoder.decode_u16();
+        let originator_serial_number = decoder.decode_u32();
+        let originator_o_t_connection_id = decoder.decode_u32();
+        let target_o_t_connection_id = decoder.decode_u32();
+        let o_t_timeout_multiplier = decoder.decode_u8();
+        decoder.buf_mut().advance(3);
+        let originator_o_t_rpi = decoder.decode_u32();
+        let originator_o_t_api = decoder.decode_u32();
+        let originator_t_o_connection_id = decoder.decode_u32();
+        let target_t_o_connection_id = decoder.decode_u32();
+        let t_o_timeout_multiplier = decoder.decode_u8();
+        decoder.buf_mut().advance(3);
+        let originator_t_o_rpi = decoder.decode_u32();
+        let originator_t_o_api = decoder.decode_u32();
+        Ok(Self {
+            connection_number,
+            state,
+            originator_port,
+            target_port,
+            connection_serial_number,
+            originator_vendor_id,
+            originator_serial_number,
+            originator_o_t_connection_id,
+            target_o_t_connection_id,
+            o_t_timeout_multiplier,
+            originator_o_t_rpi,
+            originator_o_t_api,
+            originator_t_o_connection_id,
+            target_t_o_connection_id,
+            t_o_timeout_multiplier,
+            originator_t_o_rpi,
+            originator_t_o_api,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::{BufMut, BytesMut};
+    use rseip_core::{codec::LittleEndianDecoder, tests::CodecError};
+
+    #[test]
+    fn test_connection_statistics() {
+        let mut buf = BytesMut::new();
+        for i in 1..=8_u16 {
+            buf.put_u16_le(i);
+        }
+        buf.put_u16_le(10);
+        buf.put_slice(&[0b0000_0101, 0b0000_0010]);
+        let res =
+            ConnectionStatistics::decode(LittleEndianDecoder::<CodecError>::new(buf.freeze()))
+                .unwrap();
+        assert_eq!(res.open_requests, 1);
+        assert_eq!(res.connection_timeouts, 8);
+        assert_eq!(res.connection_entries, Some(10));
+        assert_eq!(res.open_connections().collect::<Vec<_>>(), vec![0, 2, 9]);
+    }
+
+    #[test]
+    fn test_connection_data() {
+        let mut buf = BytesMut::new();
+        buf.put_u16_le(2); // connection number
+        buf.put_u16_le(3); // established
+        buf.put_u16_le(1);
+        buf.put_u16_le(1);
+        buf.put_u16_le(0x1234);
+        buf.put_u16_le(0x0001);
+        buf.put_u32_le(0xAABBCCDD);
+        buf.put_slice(&[0; 8]);
+        buf.put_slice(&[2, 0, 0, 0]);
+        buf.put_u32_le(20_000);
+        buf.put_slice(&[0; 12]);
+        buf.put_slice(&[2, 0, 0, 0]);
+        buf.put_u32_le(20_000);
+        buf.put_u32_le(20_000);
+        assert_eq!(buf.len(), 56);
+        let res =
+            ConnectionData::decode(LittleEndianDecoder::<CodecError>::new(buf.freeze())).unwrap();
+        assert_eq!(res.connection_number, 2);
+        assert_eq!(res.state, ConnectionState::Established);
+        assert_eq!(res.originator_serial_number, 0xAABBCCDD);
+        assert_eq!(res.originator_o_t_rpi, 20_000);
+        assert_eq!(res.originator_t_o_api, 20_000);
+    }
+}
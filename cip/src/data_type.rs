@@ -0,0 +1,125 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+//! CIP elementary data types, by type code
+//!
+//! The one mapping of type codes for Assembly and Parameter objects,
+//! Logix tag types and UDT code generation.
+
+/// CIP elementary data type of fixed size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ElementaryType {
+    /// BOOL, one byte, or one bit if bit specified
+    Bool,
+    /// SINT
+    Sint,
+    /// INT
+    Int,
+    /// DINT
+    Dint,
+    /// LINT
+    Lint,
+    /// USINT
+    Usint,
+    /// UINT
+    Uint,
+    /// UDINT
+    Udint,
+    /// ULINT
+    Ulint,
+    /// REAL
+    Real,
+    /// LREAL
+    Lreal,
+}
+
+impl ElementaryType {
+    /// from CIP data type code, eg. 0xC3 for INT;
+    /// BYTE, WORD, DWORD and LWORD as unsigned integers
+    #[inline]
+    pub fn from_code(code: u16) -> Option<Self> {
+        let res = match code {
+            0xC1 => Self::Bool,
+            0xC2 => Self::Sint,
+            0xC3 => Self::Int,
+            0xC4 => Self::Dint,
+            0xC5 => Self::Lint,
+            0xC6 | 0xD1 => Self::Usint,
+            0xC7 | 0xD2 => Self::Uint,
+            0xC8 | 0xD3 => Self::Udint,
+            0xC9 | 0xD4 => Self::Ulint,
+            0xCA => Self::Real,
+            0xCB => Self::Lreal,
+            _ => return None,
+        };
+        Some(res)
+    }
+
+    /// CIP data type code
+    #[inline]
+    pub fn code(&self) -> u16 {
+        match self {
+            Self::Bool => 0xC1,
+            Self::Sint => 0xC2,
+            Self::Int => 0xC3,
+            Self::Dint => 0xC4,
+            Self::Lint => 0xC5,
+            Self::Usint => 0xC6,
+            Self::Uint => 0xC7,
+            Self::Udint => 0xC8,
+            Self::Ulint => 0xC9,
+            Self::Real => 0xCA,
+            Self::Lreal => 0xCB,
+        }
+    }
+
+    /// size in bytes
+    #[inline]
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Bool | Self::Sint | Self::Usint => 1,
+            Self::Int | Self::Uint => 2,
+            Self::Dint | Self::Udint | Self::Real => 4,
+            Self::Lint | Self::Ulint | Self::Lreal => 8,
+        }
+    }
+
+    /// name of the rust primitive type
+    #[inline]
+    pub fn rust_type(&self) -> &'static str {
+        match self {
+            Self::Bool => "bool",
+            Self::Sint => "i8",
+            Self::Int => "i16",
+            Self::Dint => "i32",
+            Self::Lint => "i64",
+            Self::Usint => "u8",
+            Self::Uint => "u16",
+            Self::Udint => "u32",
+            Self::Ulint => "u64",
+            Self::Real => "f32",
+            Self::Lreal => "f64",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_elementary_type() {
+        assert_eq!(ElementaryType::from_code(0xC3), Some(ElementaryType::Int));
+        assert_eq!(ElementaryType::from_code(0xD3), Some(ElementaryType::Udint));
+        assert_eq!(ElementaryType::from_code(0xDA), None);
+        for code in 0xC1..=0xCB {
+            let ty = ElementaryType::from_code(code).unwrap();
+            assert_eq!(ty.code(), code);
+        }
+        assert_eq!(ElementaryType::Lreal.size(), 8);
+        assert_eq!(ElementaryType::Lreal.rust_type(), "f64");
+    }
+}
@@ -0,0 +1,1158 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+//! EDS (Electronic Data Sheet) files
+//!
+//! EDS files are INI-style text files, `Keyword = field, field, ...;` entries in
+//! `[Section]`s, with `$` comments. All sections are kept as raw entries; the
+//! sections `[File]`, `[Device]`, `[Device Classification]`, `[Params]`,
+//! `[Assembly]` and `[Connection Manager]` are typed.
+//!
+//! EDS files read from devices by [`FileService::read_eds`] are often
+//! [`FileEncoding::Compressed`] (ZLIB); check the encoding and decompress the data
+//! before parsing, compressed data is not detected by the parser.
+//!
+//! ```rust,ignore
+//! let (info, data) = client.read_eds().await?;
+//! if info.encoding == Some(FileEncoding::Compressed) {
+//!     // decompress by a ZLIB decoder first, eg. `flate2`
+//! }
+//! let eds = Eds::parse::<ClientError>(&String::from_utf8_lossy(&data))?;
+//! // Connection1, the path is the application path of the device
+//! let options = eds.open_options(1).unwrap();
+//! let layout = eds.assembly_layout(100).unwrap();
+//! let params: Vec<_> = eds.params.iter().map(|v| v.to_parameter()).collect();
+//! ```
+//!
+//! [`FileService::read_eds`]: crate::file::FileService::read_eds
+//! [`FileEncoding::Compressed`]: crate::file::FileEncoding::Compressed
+
+use crate::{
+    assembly::{AssemblyLayout, FieldValue},
+    connection::{
+        ConnectionParameters, ConnectionType, Direction, OpenOptions, Priority, ReadlTimeFormat,
+        TransportClass, TriggerType, VariableLength,
+    },
+    data_type::ElementaryType,
+    error::cip_error,
+    parameter::{Parameter, ParameterDescriptor, Scaling},
+    Revision,
+};
+use bytes::Bytes;
+use core::{iter::Peekable, mem, str::Chars};
+use rseip_core::Error;
+
+/// max depth of nested assemblies
+const MAX_ASSEMBLY_DEPTH: usize = 8;
+
+/// entry of a section, `Keyword = field, field, ...;`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdsEntry {
+    /// keyword
+    pub keyword: String,
+    /// fields, strings unquoted, empty if not specified
+    pub fields: Vec<String>,
+    /// line number of the keyword
+    pub line: usize,
+}
+
+impl EdsEntry {
+    /// field at the index, empty if not present
+    #[inline]
+    pub fn field(&self, index: usize) -> &str {
+        self.fields
+            .get(index)
+            .map(|v| v.as_str())
+            .unwrap_or_default()
+    }
+}
+
+/// section of EDS file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdsSection {
+    /// section name, without brackets
+    pub name: String,
+    /// entries
+    pub entries: Vec<EdsEntry>,
+}
+
+impl EdsSection {
+    /// entry of the keyword, case insensitive
+    #[inline]
+    pub fn entry(&self, keyword: &str) -> Option<&EdsEntry> {
+        self.entries
+            .iter()
+            .find(|v| v.keyword.eq_ignore_ascii_case(keyword))
+    }
+
+    /// first field of the entry, empty if not present
+    #[inline]
+    fn text(&self, keyword: &str) -> String {
+        self.entry(keyword)
+            .map(|v| v.field(0).to_owned())
+            .unwrap_or_default()
+    }
+}
+
+/// value or reference of an entry field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdsValue {
+    /// not specified
+    Empty,
+    /// literal number
+    Number(u32),
+    /// `ParamN`, the default value of the parameter
+    Param(u16),
+    /// `AssemN`, the assembly
+    Assembly(u16),
+}
+
+/// item of a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathItem {
+    /// literal byte
+    Byte(u8),
+    /// `ParamN` or `[ParamN]`, the default value of the parameter in its data size
+    Param(u16),
+}
+
+/// path of entries, eg. `"20 04 24 64 2C 96 2C 64"`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EdsPath(pub Vec<PathItem>);
+
+/// `[File]` section
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EdsFile {
+    /// DescText
+    pub description: String,
+    /// CreateDate
+    pub create_date: String,
+    /// CreateTime
+    pub create_time: String,
+    /// ModDate
+    pub mod_date: String,
+    /// ModTime
+    pub mod_time: String,
+    /// Revision, eg. `1.1`
+    pub revision: String,
+    /// HomeURL
+    pub home_url: String,
+}
+
+/// `[Device]` section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdsDevice {
+    /// VendCode
+    pub vendor_id: u16,
+    /// VendName
+    pub vendor_name: String,
+    /// ProdType
+    pub device_type: u16,
+    /// ProdTypeStr
+    pub device_type_name: String,
+    /// ProdCode
+    pub product_code: u16,
+    /// MajRev and MinRev
+    pub revision: Revision,
+    /// ProdName
+    pub product_name: String,
+    /// Catalog
+    pub catalog: String,
+}
+
+impl Default for EdsDevice {
+    fn default() -> Self {
+        Self {
+            vendor_id: 0,
+            vendor_name: String::new(),
+            device_type: 0,
+            device_type_name: String::new(),
+            product_code: 0,
+            revision: Revision { major: 0, minor: 0 },
+            product_name: String::new(),
+            catalog: String::new(),
+        }
+    }
+}
+
+/// `ParamN` entry of `[Params]` section
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdsParam {
+    /// N of `ParamN`, the instance of Parameter Object
+    pub number: u16,
+    /// link path
+    pub link_path: EdsPath,
+    /// descriptor
+    pub descriptor: ParameterDescriptor,
+    /// CIP data type code, eg. 0xC3 for INT
+    pub data_type: u8,
+    /// data size in bytes
+    pub data_size: u8,
+    /// name
+    pub name: String,
+    /// units
+    pub units: String,
+    /// help text
+    pub help: String,
+    /// min value
+    pub min: Option<f64>,
+    /// max value
+    pub max: Option<f64>,
+    /// default value
+    pub default: Option<f64>,
+    /// scaling, defaults if not specified
+    pub scaling: Scaling,
+    /// `EnumN` entry, values and names
+    pub enums: Vec<(i64, String)>,
+}
+
+impl EdsParam {
+    /// elementary type of the parameter
+    #[inline]
+    pub fn field_type(&self) -> Option<ElementaryType> {
+        ElementaryType::from_code(self.data_type.into())
+    }
+
+    /// parameter metadata, without value
+    pub fn to_parameter(&self) -> Parameter {
+        let ty = self.field_type();
+        let value = |v: Option<f64>| FieldValue::from_f64(ty?, v?);
+        Parameter {
+            instance: self.number,
+            value: None,
+            descriptor: self.descriptor,
+            data_type: self.data_type,
+            data_size: self.data_size,
+            name: self.name.clone(),
+            units: self.units.clone(),
+            help: self.help.clone(),
+            min: value(self.min),
+            max: value(self.max),
+            default: value(self.default),
+            scaling: self.scaling,
+        }
+    }
+
+    /// name of the enumerated value
+    #[inline]
+    pub fn enum_name(&self, value: i64) -> Option<&str> {
+        self.enums
+            .iter()
+            .find(|(v, _)| *v == value)
+            .map(|(_, name)| name.as_str())
+    }
+}
+
+/// member of assembly
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EdsMember {
+    /// size in bits, `None` for the size of the reference
+    pub size: Option<u32>,
+    /// `ParamN`, `AssemN`, or empty for padding
+    pub reference: EdsValue,
+}
+
+/// `AssemN` entry of `[Assembly]` section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdsAssembly {
+    /// N of `AssemN`
+    pub number: u16,
+    /// name
+    pub name: String,
+    /// path
+    pub path: EdsPath,
+    /// size in bytes
+    pub size: Option<u32>,
+    /// descriptor
+    pub descriptor: u16,
+    /// members
+    pub members: Vec<EdsMember>,
+}
+
+/// `ConnectionN` entry of `[Connection Manager]` section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdsConnection {
+    /// N of `ConnectionN`
+    pub number: u16,
+    /// trigger and transport bits
+    pub trigger_transport: u32,
+    /// connection parameters bits
+    pub parameters: u32,
+    /// O->T RPI in microseconds
+    pub o_t_rpi: EdsValue,
+    /// O->T size in bytes
+    pub o_t_size: EdsValue,
+    /// O->T format
+    pub o_t_format: EdsValue,
+    /// T->O RPI in microseconds
+    pub t_o_rpi: EdsValue,
+    /// T->O size in bytes
+    pub t_o_size: EdsValue,
+    /// T->O format
+    pub t_o_format: EdsValue,
+    /// proxy config size in bytes
+    pub proxy_config_size: EdsValue,
+    /// proxy config format
+    pub proxy_config_format: EdsValue,
+    /// target config size in bytes
+    pub target_config_size: EdsValue,
+    /// target config format
+    pub target_config_format: EdsValue,
+    /// name, eg. `Exclusive Owner`
+    pub name: String,
+    /// help text
+    pub help: String,
+    /// application path
+    pub path: EdsPath,
+}
+
+impl EdsConnection {
+    /// lowest supported transport class
+    #[inline]
+    pub fn transport_class(&self) -> Option<TransportClass> {
+        let res = match (0..7).find(|i| self.trigger_transport & (1 << i) != 0)? {
+            0 => TransportClass::Class0,
+            1 => TransportClass::Class1,
+            2 => TransportClass::Class2,
+            3 => TransportClass::Class3,
+            4 => TransportClass::Class4,
+            5 => TransportClass::Class5,
+            _ => TransportClass::Class6,
+        };
+        Some(res)
+    }
+
+    /// supported trigger, cyclic preferred
+    #[inline]
+    pub fn transport_trigger(&self) -> TriggerType {
+        if self.trigger_transport & (1 << 16) != 0 {
+            TriggerType::Cyclic
+        } else if self.trigger_transport & (1 << 17) != 0 {
+            TriggerType::ChangeOfState
+        } else if self.trigger_transport & (1 << 18) != 0 {
+            TriggerType::Application
+        } else {
+            TriggerType::default()
+        }
+    }
+
+    /// transport direction
+    #[inline]
+    pub fn transport_direction(&self) -> Direction {
+        if self.trigger_transport & (1 << 31) != 0 {
+            Direction::Server
+        } else {
+            Direction::Client
+        }
+    }
+
+    /// listen only connection?
+    #[inline]
+    pub fn listen_only(&self) -> bool {
+        self.trigger_transport & (1 << 24) != 0
+    }
+
+    /// input only connection?
+    #[inline]
+    pub fn input_only(&self) -> bool {
+        self.trigger_transport & (1 << 25) != 0
+    }
+
+    /// exclusive owner connection?
+    #[inline]
+    pub fn exclusive_owner(&self) -> bool {
+        self.trigger_transport & (1 << 26) != 0
+    }
+
+    /// redundant owner connection?
+    #[inline]
+    pub fn redundant_owner(&self) -> bool {
+        self.trigger_transport & (1 << 27) != 0
+    }
+
+    /// O->T real time format
+    #[inline]
+    pub fn o_t_real_time_format(&self) -> Option<ReadlTimeFormat> {
+        real_time_format(self.parameters >> 8)
+    }
+
+    /// T->O real time format
+    #[inline]
+    pub fn t_o_real_time_format(&self) -> Option<ReadlTimeFormat> {
+        real_time_format(self.parameters >> 12)
+    }
+
+    /// connection parameters of one direction, from bits at the shift
+    fn connection_parameters(&self, shift: u32, connection_size: u16) -> ConnectionParameters {
+        let bits = self.parameters;
+        let variable_length = if bits & (1 << shift) == 0 && bits & (1 << (shift + 1)) != 0 {
+            VariableLength::Variable
+        } else {
+            VariableLength::Fixed
+        };
+        let kind = bits >> (16 + shift * 2);
+        let connection_type = if kind & 0b100 != 0 {
+            ConnectionType::P2P
+        } else if kind & 0b010 != 0 {
+            ConnectionType::Multicast
+        } else if kind & 0b001 != 0 {
+            ConnectionType::Null
+        } else {
+            ConnectionType::P2P
+        };
+        let priority = bits >> (24 + shift * 2);
+        let priority = if priority & 0b100 != 0 {
+            Priority::Scheduled
+        } else if priority & 0b010 != 0 {
+            Priority::High
+        } else if priority & 0b001 != 0 {
+            Priority::Low
+        } else {
+            Priority::default()
+        };
+        ConnectionParameters {
+            redundant_owner: self.redundant_owner(),
+            connection_type,
+            variable_length,
+            priority,
+            connection_size,
+        }
+    }
+}
+
+/// parsed EDS file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Eds {
+    /// `[File]` section
+    pub file: EdsFile,
+    /// `[Device]` section
+    pub device: EdsDevice,
+    /// `[Device Classification]` section, eg. `EtherNetIP`
+    pub classifications: Vec<String>,
+    /// `[Params]` section
+    pub params: Vec<EdsParam>,
+    /// `[Assembly]` section
+    pub assemblies: Vec<EdsAssembly>,
+    /// `[Connection Manager]` section
+    pub connections: Vec<EdsConnection>,
+    /// all sections
+    pub sections: Vec<EdsSection>,
+}
+
+impl Eds {
+    /// parse EDS file content
+    pub fn parse<E: Error>(text: &str) -> Result<Self, E> {
+        let sections = parse_sections::<E>(text)?;
+        let mut eds = Self {
+            file: Default::default(),
+            device: Default::default(),
+            classifications: Vec::new(),
+            params: Vec::new(),
+            assemblies: Vec::new(),
+            connections: Vec::new(),
+            sections: Vec::new(),
+        };
+        for section in sections.iter() {
+            match section.name.to_ascii_lowercase().as_str() {
+                "file" => eds.file = parse_file(section),
+                "device" => eds.device = parse_device::<E>(section)?,
+                "device classification" => {
+                    eds.classifications = section
+                        .entries
+                        .iter()
+                        .filter(|v| keyword_number(&v.keyword, "Class").is_some())
+                        .map(|v| v.field(0).to_owned())
+                        .collect()
+                }
+                "params" => eds.params = parse_params::<E>(section)?,
+                "assembly" => {
+                    for entry in section.entries.iter() {
+                        if let Some(number) = keyword_number(&entry.keyword, "Assem") {
+                            eds.assemblies.push(parse_assembly::<E>(number, entry)?);
+                        }
+                    }
+                }
+                "connection manager" => {
+                    for entry in section.entries.iter() {
+                        if let Some(number) = keyword_number(&entry.keyword, "Connection") {
+                            eds.connections.push(parse_connection::<E>(number, entry)?);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        eds.sections = sections;
+        Ok(eds)
+    }
+
+    /// section of the name, case insensitive
+    #[inline]
+    pub fn section(&self, name: &str) -> Option<&EdsSection> {
+        self.sections
+            .iter()
+            .find(|v| v.name.eq_ignore_ascii_case(name))
+    }
+
+    /// `ParamN`
+    #[inline]
+    pub fn param(&self, number: u16) -> Option<&EdsParam> {
+        self.params.iter().find(|v| v.number == number)
+    }
+
+    /// `AssemN`
+    #[inline]
+    pub fn assembly(&self, number: u16) -> Option<&EdsAssembly> {
+        self.assemblies.iter().find(|v| v.number == number)
+    }
+
+    /// `ConnectionN`
+    #[inline]
+    pub fn connection(&self, number: u16) -> Option<&EdsConnection> {
+        self.connections.iter().find(|v| v.number == number)
+    }
+
+    /// resolve the value; default value of parameter, or size of assembly
+    pub fn value(&self, value: &EdsValue) -> Option<u32> {
+        match *value {
+            EdsValue::Empty => None,
+            EdsValue::Number(v) => Some(v),
+            EdsValue::Param(n) => {
+                let v = self.param(n)?.default?;
+                if v >= 0.0 && v <= u32::MAX as f64 {
+                    Some(v as u32)
+                } else {
+                    None
+                }
+            }
+            EdsValue::Assembly(n) => self.assembly(n)?.size,
+        }
+    }
+
+    /// encode the path, parameters replaced by their default values
+    pub fn encode_path(&self, path: &EdsPath) -> Option<Bytes> {
+        let mut buf = Vec::new();
+        for item in path.0.iter() {
+            match *item {
+                PathItem::Byte(v) => buf.push(v),
+                PathItem::Param(n) => {
+                    let param = self.param(n)?;
+                    let v = param.default? as i64;
+                    let size = (param.data_size as usize).clamp(1, 8);
+                    buf.extend_from_slice(&v.to_le_bytes()[..size]);
+                }
+            }
+        }
+        Some(buf.into())
+    }
+
+    /// layout of the assembly, by the members referring to parameters of elementary types;
+    /// `None` if the assembly or size of any member is unknown
+    pub fn assembly_layout(&self, number: u16) -> Option<AssemblyLayout> {
+        self.layout_members(number, AssemblyLayout::new(), 0, 0)
+            .map(|(layout, _)| layout)
+    }
+
+    /// lay out members from the bit offset, returns the layout and the end bit offset
+    fn layout_members(
+        &self,
+        number: u16,
+        mut layout: AssemblyLayout,
+        mut offset: usize,
+        depth: usize,
+    ) -> Option<(AssemblyLayout, usize)> {
+        if depth > MAX_ASSEMBLY_DEPTH {
+            return None;
+        }
+        let assembly = self.assembly(number)?;
+        for member in assembly.members.iter() {
+            let bits = match (member.size, member.reference) {
+                (Some(v), _) => v as usize,
+                (None, EdsValue::Param(n)) => self.param(n)?.data_size as usize * 8,
+                (None, EdsValue::Assembly(n)) => self.assembly(n)?.size? as usize * 8,
+                _ => return None,
+            };
+            match member.reference {
+                EdsValue::Param(n) => {
+                    let param = self.param(n)?;
+                    let (byte, bit) = (offset / 8, offset % 8);
+                    match param.field_type() {
+                        Some(_) if bits == 1 => {
+                            layout = layout.bit(param.name.clone(), byte, bit as u16)
+                        }
+                        Some(ty) if bit == 0 && ty.size() * 8 == bits => {
+                            layout = layout.field(param.name.clone(), byte, ty)
+                        }
+                        _ => {}
+                    }
+                }
+                EdsValue::Assembly(n) => {
+                    let (res, _) = self.layout_members(n, layout, offset, depth + 1)?;
+                    layout = res;
+                }
+                _ => {}
+            }
+            offset += bits;
+        }
+        Some((layout, offset))
+    }
+
+    /// forward open options of `ConnectionN`, with the application path of the connection;
+    /// connection sizes include the sequence count and the 32-bit real time header
+    pub fn open_options(&self, number: u16) -> Option<OpenOptions<Bytes>> {
+        let conn = self.connection(number)?;
+        let transport_class = conn.transport_class()?;
+        let path = self.encode_path(&conn.path)?;
+        let data_size = |size: &EdsValue, format: &EdsValue| match size {
+            EdsValue::Empty => self.value(format),
+            _ => self.value(size),
+        };
+        let o_t_size = frame_size(
+            transport_class,
+            conn.o_t_real_time_format(),
+            data_size(&conn.o_t_size, &conn.o_t_format).unwrap_or_default(),
+        )?;
+        let t_o_size = frame_size(
+            transport_class,
+            conn.t_o_real_time_format(),
+            data_size(&conn.t_o_size, &conn.t_o_format).unwrap_or_default(),
+        )?;
+        let mut options = OpenOptions::default()
+            .with_connection_path(path)
+            .transport_class(transport_class)
+            .transport_trigger(conn.transport_trigger())
+            .transport_direction(conn.transport_direction())
+            .large_open(o_t_size > 511 || t_o_size > 511);
+        if let Some(rpi) = self.value(&conn.o_t_rpi) {
+            options = options.o_t_rpi(rpi);
+        }
+        if let Some(rpi) = self.value(&conn.t_o_rpi) {
+            options = options.t_o_rpi(rpi);
+        }
+        options.o_t_params = conn.connection_parameters(0, o_t_size);
+        options.t_o_params = conn.connection_parameters(2, t_o_size);
+        Some(options)
+    }
+}
+
+/// size of message frame, including the sequence count and the real time header
+#[inline]
+fn frame_size(
+    transport_class: TransportClass,
+    format: Option<ReadlTimeFormat>,
+    data_size: u32,
+) -> Option<u16> {
+    let header = match format {
+        Some(ReadlTimeFormat::Header32Bit) => 4,
+        _ => 0,
+    };
+    let sequence = match transport_class {
+        TransportClass::Class0 => 0,
+        _ => 2,
+    };
+    u16::try_from(data_size + header + sequence).ok()
+}
+
+#[inline]
+fn real_time_format(bits: u32) -> Option<ReadlTimeFormat> {
+    match bits & 0x0F {
+        0 => Some(ReadlTimeFormat::Modeless),
+        1 => Some(ReadlTimeFormat::ZeroLength),
+        3 => Some(ReadlTimeFormat::Heartbeat),
+        4 => Some(ReadlTimeFormat::Header32Bit),
+        _ => None,
+    }
+}
+
+fn parse_file(section: &EdsSection) -> EdsFile {
+    EdsFile {
+        description: section.text("DescText"),
+        create_date: section.text("CreateDate"),
+        create_time: section.text("CreateTime"),
+        mod_date: section.text("ModDate"),
+        mod_time: section.text("ModTime"),
+        revision: section.text("Revision"),
+        home_url: section.text("HomeURL"),
+    }
+}
+
+fn parse_device<E: Error>(section: &EdsSection) -> Result<EdsDevice, E> {
+    fn number<T: TryFrom<i64> + Default, E: Error>(
+        section: &EdsSection,
+        keyword: &str,
+    ) -> Result<T, E> {
+        match section.entry(keyword) {
+            Some(entry) => Ok(int::<T, E>(entry, 0)?.unwrap_or_default()),
+            None => Ok(T::default()),
+        }
+    }
+    Ok(EdsDevice {
+        vendor_id: number::<_, E>(section, "VendCode")?,
+        vendor_name: section.text("VendName"),
+        device_type: number::<_, E>(section, "ProdType")?,
+        device_type_name: section.text("ProdTypeStr"),
+        product_code: number::<_, E>(section, "ProdCode")?,
+        revision: Revision {
+            major: number::<_, E>(section, "MajRev")?,
+            minor: number::<_, E>(section, "MinRev")?,
+        },
+        product_name: section.text("ProdName"),
+        catalog: section.text("Catalog"),
+    })
+}
+
+fn parse_params<E: Error>(section: &EdsSection) -> Result<Vec<EdsParam>, E> {
+    let mut params = Vec::new();
+    for entry in section.entries.iter() {
+        if let Some(number) = keyword_number(&entry.keyword, "Param") {
+            params.push(parse_param::<E>(number, entry)?);
+        }
+    }
+    for entry in section.entries.iter() {
+        let number = match keyword_number(&entry.keyword, "Enum") {
+            Some(v) => v,
+            None => continue,
+        };
+        let mut enums = Vec::new();
+        for i in (0..entry.fields.len()).step_by(2) {
+            let value = int::<i64, E>(entry, i)?.ok_or_else(|| invalid::<E>(entry, i))?;
+            enums.push((value, entry.field(i + 1).to_owned()));
+        }
+        if let Some(param) = params.iter_mut().find(|v| v.number == number) {
+            param.enums = enums;
+        }
+    }
+    Ok(params)
+}
+
+fn parse_param<E: Error>(number: u16, entry: &EdsEntry) -> Result<EdsParam, E> {
+    let defaults = Scaling::default();
+    Ok(EdsParam {
+        number,
+        link_path: path::<E>(entry, 2)?,
+        descriptor: ParameterDescriptor(int::<_, E>(entry, 3)?.unwrap_or_default()),
+        data_type: int::<_, E>(entry, 4)?.unwrap_or_default(),
+        data_size: int::<_, E>(entry, 5)?.unwrap_or_default(),
+        name: entry.field(6).to_owned(),
+        units: entry.field(7).to_owned(),
+        help: entry.field(8).to_owned(),
+        min: float::<E>(entry, 9)?,
+        max: float::<E>(entry, 10)?,
+        default: float::<E>(entry, 11)?,
+        scaling: Scaling {
+            multiplier: int::<_, E>(entry, 12)?.unwrap_or(defaults.multiplier),
+            divisor: int::<_, E>(entry, 13)?.unwrap_or(defaults.divisor),
+            base: int::<_, E>(entry, 14)?.unwrap_or(defaults.base),
+            offset: int::<_, E>(entry, 15)?.unwrap_or(defaults.offset),
+            precision: int::<_, E>(entry, 20)?.unwrap_or(defaults.precision),
+        },
+        enums: Vec::new(),
+    })
+}
+
+fn parse_assembly<E: Error>(number: u16, entry: &EdsEntry) -> Result<EdsAssembly, E> {
+    let mut members = Vec::new();
+    for i in (6..entry.fields.len()).step_by(2) {
+        let size = int::<u32, E>(entry, i)?;
+        let reference = value::<E>(entry, i + 1)?;
+        if size.is_some() || reference != EdsValue::Empty {
+            members.push(EdsMember { size, reference });
+        }
+    }
+    Ok(EdsAssembly {
+        number,
+        name: entry.field(0).to_owned(),
+        path: path::<E>(entry, 1)?,
+        size: int::<_, E>(entry, 2)?,
+        descriptor: int::<_, E>(entry, 3)?.unwrap_or_default(),
+        members,
+    })
+}
+
+fn parse_connection<E: Error>(number: u16, entry: &EdsEntry) -> Result<EdsConnection, E> {
+    Ok(EdsConnection {
+        number,
+        trigger_transport: int::<_, E>(entry, 0)?.unwrap_or_default(),
+        parameters: int::<_, E>(entry, 1)?.unwrap_or_default(),
+        o_t_rpi: value::<E>(entry, 2)?,
+        o_t_size: value::<E>(entry, 3)?,
+        o_t_format: value::<E>(entry, 4)?,
+        t_o_rpi: value::<E>(entry, 5)?,
+        t_o_size: value::<E>(entry, 6)?,
+        t_o_format: value::<E>(entry, 7)?,
+        proxy_config_size: value::<E>(entry, 8)?,
+        proxy_config_format: value::<E>(entry, 9)?,
+        target_config_size: value::<E>(entry, 10)?,
+        target_config_format: value::<E>(entry, 11)?,
+        name: entry.field(12).to_owned(),
+        help: entry.field(13).to_owned(),
+        path: path::<E>(entry, 14)?,
+    })
+}
+
+/// N of keyword `{prefix}N`, prefix case insensitive
+#[inline]
+fn keyword_number(keyword: &str, prefix: &str) -> Option<u16> {
+    let head = keyword.get(..prefix.len())?;
+    if !head.eq_ignore_ascii_case(prefix) {
+        return None;
+    }
+    let rest = &keyword[prefix.len()..];
+    if rest.is_empty() || !rest.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    rest.parse().ok()
+}
+
+#[inline]
+fn parse_int(s: &str) -> Option<i64> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => i64::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+#[inline]
+fn invalid<E: Error>(entry: &EdsEntry, index: usize) -> E {
+    cip_error(format_args!(
+        "eds - line {}: invalid field {} of {}: {}",
+        entry.line,
+        index + 1,
+        entry.keyword,
+        entry.field(index)
+    ))
+}
+
+/// integer field, `None` if empty
+fn int<T: TryFrom<i64>, E: Error>(entry: &EdsEntry, index: usize) -> Result<Option<T>, E> {
+    let s = entry.field(index);
+    if s.is_empty() {
+        return Ok(None);
+    }
+    parse_int(s)
+        .and_then(|v| T::try_from(v).ok())
+        .map(Some)
+        .ok_or_else(|| invalid(entry, index))
+}
+
+/// numeric field, `None` if empty
+fn float<E: Error>(entry: &EdsEntry, index: usize) -> Result<Option<f64>, E> {
+    let s = entry.field(index);
+    if s.is_empty() {
+        return Ok(None);
+    }
+    parse_int(s)
+        .map(|v| v as f64)
+        .or_else(|| s.parse().ok())
+        .map(Some)
+        .ok_or_else(|| invalid(entry, index))
+}
+
+fn value<E: Error>(entry: &EdsEntry, index: usize) -> Result<EdsValue, E> {
+    let s = entry.field(index);
+    if let Some(n) = keyword_number(s, "Param") {
+        Ok(EdsValue::Param(n))
+    } else if let Some(n) = keyword_number(s, "Assem") {
+        Ok(EdsValue::Assembly(n))
+    } else {
+        Ok(int::<u32, E>(entry, index)?
+            .map(EdsValue::Number)
+            .unwrap_or(EdsValue::Empty))
+    }
+}
+
+/// path of hex bytes and parameter references, bytes may not be separated
+fn path<E: Error>(entry: &EdsEntry, index: usize) -> Result<EdsPath, E> {
+    let mut items = Vec::new();
+    for token in entry.field(index).split_whitespace() {
+        let token = token.trim_start_matches('[').trim_end_matches(']');
+        if let Some(n) = keyword_number(token, "Param") {
+            items.push(PathItem::Param(n));
+            continue;
+        }
+        if token.len() % 2 == 1 || !token.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid(entry, index));
+        }
+        for i in (0..token.len()).step_by(2) {
+            let v =
+                u8::from_str_radix(&token[i..i + 2], 16).map_err(|_| invalid::<E>(entry, index))?;
+            items.push(PathItem::Byte(v));
+        }
+    }
+    Ok(EdsPath(items))
+}
+
+#[inline]
+fn syntax_error<E: Error>(line: usize, msg: &str) -> E {
+    cip_error(format_args!("eds - line {}: {}", line, msg))
+}
+
+/// split text into sections and entries
+fn parse_sections<E: Error>(text: &str) -> Result<Vec<EdsSection>, E> {
+    let mut sections: Vec<EdsSection> = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+    loop {
+        skip_blank(&mut chars, &mut line);
+        match chars.next() {
+            None => break,
+            Some('[') => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some('\n') | None => {
+                            return Err(syntax_error(line, "unterminated section name"))
+                        }
+                        Some(c) => name.push(c),
+                    }
+                }
+                sections.push(EdsSection {
+                    name: name.trim().to_owned(),
+                    entries: Vec::new(),
+                });
+            }
+            Some(c) => {
+                let start = line;
+                let mut keyword = String::from(c);
+                loop {
+                    match chars.next() {
+                        Some('=') => break,
+                        Some('\n') | Some(';') | None => {
+                            return Err(syntax_error(start, "expected ="))
+                        }
+                        Some(c) => keyword.push(c),
+                    }
+                }
+                let fields = parse_fields::<E>(&mut chars, &mut line, start)?;
+                let section = sections
+                    .last_mut()
+                    .ok_or_else(|| syntax_error::<E>(start, "entry outside of section"))?;
+                section.entries.push(EdsEntry {
+                    keyword: keyword.trim().to_owned(),
+                    fields,
+                    line: start,
+                });
+            }
+        }
+    }
+    Ok(sections)
+}
+
+/// fields of an entry until `;`, adjacent strings concatenated
+fn parse_fields<E: Error>(
+    chars: &mut Peekable<Chars>,
+    line: &mut usize,
+    start: usize,
+) -> Result<Vec<String>, E> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut space = false;
+    loop {
+        match chars.next() {
+            None => return Err(syntax_error(start, "missing ;")),
+            Some(';') => {
+                fields.push(field);
+                return Ok(fields);
+            }
+            Some(',') => {
+                fields.push(mem::take(&mut field));
+                space = false;
+            }
+            Some('$') => skip_comment(chars),
+            Some('"') => {
+                read_string::<E>(chars, line, &mut field)?;
+                space = false;
+            }
+            Some(c) if c.is_whitespace() => {
+                if c == '\n' {
+                    *line += 1;
+                }
+                space = true;
+            }
+            Some(c) => {
+                if space && !field.is_empty() {
+                    field.push(' ');
+                }
+                field.push(c);
+                space = false;
+            }
+        }
+    }
+}
+
+/// quoted string, after the opening quote
+fn read_string<E: Error>(
+    chars: &mut Peekable<Chars>,
+    line: &mut usize,
+    buf: &mut String,
+) -> Result<(), E> {
+    loop {
+        match chars.next() {
+            None => return Err(syntax_error(*line, "unterminated string")),
+            Some('"') => return Ok(()),
+            Some('\\') => match chars.next() {
+                Some('n') => buf.push('\n'),
+                Some('t') => buf.push('\t'),
+                Some(c) => buf.push(c),
+                None => return Err(syntax_error(*line, "unterminated string")),
+            },
+            Some(c) => {
+                if c == '\n' {
+                    *line += 1;
+                }
+                buf.push(c);
+            }
+        }
+    }
+}
+
+#[inline]
+fn skip_comment(chars: &mut Peekable<Chars>) {
+    while let Some(c) = chars.peek() {
+        if *c == '\n' {
+            break;
+        }
+        chars.next();
+    }
+}
+
+fn skip_blank(chars: &mut Peekable<Chars>, line: &mut usize) {
+    while let Some(c) = chars.peek() {
+        match *c {
+            '$' => skip_comment(chars),
+            '\n' => {
+                *line += 1;
+                chars.next();
+            }
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            _ => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rseip_core::tests::CodecError;
+
+    const EDS: &str = r#"$ demo EDS
+[File]
+        DescText = "Demo Adapter"
+                   " EDS";
+        CreateDate = 04-14-2021;
+        Revision = 1.1;
+
+[Device]
+        VendCode = 0x01;        $ vendor
+        VendName = "Vendor";
+        ProdType = 12;
+        ProdCode = 100;
+        MajRev = 2;
+        MinRev = 3;
+        ProdName = "Demo Adapter";
+
+[Device Classification]
+        Class1 = EtherNetIP;
+
+[Params]
+        Param1 =
+                0,                      $ reserved
+                ,,                      $ link path size, link path
+                0x0000,                 $ descriptor
+                0xC7,                   $ UINT
+                2,                      $ data size
+                "RPI",                  $ name
+                "us",                   $ units
+                "",                     $ help
+                1000,1000000,10000,     $ min, max, default
+                ,,,,                    $ scaling
+                ,,,,                    $ links
+                ;                       $ decimal places
+        Param2 = 0,,,0x0014,0xC1,1,"Running","","",0,1,0,,,,,,,,,;
+        Param3 = 0,,,0,0xC3,2,"Speed","rpm","",-3000,3000,0,1,10,1,0,,,,,1;
+        Param4 = 0,,,0,0xC6,1,"Instance","","",0,255,150,,,,,,,,,;
+        Enum2 = 0,"Stopped",1,"Running";
+
+[Assembly]
+        Assem100 = "Input","20 04 24 64 30 03",4,0x0000,,,
+                1,Param2,
+                15,,
+                16,Param3;
+        Assem150 = "Output","",2,,,,16,Param3;
+
+[Connection Manager]
+        Connection1 =
+                0x04010002,             $ class 1, cyclic, exclusive owner
+                0x44640405,             $ O->T header, P2P, T->O multicast or P2P
+                Param1,2,Assem150,      $ O->T RPI, size, format
+                Param1,,Assem100,       $ T->O RPI, size, format
+                ,,,,
+                "Exclusive Owner","",
+                "20 04 24 [Param4] 2C 96 2C64";
+"#;
+
+    #[test]
+    fn test_parse_eds() {
+        let eds = Eds::parse::<CodecError>(EDS).unwrap();
+        assert_eq!(eds.file.description, "Demo Adapter EDS");
+        assert_eq!(eds.file.revision, "1.1");
+        assert_eq!(eds.device.vendor_id, 1);
+        assert_eq!(eds.device.product_code, 100);
+        assert_eq!(eds.device.revision, Revision { major: 2, minor: 3 });
+        assert_eq!(eds.classifications, vec!["EtherNetIP".to_owned()]);
+        assert_eq!(eds.section("device").unwrap().entries.len(), 7);
+
+        let param = eds.param(1).unwrap();
+        assert_eq!(param.name, "RPI");
+        assert_eq!(param.data_type, 0xC7);
+        assert_eq!(param.default, Some(10000.0));
+        assert_eq!(eds.param(2).unwrap().enum_name(1), Some("Running"));
+        let param = eds.param(3).unwrap().to_parameter();
+        assert_eq!(param.min, Some(FieldValue::Int(-3000)));
+        assert_eq!(param.scaling.divisor, 10);
+        assert_eq!(param.scaling.precision, 1);
+
+        let layout = eds.assembly_layout(100).unwrap();
+        let fields = layout.fields();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(
+            (fields[0].name.as_str(), fields[0].bit),
+            ("Running", Some(0))
+        );
+        assert_eq!((fields[1].name.as_str(), fields[1].offset), ("Speed", 2));
+
+        let conn = eds.connection(1).unwrap();
+        assert!(conn.exclusive_owner());
+        assert_eq!(
+            conn.o_t_real_time_format(),
+            Some(ReadlTimeFormat::Header32Bit)
+        );
+        let options = eds.open_options(1).unwrap();
+        assert_eq!(
+            &options.connection_path[..],
+            &[0x20, 0x04, 0x24, 0x96, 0x2C, 0x96, 0x2C, 0x64]
+        );
+        assert_eq!(options.transport_class, TransportClass::Class1);
+        assert_eq!(options.transport_direction, Direction::Client);
+        assert_eq!(options.o_t_rpi, 10000);
+        assert_eq!(options.o_t_params.connection_size, 8);
+        assert_eq!(options.o_t_params.priority, Priority::Scheduled);
+        assert_eq!(options.t_o_params.connection_size, 6);
+        assert_eq!(options.t_o_params.connection_type, ConnectionType::P2P);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let res = Eds::parse::<CodecError>("[File]\nDescText = \"demo\"\n");
+        assert!(res.unwrap_err().to_string().contains("line 2: missing ;"));
+        let res = Eds::parse::<CodecError>("[Device]\nVendCode = abc;\n");
+        assert!(res.is_err());
+        assert!(Eds::parse::<CodecError>("VendCode = 1;").is_err());
+    }
+}
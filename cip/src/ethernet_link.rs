@@ -0,0 +1,326 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+//! Ethernet Link Object (class 0xF6), one instance per port starting from 1
+//!
+//! ```rust,ignore
+//! let link = client.ethernet_link(1).await?;
+//! println!("{} {} Mbps, full duplex: {}", link.mac, link.speed, link.flags.full_duplex());
+//! let counters = client.interface_counters(1, false).await?;
+//! ```
+
+use crate::{epath::EPath, service::*};
+use bytes::Buf;
+use core::fmt;
+use rseip_core::codec::{Decode, Decoder};
+
+/// Ethernet Link Object
+pub const CLASS_ETHERNET_LINK: u16 = 0xF6;
+
+const ATTR_INTERFACE_SPEED: u16 = 1;
+const ATTR_INTERFACE_FLAGS: u16 = 2;
+const ATTR_PHYSICAL_ADDRESS: u16 = 3;
+const ATTR_INTERFACE_COUNTERS: u16 = 4;
+const ATTR_MEDIA_COUNTERS: u16 = 5;
+
+/// Get_and_Clear service
+const SERVICE_GET_AND_CLEAR: u8 = 0x4C;
+
+/// MAC address
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MacAddress(pub [u8; 6]);
+
+impl fmt::Display for MacAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(
+            f,
+            "{:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}",
+            a, b, c, d, e, g
+        )
+    }
+}
+
+/// auto-negotiation status, bits 2-4 of interface flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NegotiationStatus {
+    /// 0: auto-negotiation in progress
+    InProgress,
+    /// 1: auto-negotiation and speed detection failed
+    Failed,
+    /// 2: auto-negotiation failed, speed detected
+    DuplexFailed,
+    /// 3: speed and duplex negotiated
+    Success,
+    /// 4: auto-negotiation not attempted, forced speed and duplex
+    NotAttempted,
+    /// other values, reserved
+    Other(u8),
+}
+
+/// interface flags of Ethernet Link Object, attribute 2
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InterfaceFlags(pub u32);
+
+impl InterfaceFlags {
+    /// bit 0: link is active
+    #[inline]
+    pub fn link_active(&self) -> bool {
+        self.0 & 0x01 != 0
+    }
+
+    /// bit 1: full duplex, otherwise half duplex
+    #[inline]
+    pub fn full_duplex(&self) -> bool {
+        self.0 & 0x02 != 0
+    }
+
+    /// bits 2-4: auto-negotiation status
+    #[inline]
+    pub fn negotiation_status(&self) -> NegotiationStatus {
+        match (self.0 >> 2) & 0x07 {
+            0 => NegotiationStatus::InProgress,
+            1 => NegotiationStatus::Failed,
+            2 => NegotiationStatus::DuplexFailed,
+            3 => NegotiationStatus::Success,
+            4 => NegotiationStatus::NotAttempted,
+            v => NegotiationStatus::Other(v as u8),
+        }
+    }
+
+    /// bit 5: manual setting requires reset
+    #[inline]
+    pub fn manual_setting_requires_reset(&self) -> bool {
+        self.0 & 0x20 != 0
+    }
+
+    /// bit 6: local hardware fault
+    #[inline]
+    pub fn hardware_fault(&self) -> bool {
+        self.0 & 0x40 != 0
+    }
+}
+
+/// interface counters of Ethernet Link Object, attribute 4
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InterfaceCounters {
+    /// octets received
+    pub in_octets: u32,
+    /// unicast packets received
+    pub in_ucast_packets: u32,
+    /// non-unicast packets received
+    pub in_nucast_packets: u32,
+    /// inbound packets discarded
+    pub in_discards: u32,
+    /// inbound packets with errors
+    pub in_errors: u32,
+    /// inbound packets with unknown protocol
+    pub in_unknown_protos: u32,
+    /// octets sent
+    pub out_octets: u32,
+    /// unicast packets sent
+    pub out_ucast_packets: u32,
+    /// non-unicast packets sent
+    pub out_nucast_packets: u32,
+    /// outbound packets discarded
+    pub out_discards: u32,
+    /// outbound packets with errors
+    pub out_errors: u32,
+}
+
+/// media counters of Ethernet Link Object, attribute 5
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MediaCounters {
+    /// frames not having an integral number of octets
+    pub alignment_errors: u32,
+    /// frames not passing the FCS check
+    pub fcs_errors: u32,
+    /// frames transmitted after exactly one collision
+    pub single_collisions: u32,
+    /// frames transmitted after more than one collision
+    pub multiple_collisions: u32,
+    /// SQE test errors
+    pub sqe_test_errors: u32,
+    /// frames with deferred transmission
+    pub deferred_transmissions: u32,
+    /// late collisions
+    pub late_collisions: u32,
+    /// frames failed because of excessive collisions
+    pub excessive_collisions: u32,
+    /// frames failed because of internal MAC transmit errors
+    pub mac_transmit_errors: u32,
+    /// times of carrier sense condition lost or never asserted
+    pub carrier_sense_errors: u32,
+    /// frames received exceeding the maximum frame size
+    pub frame_too_long: u32,
+    /// frames failed because of internal MAC receive errors
+    pub mac_receive_errors: u32,
+}
+
+/// link status of an Ethernet port
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EthernetLink {
+    /// interface speed in Mbps, 0 if unknown
+    pub speed: u32,
+    /// interface flags
+    pub flags: InterfaceFlags,
+    /// MAC address
+    pub mac: MacAddress,
+}
+
+/// Ethernet Link Object services
+#[async_trait::async_trait]
+pub trait EthernetLinkService: MessageService {
+    /// read interface speed in Mbps of the port
+    async fn link_speed(&mut self, instance: u16) -> Result<u32, Self::Error> {
+        let path = attribute_path(instance, ATTR_INTERFACE_SPEED);
+        send_and_extract(self, 0x0E, path, ()).await
+    }
+
+    /// read interface flags of the port, eg. link status and duplex
+    async fn link_flags(&mut self, instance: u16) -> Result<InterfaceFlags, Self::Error> {
+        let path = attribute_path(instance, ATTR_INTERFACE_FLAGS);
+        let res = send_and_extract(self, 0x0E, path, ()).await?;
+        Ok(InterfaceFlags(res))
+    }
+
+    /// read MAC address of the port
+    async fn mac_address(&mut self, instance: u16) -> Result<MacAddress, Self::Error> {
+        let path = attribute_path(instance, ATTR_PHYSICAL_ADDRESS);
+        let res = send_and_extract(self, 0x0E, path, ()).await?;
+        Ok(MacAddress(res))
+    }
+
+    /// read speed, flags and MAC address of the port
+    async fn ethernet_link(&mut self, instance: u16) -> Result<EthernetLink, Self::Error> {
+        let speed = self.link_speed(instance).await?;
+        let flags = self.link_flags(instance).await?;
+        let mac = self.mac_address(instance).await?;
+        Ok(EthernetLink { speed, flags, mac })
+    }
+
+    /// read interface counters of the port, clear the counters if `clear`
+    async fn interface_counters(
+        &mut self,
+        instance: u16,
+        clear: bool,
+    ) -> Result<InterfaceCounters, Self::Error> {
+        let path = attribute_path(instance, ATTR_INTERFACE_COUNTERS);
+        send_and_extract(self, counters_service(clear), path, ()).await
+    }
+
+    /// read media counters of the port, clear the counters if `clear`
+    async fn media_counters(
+        &mut self,
+        instance: u16,
+        clear: bool,
+    ) -> Result<MediaCounters, Self::Error> {
+        let path = attribute_path(instance, ATTR_MEDIA_COUNTERS);
+        send_and_extract(self, counters_service(clear), path, ()).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: MessageService> EthernetLinkService for T {}
+
+#[inline]
+fn attribute_path(instance: u16, attribute: u16) -> EPath {
+    EPath::default()
+        .with_class(CLASS_ETHERNET_LINK)
+        .with_instance(instance)
+        .with_attribute(attribute)
+}
+
+/// Get_Attribute_Single, or Get_and_Clear
+#[inline]
+fn counters_service(clear: bool) -> u8 {
+    if clear {
+        SERVICE_GET_AND_CLEAR
+    } else {
+        0x0E
+    }
+}
+
+macro_rules! impl_counters {
+    ($ty:ty, $size:expr, [$($field:ident),+]) => {
+        impl<'de> Decode<'de> for $ty {
+            #[inline]
+            fn decode<D>(mut decoder: D) -> Result<Self, D::Error>
+            where
+                D: Decoder<'de>,
+            {
+                decoder.ensure_size($size * 4)?;
+                Ok(Self {
+                    $($field: decoder.buf_mut().get_u32_le(),)+
+                })
+            }
+        }
+    };
+}
+
+impl_counters!(
+    InterfaceCounters,
+    11,
+    [
+        in_octets,
+        in_ucast_packets,
+        in_nucast_packets,
+        in_discards,
+        in_errors,
+        in_unknown_protos,
+        out_octets,
+        out_ucast_packets,
+        out_nucast_packets,
+        out_discards,
+        out_errors
+    ]
+);
+
+impl_counters!(
+    MediaCounters,
+    12,
+    [
+        alignment_errors,
+        fcs_errors,
+        single_collisions,
+        multiple_collisions,
+        sqe_test_errors,
+        deferred_transmissions,
+        late_collisions,
+        excessive_collisions,
+        mac_transmit_errors,
+        carrier_sense_errors,
+        frame_too_long,
+        mac_receive_errors
+    ]
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::{BufMut, BytesMut};
+    use rseip_core::{codec::LittleEndianDecoder, tests::CodecError};
+
+    #[test]
+    fn test_interface_counters() {
+        let mut buf = BytesMut::new();
+        for i in 0..11 {
+            buf.put_u32_le(i);
+        }
+        let res = InterfaceCounters::decode(LittleEndianDecoder::<CodecError>::new(buf.freeze()))
+            .unwrap();
+        assert_eq!(res.in_ucast_packets, 1);
+        assert_eq!(res.out_errors, 10);
+
+        let flags = InterfaceFlags(0x0F);
+        assert!(flags.link_active() && flags.full_duplex());
+        assert_eq!(flags.negotiation_status(), NegotiationStatus::Success);
+        assert_eq!(
+            MacAddress([0, 0x1D, 0x9C, 0xAB, 0x01, 0x02]).to_string(),
+            "00:1D:9C:AB:01:02"
+        );
+    }
+}
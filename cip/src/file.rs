@@ -0,0 +1,617 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+//! File Object (class 0x37), upload and download of files, eg. EDS files
+//!
+//! Files are transferred in chunks of the transfer size negotiated by the
+//! initiate services; the checksum of the file is validated on upload and sent
+//! on download.
+//!
+//! ```rust,ignore
+//! let info = client.save_eds("device.eds").await?;
+//! println!("{} rev {}.{}, {} bytes", info.file_name, info.revision.0, info.revision.1, info.size);
+//!
+//! let data = client
+//!     .upload_file_with_progress(0xC9, |done, total| println!("{}/{}", done, total))
+//!     .await?;
+//! ```
+
+use crate::{epath::EPath, error::cip_error, service::*, Status};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use rseip_core::codec::{Decode, Decoder, Encode, Encoder};
+use std::{fs, path::Path};
+
+/// File Object
+pub const CLASS_FILE: u16 = 0x37;
+/// instance of EDS and icon files
+pub const INSTANCE_EDS: u16 = 0xC8;
+/// instance of related EDS and icon files
+pub const INSTANCE_RELATED_EDS: u16 = 0xC9;
+
+const SERVICE_INITIATE_UPLOAD: u8 = 0x4B;
+const SERVICE_INITIATE_DOWNLOAD: u8 = 0x4C;
+const SERVICE_UPLOAD_TRANSFER: u8 = 0x4F;
+const SERVICE_DOWNLOAD_TRANSFER: u8 = 0x50;
+
+const ATTR_STATE: u16 = 1;
+const ATTR_INSTANCE_NAME: u16 = 2;
+const ATTR_FILE_NAME: u16 = 4;
+const ATTR_FILE_REVISION: u16 = 5;
+const ATTR_FILE_SIZE: u16 = 6;
+const ATTR_FILE_CHECKSUM: u16 = 7;
+const ATTR_FILE_TYPE: u16 = 10;
+const ATTR_FILE_ENCODING: u16 = 11;
+
+/// max transfer size to request
+const MAX_TRANSFER_SIZE: u8 = 255;
+
+/// state of file instance, attribute 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileState {
+    /// 0: nonexistent
+    NonExistent,
+    /// 1: file empty
+    Empty,
+    /// 2: file loaded
+    Loaded,
+    /// 3: upload initiated
+    UploadInitiated,
+    /// 4: download initiated
+    DownloadInitiated,
+    /// 5: upload in progress
+    UploadInProgress,
+    /// 6: download in progress
+    DownloadInProgress,
+    /// 7: storing to non-volatile storage
+    Storing,
+    /// other values, reserved
+    Other(u8),
+}
+
+impl From<u8> for FileState {
+    #[inline]
+    fn from(src: u8) -> Self {
+        match src {
+            0 => Self::NonExistent,
+            1 => Self::Empty,
+            2 => Self::Loaded,
+            3 => Self::UploadInitiated,
+            4 => Self::DownloadInitiated,
+            5 => Self::UploadInProgress,
+            6 => Self::DownloadInProgress,
+            7 => Self::Storing,
+            v => Self::Other(v),
+        }
+    }
+}
+
+/// encoding of file, attribute 11
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileEncoding {
+    /// 0: binary
+    Binary,
+    /// 1: compressed, ZLIB
+    Compressed,
+    /// other values, reserved or vendor specific
+    Other(u8),
+}
+
+impl From<u8> for FileEncoding {
+    #[inline]
+    fn from(src: u8) -> Self {
+        match src {
+            0 => Self::Binary,
+            1 => Self::Compressed,
+            v => Self::Other(v),
+        }
+    }
+}
+
+/// attributes of file instance
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileInfo {
+    /// instance id
+    pub instance: u16,
+    /// state
+    pub state: FileState,
+    /// instance name, eg. `EDS and Icon Files`
+    pub instance_name: String,
+    /// file name
+    pub file_name: String,
+    /// file revision, (major, minor)
+    pub revision: (u8, u8),
+    /// file size in bytes
+    pub size: u32,
+    /// checksum of the file
+    pub checksum: i16,
+    /// read only file? `None` if not supported
+    pub read_only: Option<bool>,
+    /// encoding, `None` if not supported
+    pub encoding: Option<FileEncoding>,
+}
+
+/// checksum of file data, two's complement of the 16-bit sum of all bytes
+#[inline]
+pub fn checksum(data: &[u8]) -> i16 {
+    let sum = data
+        .iter()
+        .fold(0_u16, |acc, v| acc.wrapping_add(*v as u16));
+    0_u16.wrapping_sub(sum) as i16
+}
+
+/// File Object services
+#[async_trait::async_trait]
+pub trait FileService: MessageService {
+    /// read attributes of the file instance
+    async fn file_info(&mut self, instance: u16) -> Result<FileInfo, Self::Error> {
+        let state: u8 =
+            send_and_extract(self, 0x0E, attribute_path(instance, ATTR_STATE), ()).await?;
+        let instance_name: StringI =
+            send_and_extract(self, 0x0E, attribute_path(instance, ATTR_INSTANCE_NAME), ()).await?;
+        let file_name: StringI =
+            send_and_extract(self, 0x0E, attribute_path(instance, ATTR_FILE_NAME), ()).await?;
+        let revision: (u8, u8) =
+            send_and_extract(self, 0x0E, attribute_path(instance, ATTR_FILE_REVISION), ()).await?;
+        let size: u32 =
+            send_and_extract(self, 0x0E, attribute_path(instance, ATTR_FILE_SIZE), ()).await?;
+        let checksum: i16 =
+            send_and_extract(self, 0x0E, attribute_path(instance, ATTR_FILE_CHECKSUM), ()).await?;
+        let file_type: Result<u8, Status> =
+            send_and_extract_status(self, 0x0E, attribute_path(instance, ATTR_FILE_TYPE), ())
+                .await?;
+        let encoding: Result<u8, Status> =
+            send_and_extract_status(self, 0x0E, attribute_path(instance, ATTR_FILE_ENCODING), ())
+                .await?;
+        Ok(FileInfo {
+            instance,
+            state: state.into(),
+            instance_name: instance_name.0,
+            file_name: file_name.0,
+            revision,
+            size,
+            checksum,
+            read_only: file_type.ok().map(|v| v == 1),
+            encoding: encoding.ok().map(Into::into),
+        })
+    }
+
+    /// upload file from the device
+    async fn upload_file(&mut self, instance: u16) -> Result<Bytes, Self::Error> {
+        self.upload_file_with_progress(instance, |_, _| {}).await
+    }
+
+    /// upload file from the device, `progress` is called with transferred bytes and file size
+    async fn upload_file_with_progress<F>(
+        &mut self,
+        instance: u16,
+        mut progress: F,
+    ) -> Result<Bytes, Self::Error>
+    where
+        F: FnMut(usize, usize) + Send,
+    {
+        let path = instance_path(instance);
+        let (file_size, _transfer_size): (u32, u8) = send_and_extract(
+            self,
+            SERVICE_INITIATE_UPLOAD,
+            path.clone(),
+            MAX_TRANSFER_SIZE,
+        )
+        .await?;
+        let file_size = file_size as usize;
+        let mut data = BytesMut::with_capacity(file_size.min(0x10000));
+        let mut transfer_number = 0_u8;
+        loop {
+            let packet: UploadPacket =
+                send_and_extract(self, SERVICE_UPLOAD_TRANSFER, path.clone(), transfer_number)
+                    .await?;
+            if packet.transfer_number != transfer_number {
+                return Err(cip_error(format_args!(
+                    "file - expected transfer number {}, got {}",
+                    transfer_number, packet.transfer_number
+                )));
+            }
+            if packet.packet_type == PacketType::Abort {
+                return Err(cip_error("file - transfer aborted by the device"));
+            }
+            if data.len() + packet.data.len() > file_size {
+                return Err(cip_error(format_args!(
+                    "file - expected {} bytes, got more",
+                    file_size
+                )));
+            }
+            data.put_slice(&packet.data);
+            progress(data.len(), file_size);
+            if let Some(expected) = packet.checksum {
+                if data.len() != file_size {
+                    return Err(cip_error(format_args!(
+                        "file - expected {} bytes, got {}",
+                        file_size,
+                        data.len()
+                    )));
+                }
+                if checksum(&data) != expected {
+                    return Err(cip_error("file - checksum mismatch"));
+                }
+                return Ok(data.freeze());
+            }
+            transfer_number = transfer_number.wrapping_add(1);
+        }
+    }
+
+    /// download file to the device
+    async fn download_file(
+        &mut self,
+        instance: u16,
+        file_name: &str,
+        revision: (u8, u8),
+        data: Bytes,
+    ) -> Result<(), Self::Error> {
+        self.download_file_with_progress(instance, file_name, revision, data, |_, _| {})
+            .await
+    }
+
+    /// download file to the device, `progress` is called with transferred bytes and file size
+    async fn download_file_with_progress<F>(
+        &mut self,
+        instance: u16,
+        file_name: &str,
+        revision: (u8, u8),
+        data: Bytes,
+        mut progress: F,
+    ) -> Result<(), Self::Error>
+    where
+        F: FnMut(usize, usize) + Send,
+    {
+        let path = instance_path(instance);
+        let request = (
+            data.len() as u32,
+            revision.0,
+            revision.1,
+            StringI(file_name.to_owned()),
+        );
+        let reply: InitiateDownloadReply =
+            send_and_extract(self, SERVICE_INITIATE_DOWNLOAD, path.clone(), request).await?;
+        let transfer_size = reply.transfer_size.max(1) as usize;
+        let total = data.len();
+        let chunks = total.max(1).div_ceil(transfer_size);
+        for (i, transfer_number) in (0..chunks).zip((0..=u8::MAX).cycle()) {
+            let start = i * transfer_size;
+            let end = (start + transfer_size).min(total);
+            let packet_type = match (i == 0, i + 1 == chunks) {
+                (true, true) => PacketType::FirstAndLast,
+                (true, false) => PacketType::First,
+                (false, false) => PacketType::Middle,
+                (false, true) => PacketType::Last,
+            };
+            let checksum = match packet_type {
+                PacketType::Last | PacketType::FirstAndLast => Some(checksum(&data)),
+                _ => None,
+            };
+            let packet = DownloadPacket {
+                transfer_number,
+                packet_type,
+                data: data.slice(start..end),
+                checksum,
+            };
+            let res: TransferNumber =
+                send_and_extract(self, SERVICE_DOWNLOAD_TRANSFER, path.clone(), packet).await?;
+            if res.0 != transfer_number {
+                return Err(cip_error(format_args!(
+                    "file - expected transfer number {}, got {}",
+                    transfer_number, res.0
+                )));
+            }
+            progress(end, total);
+        }
+        Ok(())
+    }
+
+    /// upload EDS file of the device, instance 0xC8;
+    /// data is compressed if the encoding is [`FileEncoding::Compressed`]
+    async fn read_eds(&mut self) -> Result<(FileInfo, Bytes), Self::Error> {
+        let info = self.file_info(INSTANCE_EDS).await?;
+        let data = self.upload_file(INSTANCE_EDS).await?;
+        Ok((info, data))
+    }
+
+    /// upload EDS file of the device and save to the path;
+    /// saved as is if the encoding is [`FileEncoding::Compressed`]
+    async fn save_eds<P>(&mut self, path: P) -> Result<FileInfo, Self::Error>
+    where
+        P: AsRef<Path> + Send,
+    {
+        let (info, data) = self.read_eds().await?;
+        if let Err(e) = fs::write(path, &data) {
+            return Err(cip_error(format_args!("file - failed to save EDS: {}", e)));
+        }
+        Ok(info)
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: MessageService> FileService for T {}
+
+#[inline]
+fn instance_path(instance: u16) -> EPath {
+    EPath::default()
+        .with_class(CLASS_FILE)
+        .with_instance(instance)
+}
+
+#[inline]
+fn attribute_path(instance: u16, attribute: u16) -> EPath {
+    instance_path(instance).with_attribute(attribute)
+}
+
+/// type of transfer packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PacketType {
+    First,
+    Middle,
+    Last,
+    Abort,
+    FirstAndLast,
+    Other(u8),
+}
+
+impl From<u8> for PacketType {
+    #[inline]
+    fn from(src: u8) -> Self {
+        match src {
+            0 => Self::First,
+            1 => Self::Middle,
+            2 => Self::Last,
+            3 => Self::Abort,
+            4 => Self::FirstAndLast,
+            v => Self::Other(v),
+        }
+    }
+}
+
+impl From<PacketType> for u8 {
+    #[inline]
+    fn from(src: PacketType) -> Self {
+        match src {
+            PacketType::First => 0,
+            PacketType::Middle => 1,
+            PacketType::Last => 2,
+            PacketType::Abort => 3,
+            PacketType::FirstAndLast => 4,
+            PacketType::Other(v) => v,
+        }
+    }
+}
+
+/// reply of Upload_Transfer
+#[derive(Debug)]
+struct UploadPacket {
+    transfer_number: u8,
+    packet_type: PacketType,
+    data: Bytes,
+    /// checksum of the file, in the last packet
+    checksum: Option<i16>,
+}
+
+impl<'de> Decode<'de> for UploadPacket {
+    fn decode<D>(mut decoder: D) -> Result<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        decoder.ensure_size(2)?;
+        let transfer_number = decoder.decode_u8();
+        let packet_type = PacketType::from(decoder.decode_u8());
+        let last = matches!(packet_type, PacketType::Last | PacketType::FirstAndLast);
+        let size = decoder.remaining();
+        if last {
+            decoder.ensure_size(2)?;
+        }
+        let data_size = if last { size - 2 } else { size };
+        let data = decoder.buf_mut().copy_to_bytes(data_size);
+        let checksum = if last {
+            Some(decoder.buf_mut().get_i16_le())
+        } else {
+            None
+        };
+        Ok(Self {
+            transfer_number,
+            packet_type,
+            data,
+            checksum,
+        })
+    }
+}
+
+/// request of Download_Transfer
+struct DownloadPacket {
+    transfer_number: u8,
+    packet_type: PacketType,
+    data: Bytes,
+    checksum: Option<i16>,
+}
+
+impl Encode for DownloadPacket {
+    #[inline]
+    fn encode_by_ref<A: Encoder>(
+        &self,
+        buf: &mut BytesMut,
+        _encoder: &mut A,
+    ) -> Result<(), A::Error> {
+        buf.put_u8(self.transfer_number);
+        buf.put_u8(self.packet_type.into());
+        buf.put_slice(&self.data);
+        if let Some(v) = self.checksum {
+            buf.put_i16_le(v);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn bytes_count(&self) -> usize {
+        2 + self.data.len() + if self.checksum.is_some() { 2 } else { 0 }
+    }
+}
+
+/// reply of Initiate_Download
+struct InitiateDownloadReply {
+    transfer_size: u8,
+}
+
+impl<'de> Decode<'de> for InitiateDownloadReply {
+    fn decode<D>(mut decoder: D) -> Result<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        // incremental burn: UDINT, incremental burn time: UINT
+        decoder.ensure_size(7)?;
+        decoder.buf_mut().advance(6);
+        let transfer_size = decoder.decode_u8();
+        Ok(Self { transfer_size })
+    }
+}
+
+/// reply of Download_Transfer, additional data ignored
+struct TransferNumber(u8);
+
+impl<'de> Decode<'de> for TransferNumber {
+    fn decode<D>(mut decoder: D) -> Result<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        decoder.ensure_size(1)?;
+        let res = decoder.decode_u8();
+        let remaining = decoder.remaining();
+        decoder.buf_mut().advance(remaining);
+        Ok(Self(res))
+    }
+}
+
+/// STRINGI, international string; only the first string is kept
+#[derive(Debug, Default, PartialEq, Eq)]
+struct StringI(String);
+
+impl<'de> Decode<'de> for StringI {
+    fn decode<D>(mut decoder: D) -> Result<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        decoder.ensure_size(1)?;
+        let count = decoder.decode_u8();
+        if count == 0 {
+            return Ok(Default::default());
+        }
+        // language: 3 chars, string type: USINT, charset: UINT
+        decoder.ensure_size(6)?;
+        decoder.buf_mut().advance(3);
+        let string_type = decoder.decode_u8();
+        decoder.buf_mut().advance(2);
+        let text = match string_type {
+            // SHORT_STRING
+            0xDA => {
+                decoder.ensure_size(1)?;
+                let len = decoder.decode_u8() as usize;
+                decoder.ensure_size(len)?;
+                let data = decoder.buf_mut().copy_to_bytes(len);
+                String::from_utf8_lossy(&data).into_owned()
+            }
+            // STRING
+            0xD0 => {
+                decoder.ensure_size(2)?;
+                let len = decoder.decode_u16() as usize;
+                decoder.ensure_size(len)?;
+                let data = decoder.buf_mut().copy_to_bytes(len);
+                String::from_utf8_lossy(&data).into_owned()
+            }
+            // STRING2
+            0xD5 => {
+                decoder.ensure_size(2)?;
+                let len = decoder.decode_u16() as usize;
+                decoder.ensure_size(len * 2)?;
+                let chars: Vec<u16> = (0..len).map(|_| decoder.decode_u16()).collect();
+                String::from_utf16_lossy(&chars)
+            }
+            v => {
+                return Err(rseip_core::Error::invalid_value(
+                    "one of 0xDA, 0xD0, 0xD5",
+                    v,
+                ))
+            }
+        };
+        // other languages ignored
+        let remaining = decoder.remaining();
+        decoder.buf_mut().advance(remaining);
+        Ok(Self(text))
+    }
+}
+
+impl Encode for StringI {
+    /// one string in English, SHORT_STRING of ISO 8859-1
+    #[inline]
+    fn encode_by_ref<A: Encoder>(
+        &self,
+        buf: &mut BytesMut,
+        _encoder: &mut A,
+    ) -> Result<(), A::Error> {
+        let len = self.0.len().min(u8::MAX as usize);
+        buf.put_u8(1);
+        buf.put_slice(b"eng");
+        buf.put_u8(0xDA);
+        buf.put_u16_le(4);
+        buf.put_u8(len as u8);
+        buf.put_slice(&self.0.as_bytes()[..len]);
+        Ok(())
+    }
+
+    #[inline]
+    fn bytes_count(&self) -> usize {
+        8 + self.0.len().min(u8::MAX as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rseip_core::{
+        codec::LittleEndianDecoder,
+        tests::{CodecError, EncodeExt},
+    };
+
+    #[test]
+    fn test_checksum() {
+        let data = b"[File]\r\n";
+        let sum = data.iter().map(|v| *v as i32).sum::<i32>();
+        assert_eq!((sum + checksum(data) as i32) & 0xFFFF, 0);
+        assert_eq!(checksum(&[]), 0);
+    }
+
+    #[test]
+    fn test_string_i() {
+        let buf = StringI("device.eds".to_owned()).try_into_bytes().unwrap();
+        assert_eq!(&buf[..8], &[1, b'e', b'n', b'g', 0xDA, 4, 0, 10]);
+        let res = StringI::decode(LittleEndianDecoder::<CodecError>::new(buf)).unwrap();
+        assert_eq!(res.0, "device.eds");
+    }
+
+    #[test]
+    fn test_upload_packet() {
+        let buf = Bytes::from_static(&[3, 2, b'a', b'b', 0x3D, 0xFF]);
+        let res = UploadPacket::decode(LittleEndianDecoder::<CodecError>::new(buf)).unwrap();
+        assert_eq!(res.transfer_number, 3);
+        assert_eq!(res.packet_type, PacketType::Last);
+        assert_eq!(&res.data[..], b"ab");
+        assert_eq!(res.checksum, Some(checksum(b"ab")));
+
+        let packet = DownloadPacket {
+            transfer_number: 0,
+            packet_type: PacketType::FirstAndLast,
+            data: Bytes::from_static(b"ab"),
+            checksum: Some(checksum(b"ab")),
+        };
+        assert_eq!(
+            &packet.try_into_bytes().unwrap()[..],
+            &[0, 4, b'a', b'b', 0x3D, 0xFF]
+        );
+    }
+}
@@ -32,3 +32,204 @@ pub struct IdentityObject<'a> {
     /// current state of device
     pub state: u8,
 }
+
+/// Identity Object instance, reply of Get_Attribute_All service to class 0x01
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    /// device manufacturers vendor id
+    pub vendor_id: u16,
+    /// device type of product
+    pub device_type: u16,
+    /// product code
+    pub product_code: u16,
+    /// device revision
+    pub revision: Revision,
+    /// current status of device
+    pub status: IdentityStatus,
+    /// serial number of device
+    pub serial_number: u32,
+    /// product name
+    pub product_name: String,
+    /// current state of device, `None` if not reported
+    pub state: Option<IdentityState>,
+}
+
+impl From<IdentityObject<'_>> for Identity {
+    #[inline]
+    fn from(src: IdentityObject<'_>) -> Self {
+        Self {
+            vendor_id: src.vendor_id,
+            device_type: src.device_type,
+            product_code: src.product_code,
+            revision: src.revision,
+            status: IdentityStatus(src.status),
+            serial_number: src.serial_number,
+            product_name: src.product_name.into_owned(),
+            state: Some(src.state.into()),
+        }
+    }
+}
+
+/// Identity Object attributes 1-7, reply of Get_Attribute_List service
+#[derive(Debug)]
+pub(crate) struct IdentityAttributes(pub Identity);
+
+/// state of device, attribute 8 of Identity Object
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdentityState {
+    /// 0: nonexistent
+    Nonexistent,
+    /// 1: device self testing
+    SelfTesting,
+    /// 2: standby
+    Standby,
+    /// 3: operational
+    Operational,
+    /// 4: major recoverable fault
+    MajorRecoverableFault,
+    /// 5: major unrecoverable fault
+    MajorUnrecoverableFault,
+    /// 255: default value of Get_Attribute_All
+    Default,
+    /// 6-254: reserved
+    Reserved(u8),
+}
+
+impl From<u8> for IdentityState {
+    #[inline]
+    fn from(src: u8) -> Self {
+        match src {
+            0 => Self::Nonexistent,
+            1 => Self::SelfTesting,
+            2 => Self::Standby,
+            3 => Self::Operational,
+            4 => Self::MajorRecoverableFault,
+            5 => Self::MajorUnrecoverableFault,
+            0xFF => Self::Default,
+            v => Self::Reserved(v),
+        }
+    }
+}
+
+/// extended device status, bits 4-7 of the status word
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceStatus {
+    /// 0: self testing or unknown
+    SelfTesting,
+    /// 1: firmware update in progress
+    FirmwareUpdate,
+    /// 2: at least one faulted I/O connection
+    IoConnectionFaulted,
+    /// 3: no I/O connections established
+    NoIoConnection,
+    /// 4: non-volatile configuration bad
+    ConfigurationBad,
+    /// 5: major fault, bit 10 or bit 11 is set
+    MajorFault,
+    /// 6: at least one I/O connection in run mode
+    IoConnectionRun,
+    /// 7: at least one I/O connection established, all in idle mode
+    IoConnectionIdle,
+    /// 8-15: reserved or vendor specific
+    Other(u8),
+}
+
+impl From<u8> for DeviceStatus {
+    #[inline]
+    fn from(src: u8) -> Self {
+        match src {
+            0 => Self::SelfTesting,
+            1 => Self::FirmwareUpdate,
+            2 => Self::IoConnectionFaulted,
+            3 => Self::NoIoConnection,
+            4 => Self::ConfigurationBad,
+            5 => Self::MajorFault,
+            6 => Self::IoConnectionRun,
+            7 => Self::IoConnectionIdle,
+            v => Self::Other(v),
+        }
+    }
+}
+
+/// status word of Identity Object
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IdentityStatus(pub u16);
+
+impl IdentityStatus {
+    /// bit 0: the device has an owner
+    #[inline]
+    pub fn owned(&self) -> bool {
+        self.0 & 0x01 != 0
+    }
+
+    /// bit 2: the device has been configured
+    #[inline]
+    pub fn configured(&self) -> bool {
+        self.0 & 0x04 != 0
+    }
+
+    /// bits 4-7: extended device status, vendor specific
+    #[inline]
+    pub fn extended_status(&self) -> u8 {
+        ((self.0 >> 4) & 0x0F) as u8
+    }
+
+    /// extended device status of bits 4-7
+    #[inline]
+    pub fn device_status(&self) -> DeviceStatus {
+        self.extended_status().into()
+    }
+
+    /// any of bits 8-11 is set
+    #[inline]
+    pub fn is_faulted(&self) -> bool {
+        self.0 & 0x0F00 != 0
+    }
+
+    /// bit 8: minor recoverable fault
+    #[inline]
+    pub fn minor_recoverable_fault(&self) -> bool {
+        self.0 & 0x0100 != 0
+    }
+
+    /// bit 9: minor unrecoverable fault
+    #[inline]
+    pub fn minor_unrecoverable_fault(&self) -> bool {
+        self.0 & 0x0200 != 0
+    }
+
+    /// bit 10: major recoverable fault
+    #[inline]
+    pub fn major_recoverable_fault(&self) -> bool {
+        self.0 & 0x0400 != 0
+    }
+
+    /// bit 11: major unrecoverable fault
+    #[inline]
+    pub fn major_unrecoverable_fault(&self) -> bool {
+        self.0 & 0x0800 != 0
+    }
+
+    /// bits 12-15: extended device status 2, vendor specific
+    #[inline]
+    pub fn extended_status2(&self) -> u8 {
+        (self.0 >> 12) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity_status() {
+        let status = IdentityStatus(0x0461);
+        assert!(status.owned());
+        assert!(!status.configured());
+        assert_eq!(status.device_status(), DeviceStatus::IoConnectionRun);
+        assert!(status.major_recoverable_fault());
+        assert!(status.is_faulted());
+        assert_eq!(IdentityState::from(3), IdentityState::Operational);
+        assert_eq!(IdentityState::from(9), IdentityState::Reserved(9));
+    }
+}
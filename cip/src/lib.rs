@@ -19,17 +19,27 @@ Common industry protocol for `rseip`, please look at [rseip project](https://git
 //#![warn(missing_docs)]
 #![allow(clippy::match_like_matches_macro)]
 
+pub mod assembly;
+pub mod browser;
 pub mod codec;
 pub mod connection;
+pub mod connection_manager;
+pub mod data_type;
+pub mod eds;
 pub mod epath;
 pub mod error;
+pub mod ethernet_link;
+pub mod file;
 pub mod identity;
 mod list_service;
 pub mod message;
+pub mod parameter;
 mod revision;
 pub mod service;
 pub mod socket;
 mod status;
+pub mod tcpip;
+pub mod time_sync;
 
 pub use epath::EPath;
 pub use list_service::ListServiceItem;
@@ -0,0 +1,567 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+//! Parameter Object (class 0x0F), configuration of drives and other devices
+//!
+//! Parameters are numbered from 1 to the max instance of the class. The
+//! metadata of a parameter is read by Get_Attribute_All, or attribute by
+//! attribute if not supported.
+//!
+//! ```rust,ignore
+//! // backup
+//! let params = client.parameters().await?;
+//! for p in params.iter() {
+//!     println!("{} {} = {:?} {}", p.instance, p.name, p.scaled_value(), p.units);
+//! }
+//! // restore
+//! for p in params.iter().filter(|p| !p.descriptor.read_only()) {
+//!     if let Some(value) = p.value {
+//!         client.write_parameter(p, value).await?;
+//!     }
+//! }
+//! ```
+
+use crate::{
+    assembly::FieldValue, data_type::ElementaryType, epath::EPath, error::cip_error, service::*,
+    Status,
+};
+use bytes::{Buf, Bytes};
+use rseip_core::codec::BytesHolder;
+
+/// Parameter Object
+pub const CLASS_PARAMETER: u16 = 0x0F;
+
+const ATTR_VALUE: u16 = 1;
+const ATTR_DESCRIPTOR: u16 = 4;
+const ATTR_DATA_TYPE: u16 = 5;
+const ATTR_DATA_SIZE: u16 = 6;
+const ATTR_NAME: u16 = 7;
+const ATTR_UNITS: u16 = 8;
+const ATTR_HELP: u16 = 9;
+const ATTR_MIN: u16 = 10;
+const ATTR_MAX: u16 = 11;
+const ATTR_DEFAULT: u16 = 12;
+const ATTR_MULTIPLIER: u16 = 13;
+const ATTR_DIVISOR: u16 = 14;
+const ATTR_BASE: u16 = 15;
+const ATTR_OFFSET: u16 = 16;
+const ATTR_PRECISION: u16 = 21;
+
+/// descriptor of parameter, attribute 4
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ParameterDescriptor(pub u16);
+
+impl ParameterDescriptor {
+    /// bit 0: supports settable path
+    #[inline]
+    pub fn settable_path(&self) -> bool {
+        self.0 & 0x01 != 0
+    }
+
+    /// bit 1: supports enumerated strings
+    #[inline]
+    pub fn enumerated_strings(&self) -> bool {
+        self.0 & 0x02 != 0
+    }
+
+    /// bit 2: supports scaling
+    #[inline]
+    pub fn scaling(&self) -> bool {
+        self.0 & 0x04 != 0
+    }
+
+    /// bit 3: supports scaling links
+    #[inline]
+    pub fn scaling_links(&self) -> bool {
+        self.0 & 0x08 != 0
+    }
+
+    /// bit 4: read only
+    #[inline]
+    pub fn read_only(&self) -> bool {
+        self.0 & 0x10 != 0
+    }
+
+    /// bit 5: monitor parameter, updated by the device
+    #[inline]
+    pub fn monitor(&self) -> bool {
+        self.0 & 0x20 != 0
+    }
+
+    /// bit 6: supports extended precision scaling
+    #[inline]
+    pub fn extended_precision(&self) -> bool {
+        self.0 & 0x40 != 0
+    }
+}
+
+/// scaling of parameter, attributes 13-16 and 21
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scaling {
+    /// multiplier
+    pub multiplier: u16,
+    /// divisor
+    pub divisor: u16,
+    /// base
+    pub base: u16,
+    /// offset
+    pub offset: i16,
+    /// decimal precision, for extended precision scaling
+    pub precision: u8,
+}
+
+impl Default for Scaling {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            multiplier: 1,
+            divisor: 1,
+            base: 1,
+            offset: 0,
+            precision: 0,
+        }
+    }
+}
+
+impl Scaling {
+    /// engineering value from internal value
+    #[inline]
+    pub fn to_engineering(&self, v: f64) -> f64 {
+        (v + self.offset as f64) * self.multiplier as f64 * self.base as f64 / self.divisor()
+    }
+
+    /// internal value from engineering value
+    #[inline]
+    pub fn from_engineering(&self, v: f64) -> f64 {
+        let factor = self.multiplier as f64 * self.base as f64;
+        if factor == 0.0 {
+            return -(self.offset as f64);
+        }
+        v * self.divisor() / factor - self.offset as f64
+    }
+
+    #[inline]
+    fn divisor(&self) -> f64 {
+        self.divisor.max(1) as f64 * 10_f64.powi(self.precision as i32)
+    }
+}
+
+/// parameter with metadata
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    /// instance id
+    pub instance: u16,
+    /// value, `None` if not of elementary types
+    pub value: Option<FieldValue>,
+    /// descriptor
+    pub descriptor: ParameterDescriptor,
+    /// CIP data type code, eg. 0xC3 for INT
+    pub data_type: u8,
+    /// data size in bytes
+    pub data_size: u8,
+    /// name
+    pub name: String,
+    /// units
+    pub units: String,
+    /// help text
+    pub help: String,
+    /// min value
+    pub min: Option<FieldValue>,
+    /// max value
+    pub max: Option<FieldValue>,
+    /// default value
+    pub default: Option<FieldValue>,
+    /// scaling, applied if supported by descriptor
+    pub scaling: Scaling,
+}
+
+impl Parameter {
+    /// elementary type of the parameter
+    #[inline]
+    pub fn field_type(&self) -> Option<ElementaryType> {
+        ElementaryType::from_code(self.data_type.into())
+    }
+
+    /// engineering value from internal value, scaling applied if supported
+    #[inline]
+    pub fn to_engineering(&self, value: &FieldValue) -> f64 {
+        self.effective_scaling()
+            .map(|s| s.to_engineering(value.as_f64()))
+            .unwrap_or_else(|| value.as_f64())
+    }
+
+    /// internal value from engineering value, scaling applied if supported;
+    /// `None` if not of elementary types, or out of range of the type or min and max
+    #[inline]
+    pub fn from_engineering(&self, v: f64) -> Option<FieldValue> {
+        let v = self.internal_value(v);
+        FieldValue::from_f64(self.field_type()?, v).filter(|v| self.in_range(v))
+    }
+
+    /// is the value within min and max of the parameter, if available?
+    #[inline]
+    pub fn in_range(&self, value: &FieldValue) -> bool {
+        let v = value.as_f64();
+        !matches!(self.min, Some(ref min) if v < min.as_f64())
+            && !matches!(self.max, Some(ref max) if v > max.as_f64())
+    }
+
+    /// engineering value of the value read with metadata
+    #[inline]
+    pub fn scaled_value(&self) -> Option<f64> {
+        self.value.as_ref().map(|v| self.to_engineering(v))
+    }
+
+    /// internal value from engineering value, before conversion to the parameter type
+    #[inline]
+    fn internal_value(&self, v: f64) -> f64 {
+        self.effective_scaling()
+            .map(|s| s.from_engineering(v))
+            .unwrap_or(v)
+    }
+
+    #[inline]
+    fn effective_scaling(&self) -> Option<Scaling> {
+        if !self.descriptor.scaling() {
+            return None;
+        }
+        let mut res = self.scaling;
+        if !self.descriptor.extended_precision() {
+            res.precision = 0;
+        }
+        Some(res)
+    }
+}
+
+/// Parameter Object services
+#[async_trait::async_trait]
+pub trait ParameterService: MessageService {
+    /// number of parameters, max instance of the class
+    async fn parameter_count(&mut self) -> Result<u16, Self::Error> {
+        let path = EPath::default()
+            .with_class(CLASS_PARAMETER)
+            .with_instance(0)
+            .with_attribute(2);
+        send_and_extract(self, 0x0E, path, ()).await
+    }
+
+    /// read parameter with metadata
+    async fn parameter(&mut self, instance: u16) -> Result<Parameter, Self::Error> {
+        let data_type: BytesHolder =
+            send_and_extract(self, 0x0E, attribute_path(instance, ATTR_DATA_TYPE), ()).await?;
+        let data_type = Bytes::from(data_type).first().copied().unwrap_or_default();
+        let data_size: u8 =
+            send_and_extract(self, 0x0E, attribute_path(instance, ATTR_DATA_SIZE), ()).await?;
+        let path = EPath::default()
+            .with_class(CLASS_PARAMETER)
+            .with_instance(instance);
+        let all: Result<BytesHolder, Status> =
+            send_and_extract_status(self, 0x01, path, ()).await?;
+        if let Ok(data) = all {
+            if let Some(res) = decode_all(instance, data.into(), data_type, data_size) {
+                return Ok(res);
+            }
+        }
+
+        // attribute by attribute
+        let ty = ElementaryType::from_code(data_type.into());
+        let value = value_attribute(self, instance, ATTR_VALUE, ty).await?;
+        let descriptor = attribute(self, instance, ATTR_DESCRIPTOR).await?;
+        let name = string_attribute(self, instance, ATTR_NAME).await?;
+        let units = string_attribute(self, instance, ATTR_UNITS).await?;
+        let help = string_attribute(self, instance, ATTR_HELP).await?;
+        let min = value_attribute(self, instance, ATTR_MIN, ty).await?;
+        let max = value_attribute(self, instance, ATTR_MAX, ty).await?;
+        let default = value_attribute(self, instance, ATTR_DEFAULT, ty).await?;
+        let multiplier = attribute(self, instance, ATTR_MULTIPLIER).await?;
+        let divisor = attribute(self, instance, ATTR_DIVISOR).await?;
+        let base = attribute(self, instance, ATTR_BASE).await?;
+        let offset: Option<i16> = attribute(self, instance, ATTR_OFFSET).await?;
+        let precision = attribute(self, instance, ATTR_PRECISION).await?;
+        Ok(Parameter {
+            instance,
+            value,
+            descriptor: ParameterDescriptor(descriptor.unwrap_or_default()),
+            data_type,
+            data_size,
+            name: name.unwrap_or_default(),
+            units: units.unwrap_or_default(),
+            help: help.unwrap_or_default(),
+            min,
+            max,
+            default,
+            scaling: Scaling {
+                multiplier: multiplier.unwrap_or(1),
+                divisor: divisor.unwrap_or(1),
+                base: base.unwrap_or(1),
+                offset: offset.unwrap_or_default(),
+                precision: precision.unwrap_or_default(),
+            },
+        })
+    }
+
+    /// read all parameters with metadata
+    async fn parameters(&mut self) -> Result<Vec<Parameter>, Self::Error> {
+        let count = self.parameter_count().await?;
+        let mut res = Vec::with_capacity(count as usize);
+        for instance in 1..=count {
+            let param = self.parameter(instance).await?;
+            res.push(param);
+        }
+        Ok(res)
+    }
+
+    /// read value of the parameter
+    async fn read_parameter(&mut self, param: &Parameter) -> Result<FieldValue, Self::Error> {
+        let ty = elementary_type::<Self::Error>(param)?;
+        let data: BytesHolder =
+            send_and_extract(self, 0x0E, attribute_path(param.instance, ATTR_VALUE), ()).await?;
+        FieldValue::from_le_bytes(ty, &Bytes::from(data)).ok_or_else(|| {
+            cip_error(format_args!(
+                "parameter - unexpected data size of parameter {}",
+                param.instance
+            ))
+        })
+    }
+
+    /// read engineering value of the parameter, scaling applied if supported
+    async fn read_parameter_scaled(&mut self, param: &Parameter) -> Result<f64, Self::Error> {
+        let value = self.read_parameter(param).await?;
+        Ok(param.to_engineering(&value))
+    }
+
+    /// write value of the parameter; values out of min and max are rejected
+    async fn write_parameter(
+        &mut self,
+        param: &Parameter,
+        value: FieldValue,
+    ) -> Result<(), Self::Error> {
+        let ty = elementary_type::<Self::Error>(param)?;
+        if param.descriptor.read_only() {
+            return Err(cip_error(format_args!(
+                "parameter - parameter {} is read only",
+                param.instance
+            )));
+        }
+        if value.field_type() != ty {
+            return Err(cip_error(format_args!(
+                "parameter - expected {:?} for parameter {}",
+                ty, param.instance
+            )));
+        }
+        if !param.in_range(&value) {
+            return Err(out_of_range::<Self::Error>(param, value.as_f64()));
+        }
+        let path = attribute_path(param.instance, ATTR_VALUE);
+        send_and_extract(self, 0x10, path, value).await
+    }
+
+    /// write engineering value of the parameter, scaling applied if supported;
+    /// values out of min and max, or not fitting the parameter type, are rejected
+    async fn write_parameter_scaled(
+        &mut self,
+        param: &Parameter,
+        value: f64,
+    ) -> Result<(), Self::Error> {
+        let ty = elementary_type::<Self::Error>(param)?;
+        let v = param.internal_value(value);
+        let value =
+            FieldValue::from_f64(ty, v).ok_or_else(|| out_of_range::<Self::Error>(param, v))?;
+        self.write_parameter(param, value).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: MessageService> ParameterService for T {}
+
+#[inline]
+fn attribute_path(instance: u16, attribute: u16) -> EPath {
+    EPath::default()
+        .with_class(CLASS_PARAMETER)
+        .with_instance(instance)
+        .with_attribute(attribute)
+}
+
+#[inline]
+fn elementary_type<E: rseip_core::Error>(param: &Parameter) -> Result<ElementaryType, E> {
+    param.field_type().ok_or_else(|| {
+        cip_error(format_args!(
+            "parameter - unsupported data type {:#04x} of parameter {}",
+            param.data_type, param.instance
+        ))
+    })
+}
+
+#[inline]
+fn out_of_range<E: rseip_core::Error>(param: &Parameter, v: f64) -> E {
+    cip_error(format_args!(
+        "parameter - value {} out of range of parameter {}",
+        v, param.instance
+    ))
+}
+
+/// read attribute, `None` on error replies
+async fn attribute<S, R>(
+    service: &mut S,
+    instance: u16,
+    attribute: u16,
+) -> Result<Option<R>, S::Error>
+where
+    S: MessageService + ?Sized,
+    R: for<'de> rseip_core::codec::Decode<'de> + 'static,
+{
+    let path = attribute_path(instance, attribute);
+    let res: Result<R, Status> = send_and_extract_status(service, 0x0E, path, ()).await?;
+    Ok(res.ok())
+}
+
+/// read SHORT_STRING attribute, `None` on error replies
+async fn string_attribute<S>(
+    service: &mut S,
+    instance: u16,
+    id: u16,
+) -> Result<Option<String>, S::Error>
+where
+    S: MessageService + ?Sized,
+{
+    let res: Option<BytesHolder> = attribute(service, instance, id).await?;
+    Ok(res.and_then(|v| short_string(&mut Bytes::from(v))))
+}
+
+/// read attribute of the parameter type, `None` on error replies or unsupported type
+async fn value_attribute<S>(
+    service: &mut S,
+    instance: u16,
+    id: u16,
+    ty: Option<ElementaryType>,
+) -> Result<Option<FieldValue>, S::Error>
+where
+    S: MessageService + ?Sized,
+{
+    let ty = match ty {
+        Some(ty) => ty,
+        None => return Ok(None),
+    };
+    let res: Option<BytesHolder> = attribute(service, instance, id).await?;
+    Ok(res.and_then(|v| FieldValue::from_le_bytes(ty, &Bytes::from(v))))
+}
+
+/// decode SHORT_STRING
+fn short_string(buf: &mut Bytes) -> Option<String> {
+    if !buf.has_remaining() {
+        return None;
+    }
+    let len = buf.get_u8() as usize;
+    if buf.remaining() < len {
+        return None;
+    }
+    let data = buf.copy_to_bytes(len);
+    Some(String::from_utf8_lossy(&data).into_owned())
+}
+
+/// decode reply of Get_Attribute_All, `None` if invalid or only stub attributes
+fn decode_all(instance: u16, mut buf: Bytes, data_type: u8, data_size: u8) -> Option<Parameter> {
+    let ty = ElementaryType::from_code(data_type.into());
+    let size = data_size as usize;
+    let value = |buf: &mut Bytes| -> Option<Option<FieldValue>> {
+        if buf.remaining() < size {
+            return None;
+        }
+        let data = buf.copy_to_bytes(size);
+        Some(ty.and_then(|ty| FieldValue::from_le_bytes(ty, &data)))
+    };
+    let current = value(&mut buf)?;
+    if !buf.has_remaining() {
+        return None;
+    }
+    let link_size = buf.get_u8() as usize;
+    if buf.remaining() < link_size + 4 {
+        return None;
+    }
+    buf.advance(link_size);
+    let descriptor = ParameterDescriptor(buf.get_u16_le());
+    buf.advance(2); // data type, data size
+    let name = short_string(&mut buf)?;
+    let units = short_string(&mut buf)?;
+    let help = short_string(&mut buf)?;
+    let min = value(&mut buf)?;
+    let max = value(&mut buf)?;
+    let default = value(&mut buf)?;
+    if buf.remaining() < 16 {
+        return None;
+    }
+    let mut scaling = Scaling {
+        multiplier: buf.get_u16_le(),
+        divisor: buf.get_u16_le(),
+        base: buf.get_u16_le(),
+        offset: buf.get_i16_le(),
+        precision: 0,
+    };
+    buf.advance(8); // scaling links
+    if buf.has_remaining() {
+        scaling.precision = buf.get_u8();
+    }
+    Some(Parameter {
+        instance,
+        value: current,
+        descriptor,
+        data_type,
+        data_size,
+        name,
+        units,
+        help,
+        min,
+        max,
+        default,
+        scaling,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::{BufMut, BytesMut};
+
+    #[test]
+    fn test_decode_all() {
+        let mut buf = BytesMut::new();
+        buf.put_u16_le(1500); // value
+        buf.put_u8(0); // link path size
+        buf.put_u16_le(0x44); // scaling, extended precision
+        buf.put_slice(&[0xC7, 2]);
+        for s in ["Speed Ref", "Hz", "speed reference"] {
+            buf.put_u8(s.len() as u8);
+            buf.put_slice(s.as_bytes());
+        }
+        buf.put_u16_le(0); // min
+        buf.put_u16_le(6000); // max
+        buf.put_u16_le(0); // default
+        buf.put_slice(&[1, 0, 1, 0, 1, 0, 0, 0]);
+        buf.put_slice(&[0; 8]);
+        buf.put_u8(2);
+        let param = decode_all(3, buf.freeze(), 0xC7, 2).unwrap();
+        assert_eq!(param.name, "Speed Ref");
+        assert_eq!(param.units, "Hz");
+        assert_eq!(param.value, Some(FieldValue::Uint(1500)));
+        assert_eq!(param.max, Some(FieldValue::Uint(6000)));
+        assert_eq!(param.scaled_value(), Some(15.0));
+        assert_eq!(param.from_engineering(30.0), Some(FieldValue::Uint(3000)));
+        assert!(param.in_range(&FieldValue::Uint(6000)));
+        assert!(!param.in_range(&FieldValue::Uint(6001)));
+        assert_eq!(param.from_engineering(60.01), None);
+        assert_eq!(param.from_engineering(-0.05), None);
+        assert_eq!(FieldValue::from_f64(ElementaryType::Uint, -5.0), None);
+        assert_eq!(FieldValue::from_f64(ElementaryType::Uint, 70000.0), None);
+        assert_eq!(
+            FieldValue::from_f64(ElementaryType::Uint, 3000.4),
+            Some(FieldValue::Uint(3000))
+        );
+
+        // stub attributes only
+        let buf = Bytes::from_static(&[0xDC, 0x05, 0, 0, 0, 0xC7, 2]);
+        assert!(decode_all(3, buf, 0xC7, 2).is_none());
+    }
+}
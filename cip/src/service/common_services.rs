@@ -7,10 +7,16 @@
 mod multiple_packet;
 
 use super::*;
-use crate::epath::EPath;
+use crate::{
+    epath::EPath,
+    identity::{Identity, IdentityAttributes},
+};
 pub use multiple_packet::MultipleServicePacket;
 use rseip_core::codec::{Decode, Encode, SliceContainer};
 
+/// Identity Object
+const CLASS_IDENTITY: u16 = 0x01;
+
 /// common services
 #[async_trait::async_trait]
 pub trait CommonServices: MessageService {
@@ -193,6 +199,32 @@ pub trait CommonServices: MessageService {
         send_and_extract(self, 0x1C, path, ()).await
     }
 
+    /// read Identity Object instance 1 of the target device by Get_Attribute_All,
+    /// or attributes 1-7 by Get_Attribute_List if Get_Attribute_All is not supported;
+    /// to read modules in a chassis or behind a bridge, route with the connection path of the client
+    ///
+    /// ```rust,ignore
+    /// let mut client = EipClient::new_host_lookup("192.168.0.83")
+    ///     .await?
+    ///     .with_connection_path(PortSegment { port: 1, link: 3 });
+    /// let identity = client.get_identity().await?;
+    /// println!("{} {:?}", identity.product_name, identity.state);
+    /// ```
+    async fn get_identity(&mut self) -> Result<Identity, Self::Error> {
+        let path = EPath::default()
+            .with_class(CLASS_IDENTITY)
+            .with_instance(0x01);
+        let res: Option<Identity> =
+            send_and_extract_supported(self, 0x01, path.clone(), ()).await?;
+        if let Some(identity) = res {
+            return Ok(identity);
+        }
+        let res: IdentityAttributes = self
+            .get_attribute_list(path, &[1, 2, 3, 4, 5, 6, 7])
+            .await?;
+        Ok(res.0)
+    }
+
     /// multiple service packet
     #[inline]
     fn multiple_service<P, D>(&mut self) -> MultipleServicePacket<'_, Self, P, D>
@@ -207,3 +239,42 @@ pub trait CommonServices: MessageService {
 
 #[async_trait::async_trait]
 impl<T: MessageService> CommonServices for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use rseip_core::codec::{Decode, LittleEndianDecoder};
+    use rseip_core::tests::CodecError;
+
+    #[test]
+    fn test_decode_identity_attributes() {
+        let buf = Bytes::from_static(&[
+            0x03, 0x00, // count
+            0x01, 0x00, 0x00, 0x00, 0x01, 0x00, // vendor id
+            0x05, 0x00, 0x00, 0x00, 0x60, 0x30, // status
+            0x07, 0x00, 0x00, 0x00, 0x03, b'E', b'N', b'2', // product name
+        ]);
+        let res = IdentityAttributes::decode(LittleEndianDecoder::<CodecError>::new(buf)).unwrap();
+        assert_eq!(res.0.vendor_id, 1);
+        assert_eq!(res.0.status.0, 0x3060);
+        assert_eq!(res.0.product_name, "EN2");
+        assert_eq!(res.0.state, None);
+
+        let buf = Bytes::from_static(&[0x81, 0x00, 0x08, 0x00]);
+        let res =
+            StatusReply::<Identity>::decode(LittleEndianDecoder::<CodecError>::new(buf)).unwrap();
+        assert!(matches!(res.0.data, Err(status) if status.general == 0x08));
+
+        // partial transfer
+        let buf = Bytes::from_static(&[0xCC, 0x00, 0x06, 0x00, 0x01, 0x02]);
+        let res = StatusReply::<u16>::decode(LittleEndianDecoder::<CodecError>::new(buf.clone()))
+            .unwrap();
+        assert!(matches!(res.0.data, Err(status) if status.general == 0x06));
+        let res = StatusReply::<u16, STATUS_PARTIAL_TRANSFER>::decode(LittleEndianDecoder::<
+            CodecError,
+        >::new(buf))
+        .unwrap();
+        assert_eq!(res.0.data, Ok(0x0201));
+    }
+}
@@ -12,19 +12,29 @@ mod message_service;
 pub mod request;
 
 use crate::*;
+use crate::{codec::decode::message_reply::decode_service_and_status, error::cip_error_status};
 #[doc(inline)]
 pub use common_services::CommonServices;
 #[doc(inline)]
 pub use heartbeat::Heartbeat;
 #[doc(inline)]
 pub use message_service::MessageService;
-use rseip_core::codec::{Decode, Encode};
+use rseip_core::codec::{Decode, Decoder, Encode};
 
 pub const SERVICE_UNCONNECTED_SEND: u8 = 0x52;
 pub const SERVICE_FORWARD_OPEN: u8 = 0x54;
 pub const SERVICE_LARGE_FORWARD_OPEN: u8 = 0x5B;
 pub const SERVICE_FORWARD_CLOSE: u8 = 0x4E;
 
+/// general status: partial transfer, reply data present
+pub const STATUS_PARTIAL_TRANSFER: u8 = 0x06;
+/// general status: service not supported
+const STATUS_SERVICE_NOT_SUPPORTED: u8 = 0x08;
+/// general status: attribute not supported
+const STATUS_ATTRIBUTE_NOT_SUPPORTED: u8 = 0x14;
+/// general status: embedded service error, reply data present
+pub const STATUS_EMBEDDED_SERVICE_ERROR: u8 = 0x1E;
+
 /// send message request and extract the data from message reply
 #[doc(hidden)]
 #[inline]
@@ -49,3 +59,103 @@ where
     reply.expect_service::<S::Error>(service_code + REPLY_MASK)?;
     Ok(reply.data)
 }
+
+/// send message request and extract the data from message reply,
+/// `None` if the service or the attribute is not supported
+#[inline]
+pub(crate) async fn send_and_extract_supported<'de, S, P, D, R>(
+    service: &mut S,
+    service_code: u8,
+    path: P,
+    data: D,
+) -> Result<Option<R>, S::Error>
+where
+    S: MessageService + ?Sized,
+    P: Encode + Send + Sync,
+    D: Encode + Send + Sync,
+    R: Decode<'de> + 'static,
+{
+    match send_and_extract_status(service, service_code, path, data).await? {
+        Ok(v) => Ok(Some(v)),
+        Err(status)
+            if status.general == STATUS_SERVICE_NOT_SUPPORTED
+                || status.general == STATUS_ATTRIBUTE_NOT_SUPPORTED =>
+        {
+            Ok(None)
+        }
+        Err(status) => Err(cip_error_status(status)),
+    }
+}
+
+/// send message request and extract the data from message reply,
+/// error status of the reply is returned as `Err(status)`
+#[doc(hidden)]
+#[inline]
+pub async fn send_and_extract_status<'de, S, P, D, R>(
+    service: &mut S,
+    service_code: u8,
+    path: P,
+    data: D,
+) -> Result<Result<R, Status>, S::Error>
+where
+    S: MessageService + ?Sized,
+    P: Encode + Send + Sync,
+    D: Encode + Send + Sync,
+    R: Decode<'de> + 'static,
+{
+    let mr = MessageRequest {
+        service_code,
+        path,
+        data,
+    };
+    let reply: StatusReply<R> = service.send(mr).await?;
+    reply.expect_service::<S::Error>(service_code + REPLY_MASK)?;
+    Ok(reply.into_value())
+}
+
+/// message reply, with error status as `Err(status)` instead of a decoding error;
+/// the reply data is decoded if the general status is 0 or `ACCEPT`, eg.
+/// [`STATUS_PARTIAL_TRANSFER`] for fragmented services, check
+/// [`MessageReplyInterface::status`] for these
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct StatusReply<T, const ACCEPT: u8 = 0>(pub MessageReply<Result<T, Status>>);
+
+impl<T, const ACCEPT: u8> MessageReplyInterface for StatusReply<T, ACCEPT> {
+    type Value = Result<T, Status>;
+
+    fn reply_service(&self) -> u8 {
+        self.0.reply_service
+    }
+
+    fn status(&self) -> &Status {
+        &self.0.status
+    }
+
+    fn value(&self) -> &Self::Value {
+        &self.0.data
+    }
+
+    fn into_value(self) -> Self::Value {
+        self.0.data
+    }
+}
+
+impl<'de, T, const ACCEPT: u8> Decode<'de> for StatusReply<T, ACCEPT>
+where
+    T: Decode<'de>,
+{
+    #[inline]
+    fn decode<D>(mut decoder: D) -> Result<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        let (reply_service, status) = decode_service_and_status(&mut decoder)?;
+        let data = if status.general == 0 || status.general == ACCEPT {
+            Ok(decoder.decode_any()?)
+        } else {
+            Err(status)
+        };
+        Ok(Self(MessageReply::new(reply_service, status, data)))
+    }
+}
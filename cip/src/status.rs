@@ -7,7 +7,7 @@
 use core::fmt;
 
 /// message reply status
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Status {
     pub general: u8,
     pub extended: Option<u16>,
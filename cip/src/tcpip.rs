@@ -0,0 +1,514 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+//! TCP/IP Interface Object (class 0xF5)
+//!
+//! ```rust,ignore
+//! let config = client.interface_configuration().await?;
+//! println!("{} / {} via {}", config.ip_address, config.network_mask, config.gateway);
+//! client.set_host_name("press-7").await?;
+//! client
+//!     .set_configuration_control(ConfigurationControl::default().with_method(ConfigMethod::Static))
+//!     .await?;
+//! ```
+
+use crate::{epath::EPath, service::*};
+use bytes::{Buf, BufMut, BytesMut};
+use rseip_core::{
+    codec::{Decode, Decoder, Encode, Encoder},
+    Error,
+};
+use std::net::Ipv4Addr;
+
+/// TCP/IP Interface Object
+pub const CLASS_TCPIP: u16 = 0xF5;
+
+const ATTR_STATUS: u16 = 1;
+const ATTR_CONFIGURATION_CAPABILITY: u16 = 2;
+const ATTR_CONFIGURATION_CONTROL: u16 = 3;
+const ATTR_INTERFACE_CONFIGURATION: u16 = 5;
+const ATTR_HOST_NAME: u16 = 6;
+const ATTR_SELECT_ACD: u16 = 10;
+const ATTR_LAST_CONFLICT_DETECTED: u16 = 11;
+
+/// status of TCP/IP Interface Object, attribute 1
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TcpIpStatus(pub u32);
+
+impl TcpIpStatus {
+    /// bits 0-3: 0 not configured, 1 configured by BOOTP, DHCP or non-volatile storage,
+    /// 2 configured by hardware settings
+    #[inline]
+    pub fn configuration_status(&self) -> u8 {
+        (self.0 & 0x0F) as u8
+    }
+
+    /// bit 4: multicast configuration pending
+    #[inline]
+    pub fn mcast_pending(&self) -> bool {
+        self.0 & 0x10 != 0
+    }
+
+    /// bit 5: interface configuration pending, takes effect on next reset
+    #[inline]
+    pub fn configuration_pending(&self) -> bool {
+        self.0 & 0x20 != 0
+    }
+
+    /// bit 6: address conflict detected
+    #[inline]
+    pub fn acd_conflict(&self) -> bool {
+        self.0 & 0x40 != 0
+    }
+
+    /// bit 7: address conflict detection fault
+    #[inline]
+    pub fn acd_fault(&self) -> bool {
+        self.0 & 0x80 != 0
+    }
+}
+
+/// configuration capability of TCP/IP Interface Object, attribute 2
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ConfigurationCapability(pub u32);
+
+impl ConfigurationCapability {
+    /// bit 0: BOOTP client
+    #[inline]
+    pub fn bootp(&self) -> bool {
+        self.0 & 0x01 != 0
+    }
+
+    /// bit 1: DNS client
+    #[inline]
+    pub fn dns(&self) -> bool {
+        self.0 & 0x02 != 0
+    }
+
+    /// bit 2: DHCP client
+    #[inline]
+    pub fn dhcp(&self) -> bool {
+        self.0 & 0x04 != 0
+    }
+
+    /// bit 4: interface configuration is settable
+    #[inline]
+    pub fn settable(&self) -> bool {
+        self.0 & 0x10 != 0
+    }
+
+    /// bit 6: interface configuration change requires reset
+    #[inline]
+    pub fn change_requires_reset(&self) -> bool {
+        self.0 & 0x40 != 0
+    }
+
+    /// bit 7: address conflict detection capable
+    #[inline]
+    pub fn acd(&self) -> bool {
+        self.0 & 0x80 != 0
+    }
+}
+
+/// how the device obtains its IP configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigMethod {
+    /// 0: statically assigned, from Interface Configuration attribute
+    Static,
+    /// 1: BOOTP
+    Bootp,
+    /// 2: DHCP
+    Dhcp,
+    /// other values, reserved
+    Other(u8),
+}
+
+impl From<u8> for ConfigMethod {
+    #[inline]
+    fn from(src: u8) -> Self {
+        match src {
+            0 => Self::Static,
+            1 => Self::Bootp,
+            2 => Self::Dhcp,
+            v => Self::Other(v),
+        }
+    }
+}
+
+impl From<ConfigMethod> for u8 {
+    #[inline]
+    fn from(src: ConfigMethod) -> Self {
+        match src {
+            ConfigMethod::Static => 0,
+            ConfigMethod::Bootp => 1,
+            ConfigMethod::Dhcp => 2,
+            ConfigMethod::Other(v) => v & 0x0F,
+        }
+    }
+}
+
+/// configuration control of TCP/IP Interface Object, attribute 3
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ConfigurationControl(pub u32);
+
+impl ConfigurationControl {
+    /// bits 0-3: configuration method
+    #[inline]
+    pub fn method(&self) -> ConfigMethod {
+        ((self.0 & 0x0F) as u8).into()
+    }
+
+    /// bit 4: resolve host names by DNS
+    #[inline]
+    pub fn dns_enabled(&self) -> bool {
+        self.0 & 0x10 != 0
+    }
+
+    /// set configuration method
+    #[inline]
+    pub fn with_method(mut self, method: ConfigMethod) -> Self {
+        self.0 = (self.0 & !0x0F) | u8::from(method) as u32;
+        self
+    }
+
+    /// enable or disable DNS
+    #[inline]
+    pub fn with_dns_enabled(mut self, enabled: bool) -> Self {
+        if enabled {
+            self.0 |= 0x10;
+        } else {
+            self.0 &= !0x10;
+        }
+        self
+    }
+}
+
+/// interface configuration of TCP/IP Interface Object, attribute 5
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceConfiguration {
+    /// IP address
+    pub ip_address: Ipv4Addr,
+    /// network mask
+    pub network_mask: Ipv4Addr,
+    /// default gateway
+    pub gateway: Ipv4Addr,
+    /// primary name server
+    pub name_server: Ipv4Addr,
+    /// secondary name server
+    pub name_server2: Ipv4Addr,
+    /// default domain name
+    pub domain_name: String,
+}
+
+impl Default for InterfaceConfiguration {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            ip_address: Ipv4Addr::UNSPECIFIED,
+            network_mask: Ipv4Addr::UNSPECIFIED,
+            gateway: Ipv4Addr::UNSPECIFIED,
+            name_server: Ipv4Addr::UNSPECIFIED,
+            name_server2: Ipv4Addr::UNSPECIFIED,
+            domain_name: String::new(),
+        }
+    }
+}
+
+/// last address conflict of TCP/IP Interface Object, attribute 11
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AcdConflict {
+    /// ACD activity when the conflict was detected
+    pub activity: u8,
+    /// MAC address of the conflicting device
+    pub remote_mac: [u8; 6],
+    /// ARP PDU in which the conflict was detected
+    pub arp_pdu: [u8; 28],
+}
+
+/// network configuration and status of the device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TcpIpInterface {
+    /// interface status
+    pub status: TcpIpStatus,
+    /// configuration capability
+    pub capability: ConfigurationCapability,
+    /// configuration control
+    pub control: ConfigurationControl,
+    /// interface configuration
+    pub configuration: InterfaceConfiguration,
+    /// host name
+    pub host_name: String,
+    /// address conflict detection enabled, `None` if not supported
+    pub acd_enabled: Option<bool>,
+}
+
+/// TCP/IP Interface Object services
+#[async_trait::async_trait]
+pub trait TcpIpService: MessageService {
+    /// read interface status
+    async fn tcpip_status(&mut self) -> Result<TcpIpStatus, Self::Error> {
+        let res = send_and_extract(self, 0x0E, attribute_path(ATTR_STATUS), ()).await?;
+        Ok(TcpIpStatus(res))
+    }
+
+    /// read configuration capability
+    async fn configuration_capability(&mut self) -> Result<ConfigurationCapability, Self::Error> {
+        let path = attribute_path(ATTR_CONFIGURATION_CAPABILITY);
+        let res = send_and_extract(self, 0x0E, path, ()).await?;
+        Ok(ConfigurationCapability(res))
+    }
+
+    /// read configuration control
+    async fn configuration_control(&mut self) -> Result<ConfigurationControl, Self::Error> {
+        let path = attribute_path(ATTR_CONFIGURATION_CONTROL);
+        let res = send_and_extract(self, 0x0E, path, ()).await?;
+        Ok(ConfigurationControl(res))
+    }
+
+    /// read interface configuration, eg. IP address, network mask, gateway and name servers
+    async fn interface_configuration(&mut self) -> Result<InterfaceConfiguration, Self::Error> {
+        let path = attribute_path(ATTR_INTERFACE_CONFIGURATION);
+        send_and_extract(self, 0x0E, path, ()).await
+    }
+
+    /// read host name
+    async fn host_name(&mut self) -> Result<String, Self::Error> {
+        let res: PaddedString =
+            send_and_extract(self, 0x0E, attribute_path(ATTR_HOST_NAME), ()).await?;
+        Ok(res.0)
+    }
+
+    /// address conflict detection enabled? `None` if not supported
+    async fn acd_enabled(&mut self) -> Result<Option<bool>, Self::Error> {
+        let path = attribute_path(ATTR_SELECT_ACD);
+        send_and_extract_supported(self, 0x0E, path, ()).await
+    }
+
+    /// last address conflict, `None` if not supported
+    async fn last_acd_conflict(&mut self) -> Result<Option<AcdConflict>, Self::Error> {
+        let path = attribute_path(ATTR_LAST_CONFLICT_DETECTED);
+        send_and_extract_supported(self, 0x0E, path, ()).await
+    }
+
+    /// read network configuration and status
+    async fn tcpip_interface(&mut self) -> Result<TcpIpInterface, Self::Error> {
+        let status = self.tcpip_status().await?;
+        let capability = self.configuration_capability().await?;
+        let control = self.configuration_control().await?;
+        let configuration = self.interface_configuration().await?;
+        let host_name = self.host_name().await?;
+        let acd_enabled = self.acd_enabled().await?;
+        Ok(TcpIpInterface {
+            status,
+            capability,
+            control,
+            configuration,
+            host_name,
+            acd_enabled,
+        })
+    }
+
+    /// set configuration control, eg. static IP, BOOTP or DHCP
+    async fn set_configuration_control(
+        &mut self,
+        control: ConfigurationControl,
+    ) -> Result<(), Self::Error> {
+        let path = attribute_path(ATTR_CONFIGURATION_CONTROL);
+        send_and_extract(self, 0x10, path, control.0).await
+    }
+
+    /// set interface configuration; the configuration method should be static
+    async fn set_interface_configuration(
+        &mut self,
+        configuration: &InterfaceConfiguration,
+    ) -> Result<(), Self::Error> {
+        let path = attribute_path(ATTR_INTERFACE_CONFIGURATION);
+        send_and_extract(self, 0x10, path, configuration).await
+    }
+
+    /// set host name
+    async fn set_host_name(&mut self, host_name: &str) -> Result<(), Self::Error> {
+        let path = attribute_path(ATTR_HOST_NAME);
+        send_and_extract(self, 0x10, path, PaddedStr(host_name)).await
+    }
+
+    /// enable or disable address conflict detection
+    async fn set_acd_enabled(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        let path = attribute_path(ATTR_SELECT_ACD);
+        send_and_extract(self, 0x10, path, enabled as u8).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: MessageService> TcpIpService for T {}
+
+#[inline]
+fn attribute_path(attribute: u16) -> EPath {
+    EPath::default()
+        .with_class(CLASS_TCPIP)
+        .with_instance(0x01)
+        .with_attribute(attribute)
+}
+
+/// STRING, padded to even length
+struct PaddedString(String);
+
+impl<'de> Decode<'de> for PaddedString {
+    #[inline]
+    fn decode<D>(mut decoder: D) -> Result<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        decoder.ensure_size(2)?;
+        let len = decoder.decode_u16() as usize;
+        decoder.ensure_size(len)?;
+        let data = decoder.buf_mut().copy_to_bytes(len);
+        if len % 2 == 1 && decoder.has_remaining() {
+            decoder.buf_mut().advance(1);
+        }
+        Ok(Self(String::from_utf8_lossy(&data).into_owned()))
+    }
+}
+
+/// STRING, padded to even length
+struct PaddedStr<'a>(&'a str);
+
+impl Encode for PaddedStr<'_> {
+    #[inline]
+    fn encode_by_ref<A: Encoder>(
+        &self,
+        buf: &mut BytesMut,
+        _encoder: &mut A,
+    ) -> Result<(), A::Error> {
+        buf.put_u16_le(self.0.len() as u16);
+        buf.put_slice(self.0.as_bytes());
+        if self.0.len() % 2 == 1 {
+            buf.put_u8(0);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn bytes_count(&self) -> usize {
+        2 + self.0.len() + self.0.len() % 2
+    }
+}
+
+impl<'de> Decode<'de> for InterfaceConfiguration {
+    #[inline]
+    fn decode<D>(mut decoder: D) -> Result<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        decoder.ensure_size(20)?;
+        let ip_address = Ipv4Addr::from(decoder.decode_u32());
+        let network_mask = Ipv4Addr::from(decoder.decode_u32());
+        let gateway = Ipv4Addr::from(decoder.decode_u32());
+        let name_server = Ipv4Addr::from(decoder.decode_u32());
+        let name_server2 = Ipv4Addr::from(decoder.decode_u32());
+        let PaddedString(domain_name) = decoder.decode_any()?;
+        Ok(Self {
+            ip_address,
+            network_mask,
+            gateway,
+            name_server,
+            name_server2,
+            domain_name,
+        })
+    }
+}
+
+impl Encode for InterfaceConfiguration {
+    #[inline]
+    fn encode_by_ref<A: Encoder>(
+        &self,
+        buf: &mut BytesMut,
+        encoder: &mut A,
+    ) -> Result<(), A::Error> {
+        for addr in [
+            self.ip_address,
+            self.network_mask,
+            self.gateway,
+            self.name_server,
+            self.name_server2,
+        ] {
+            buf.put_u32_le(addr.into());
+        }
+        PaddedStr(&self.domain_name).encode_by_ref(buf, encoder)
+    }
+
+    #[inline]
+    fn bytes_count(&self) -> usize {
+        20 + PaddedStr(&self.domain_name).bytes_count()
+    }
+}
+
+impl<'de> Decode<'de> for AcdConflict {
+    #[inline]
+    fn decode<D>(mut decoder: D) -> Result<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        decoder.ensure_size(35)?;
+        let activity = decoder.decode_u8();
+        let mut remote_mac = [0; 6];
+        decoder.buf_mut().copy_to_slice(&mut remote_mac);
+        let mut arp_pdu = [0; 28];
+        decoder.buf_mut().copy_to_slice(&mut arp_pdu);
+        if activity > 3 {
+            return Err(Error::invalid_value(activity, "ACD activity 0-3"));
+        }
+        Ok(Self {
+            activity,
+            remote_mac,
+            arp_pdu,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use rseip_core::{
+        codec::LittleEndianDecoder,
+        tests::{CodecError, EncodeExt},
+    };
+
+    #[test]
+    fn test_interface_configuration() {
+        let config = InterfaceConfiguration {
+            ip_address: Ipv4Addr::new(192, 168, 1, 10),
+            network_mask: Ipv4Addr::new(255, 255, 255, 0),
+            gateway: Ipv4Addr::new(192, 168, 1, 1),
+            name_server: Ipv4Addr::UNSPECIFIED,
+            name_server2: Ipv4Addr::UNSPECIFIED,
+            domain_name: "abc".to_owned(),
+        };
+        assert_eq!(config.bytes_count(), 26);
+        let buf = config.clone().try_into_bytes().unwrap();
+        assert_eq!(&buf[..4], &[10, 1, 168, 192]);
+        assert_eq!(&buf[20..], &[3, 0, b'a', b'b', b'c', 0]);
+        let res =
+            InterfaceConfiguration::decode(LittleEndianDecoder::<CodecError>::new(buf)).unwrap();
+        assert_eq!(res, config);
+    }
+
+    #[test]
+    fn test_configuration_control() {
+        let control = ConfigurationControl(0x12);
+        assert_eq!(control.method(), ConfigMethod::Dhcp);
+        assert!(control.dns_enabled());
+        let control = control
+            .with_method(ConfigMethod::Static)
+            .with_dns_enabled(false);
+        assert_eq!(control.0, 0);
+
+        let buf = Bytes::from_static(&[5, 0, b'p', b'l', b'c', b'-', b'1', 0]);
+        let res = PaddedString::decode(LittleEndianDecoder::<CodecError>::new(buf)).unwrap();
+        assert_eq!(res.0, "plc-1");
+    }
+}
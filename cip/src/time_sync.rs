@@ -0,0 +1,356 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+//! Time Sync Object (class 0x43), CIP Sync diagnostics of IEEE 1588 PTP clocks
+//!
+//! ```rust,ignore
+//! let status = client.time_sync_status().await?;
+//! println!(
+//!     "synchronized: {}, offset {} ns, grandmaster {}",
+//!     status.synchronized, status.offset_from_master, status.grandmaster.identity
+//! );
+//! ```
+
+use crate::{epath::EPath, service::*};
+use bytes::Buf;
+use core::fmt;
+use rseip_core::codec::{Decode, Decoder};
+
+/// Time Sync Object
+pub const CLASS_TIME_SYNC: u16 = 0x43;
+
+const ATTR_PTP_ENABLE: u16 = 1;
+const ATTR_IS_SYNCHRONIZED: u16 = 2;
+const ATTR_SYSTEM_TIME_MICROSECONDS: u16 = 3;
+const ATTR_OFFSET_FROM_MASTER: u16 = 5;
+const ATTR_MEAN_PATH_DELAY_TO_MASTER: u16 = 7;
+const ATTR_GRANDMASTER_CLOCK_INFO: u16 = 8;
+const ATTR_PARENT_CLOCK_INFO: u16 = 9;
+const ATTR_PORT_STATE_INFO: u16 = 12;
+
+/// IEEE 1588 clock identity
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClockIdentity(pub [u8; 8]);
+
+impl fmt::Display for ClockIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, b) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ":")?;
+            }
+            write!(f, "{:02X}", b)?;
+        }
+        Ok(())
+    }
+}
+
+/// IEEE 1588 clock quality
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ClockQuality {
+    /// clock class, eg. 6 for a clock synchronized to a primary reference
+    pub clock_class: u16,
+    /// time accuracy, eg. 0x21 for 100 ns
+    pub time_accuracy: u16,
+    /// offset scaled log variance
+    pub offset_scaled_log_variance: u16,
+}
+
+/// grandmaster clock, attribute 8
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GrandmasterClock {
+    /// clock identity
+    pub identity: ClockIdentity,
+    /// clock quality
+    pub quality: ClockQuality,
+    /// current UTC offset in seconds
+    pub current_utc_offset: u16,
+    /// time property flags
+    pub time_property_flags: u16,
+    /// time source, eg. 0x20 for GPS
+    pub time_source: u16,
+    /// priority 1
+    pub priority1: u16,
+    /// priority 2
+    pub priority2: u16,
+}
+
+/// parent clock, attribute 9
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ParentClock {
+    /// clock identity
+    pub identity: ClockIdentity,
+    /// port number of the parent clock
+    pub port_number: u16,
+    /// observed offset scaled log variance
+    pub observed_offset_scaled_log_variance: u16,
+    /// observed phase change rate
+    pub observed_phase_change_rate: u32,
+}
+
+/// IEEE 1588 port state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortState {
+    /// 1: initializing
+    Initializing,
+    /// 2: faulty
+    Faulty,
+    /// 3: disabled
+    Disabled,
+    /// 4: listening
+    Listening,
+    /// 5: pre master
+    PreMaster,
+    /// 6: master
+    Master,
+    /// 7: passive
+    Passive,
+    /// 8: uncalibrated
+    Uncalibrated,
+    /// 9: slave, synchronized to the master
+    Slave,
+    /// other values, reserved
+    Other(u16),
+}
+
+impl From<u16> for PortState {
+    #[inline]
+    fn from(v: u16) -> Self {
+        match v {
+            1 => Self::Initializing,
+            2 => Self::Faulty,
+            3 => Self::Disabled,
+            4 => Self::Listening,
+            5 => Self::PreMaster,
+            6 => Self::Master,
+            7 => Self::Passive,
+            8 => Self::Uncalibrated,
+            9 => Self::Slave,
+            v => Self::Other(v),
+        }
+    }
+}
+
+/// state of a PTP port, attribute 12
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PortStateInfo {
+    /// port number
+    pub port: u16,
+    /// port state
+    pub state: PortState,
+}
+
+/// time synchronization health
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeSyncStatus {
+    /// PTP enabled
+    pub ptp_enable: bool,
+    /// synchronized to the grandmaster
+    pub synchronized: bool,
+    /// system time in microseconds since 1970-01-01
+    pub system_time: u64,
+    /// offset from master in nanoseconds
+    pub offset_from_master: i64,
+    /// grandmaster clock
+    pub grandmaster: GrandmasterClock,
+    /// parent clock
+    pub parent: ParentClock,
+    /// port states
+    pub port_states: Vec<PortStateInfo>,
+}
+
+/// Time Sync Object services
+#[async_trait::async_trait]
+pub trait TimeSyncService: MessageService {
+    /// read PTP enable
+    async fn ptp_enabled(&mut self) -> Result<bool, Self::Error> {
+        send_and_extract(self, 0x0E, attribute_path(ATTR_PTP_ENABLE), ()).await
+    }
+
+    /// read whether the clock is synchronized to the grandmaster
+    async fn is_synchronized(&mut self) -> Result<bool, Self::Error> {
+        send_and_extract(self, 0x0E, attribute_path(ATTR_IS_SYNCHRONIZED), ()).await
+    }
+
+    /// read system time in microseconds since 1970-01-01
+    async fn system_time(&mut self) -> Result<u64, Self::Error> {
+        let path = attribute_path(ATTR_SYSTEM_TIME_MICROSECONDS);
+        send_and_extract(self, 0x0E, path, ()).await
+    }
+
+    /// read offset from master in nanoseconds
+    async fn offset_from_master(&mut self) -> Result<i64, Self::Error> {
+        send_and_extract(self, 0x0E, attribute_path(ATTR_OFFSET_FROM_MASTER), ()).await
+    }
+
+    /// read mean path delay to master in nanoseconds
+    async fn mean_path_delay(&mut self) -> Result<i64, Self::Error> {
+        let path = attribute_path(ATTR_MEAN_PATH_DELAY_TO_MASTER);
+        send_and_extract(self, 0x0E, path, ()).await
+    }
+
+    /// read grandmaster clock identity and quality
+    async fn grandmaster_clock(&mut self) -> Result<GrandmasterClock, Self::Error> {
+        let path = attribute_path(ATTR_GRANDMASTER_CLOCK_INFO);
+        send_and_extract(self, 0x0E, path, ()).await
+    }
+
+    /// read parent clock identity
+    async fn parent_clock(&mut self) -> Result<ParentClock, Self::Error> {
+        send_and_extract(self, 0x0E, attribute_path(ATTR_PARENT_CLOCK_INFO), ()).await
+    }
+
+    /// read states of PTP ports
+    async fn port_states(&mut self) -> Result<Vec<PortStateInfo>, Self::Error> {
+        let path = attribute_path(ATTR_PORT_STATE_INFO);
+        let res: PortStateList = send_and_extract(self, 0x0E, path, ()).await?;
+        Ok(res.0)
+    }
+
+    /// read time synchronization health
+    async fn time_sync_status(&mut self) -> Result<TimeSyncStatus, Self::Error> {
+        let ptp_enable = self.ptp_enabled().await?;
+        let synchronized = self.is_synchronized().await?;
+        let system_time = self.system_time().await?;
+        let offset_from_master = self.offset_from_master().await?;
+        let grandmaster = self.grandmaster_clock().await?;
+        let parent = self.parent_clock().await?;
+        let port_states = self.port_states().await?;
+        Ok(TimeSyncStatus {
+            ptp_enable,
+            synchronized,
+            system_time,
+            offset_from_master,
+            grandmaster,
+            parent,
+            port_states,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: MessageService> TimeSyncService for T {}
+
+#[inline]
+fn attribute_path(attribute: u16) -> EPath {
+    EPath::default()
+        .with_class(CLASS_TIME_SYNC)
+        .with_instance(1)
+        .with_attribute(attribute)
+}
+
+#[inline]
+fn decode_identity<'de, D: Decoder<'de>>(decoder: &mut D) -> ClockIdentity {
+    let mut res = [0; 8];
+    decoder.buf_mut().copy_to_slice(&mut res);
+    ClockIdentity(res)
+}
+
+impl<'de> Decode<'de> for GrandmasterClock {
+    fn decode<D>(mut decoder: D) -> Result<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        decoder.ensure_size(24)?;
+        let identity = decode_identity(&mut decoder);
+        Ok(Self {
+            identity,
+            quality: ClockQuality {
+                clock_class: decoder.decode_u16(),
+                time_accuracy: decoder.decode_u16(),
+                offset_scaled_log_variance: decoder.decode_u16(),
+            },
+            current_utc_offset: decoder.decode_u16(),
+            time_property_flags: decoder.decode_u16(),
+            time_source: decoder.decode_u16(),
+            priority1: decoder.decode_u16(),
+            priority2: decoder.decode_u16(),
+        })
+    }
+}
+
+impl<'de> Decode<'de> for ParentClock {
+    fn decode<D>(mut decoder: D) -> Result<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        decoder.ensure_size(16)?;
+        let identity = decode_identity(&mut decoder);
+        Ok(Self {
+            identity,
+            port_number: decoder.decode_u16(),
+            observed_offset_scaled_log_variance: decoder.decode_u16(),
+            observed_phase_change_rate: decoder.decode_u32(),
+        })
+    }
+}
+
+/// port state info, attribute 12
+struct PortStateList(Vec<PortStateInfo>);
+
+impl<'de> Decode<'de> for PortStateList {
+    fn decode<D>(mut decoder: D) -> Result<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        decoder.ensure_size(2)?;
+        let count = decoder.decode_u16() as usize;
+        decoder.ensure_size(count * 4)?;
+        let res = (0..count)
+            .map(|_| PortStateInfo {
+                port: decoder.decode_u16(),
+                state: decoder.decode_u16().into(),
+            })
+            .collect();
+        Ok(Self(res))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::{BufMut, Bytes, BytesMut};
+    use rseip_core::{codec::LittleEndianDecoder, tests::CodecError};
+
+    #[test]
+    fn test_decode_clocks() {
+        let mut buf = BytesMut::new();
+        buf.put_slice(&[0x00, 0x1D, 0x9C, 0xFF, 0xFE, 0xAB, 0xCD, 0xEF]);
+        for v in [6u16, 0x21, 0x4E5D, 37, 0x0C, 0x20, 128, 128] {
+            buf.put_u16_le(v);
+        }
+        let res =
+            GrandmasterClock::decode(LittleEndianDecoder::<CodecError>::new(buf.freeze())).unwrap();
+        assert_eq!(res.identity.to_string(), "00:1D:9C:FF:FE:AB:CD:EF");
+        assert_eq!(res.quality.clock_class, 6);
+        assert_eq!(res.time_source, 0x20);
+        assert_eq!(res.priority2, 128);
+
+        let buf = Bytes::from_static(&[
+            1, 2, 3, 4, 5, 6, 7, 8, 1, 0, 0xFF, 0xFF, 0, 0, 0, 0,
+        ]);
+        let res = ParentClock::decode(LittleEndianDecoder::<CodecError>::new(buf)).unwrap();
+        assert_eq!(res.identity, ClockIdentity([1, 2, 3, 4, 5, 6, 7, 8]));
+        assert_eq!(res.port_number, 1);
+
+        let buf = Bytes::from_static(&[2, 0, 1, 0, 9, 0, 2, 0, 6, 0]);
+        let res = PortStateList::decode(LittleEndianDecoder::<CodecError>::new(buf)).unwrap();
+        assert_eq!(
+            res.0,
+            vec![
+                PortStateInfo {
+                    port: 1,
+                    state: PortState::Slave
+                },
+                PortStateInfo {
+                    port: 2,
+                    state: PortState::Master
+                }
+            ]
+        );
+        let buf = Bytes::from_static(&[2, 0, 1, 0, 9, 0]);
+        assert!(PortStateList::decode(LittleEndianDecoder::<CodecError>::new(buf)).is_err());
+    }
+}
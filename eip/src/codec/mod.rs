@@ -20,19 +20,27 @@ use rseip_core::{
 };
 use tokio_util::codec::{Decoder, Encoder};
 
+/// EIP codec, little endian
 #[derive(Debug, PartialEq)]
 pub struct ClientCodec<E> {
     _marker: PhantomData<E>,
 }
 
 impl<E> ClientCodec<E> {
-    pub(crate) fn new() -> Self {
+    /// new codec
+    pub fn new() -> Self {
         Self {
             _marker: Default::default(),
         }
     }
 }
 
+impl<E> Default for ClientCodec<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<E: Error> codec::Encoder for ClientCodec<E> {
     type Error = E;
 
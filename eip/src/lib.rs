@@ -16,6 +16,7 @@ pub mod encapsulation;
 mod error;
 mod framed;
 
+pub use codec::ClientCodec;
 pub use context::EipContext;
 pub use discover::EipDiscovery;
 pub use encapsulation::{EncapsulationHeader, EncapsulationPacket};
@@ -0,0 +1,66 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+//! generate Rust source for UDTs from live templates
+//!
+//! usage: `cargo run --example ab-udt-codegen -- <host> [-o <output file>] [tag names...]`
+//!
+//! all structure tags are processed if no tag names specified;
+//! the source is printed if no output file specified.
+
+use anyhow::Result;
+use rseip::client::ab_eip::codegen;
+use rseip::precludes::*;
+
+const USAGE: &str = "usage: ab-udt-codegen <host> [-o <output file>] [tag names...]";
+
+#[tokio::main]
+pub async fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let host = match args.next() {
+        Some(v) if !v.starts_with('-') => v,
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+    let mut output = None;
+    let mut tags: Vec<String> = Vec::new();
+    while let Some(arg) = args.next() {
+        if arg == "-o" {
+            match args.next() {
+                Some(path) => output = Some(path),
+                None => {
+                    eprintln!("{}", USAGE);
+                    std::process::exit(2);
+                }
+            }
+        } else {
+            tags.push(arg);
+        }
+    }
+
+    let mut client = AbEipClient::new_host_lookup(host)
+        .await?
+        .with_connection_path(PortSegment::default());
+    let udts = if tags.is_empty() {
+        codegen::collect_all_udts(&mut client).await?
+    } else {
+        let tags: Vec<&str> = tags.iter().map(|v| v.as_str()).collect();
+        codegen::collect_udts_for_tags(&mut client, &tags).await?
+    };
+    client.close().await?;
+
+    let src = codegen::generate(&udts)?;
+    match output {
+        Some(path) => {
+            std::fs::write(&path, src)?;
+            println!("{} UDT(s) written to {}", udts.len(), path);
+        }
+        None => println!("{}", src),
+    }
+    Ok(())
+}
@@ -0,0 +1,30 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+use anyhow::Result;
+use rseip::client::pccc::{PcccAddress, PcccClient};
+use rseip::precludes::*;
+
+#[tokio::main]
+pub async fn main() -> Result<()> {
+    let client = EipClient::new_host_lookup("192.168.0.83").await?;
+    let mut client = PcccClient::new(client);
+
+    let addr: PcccAddress = "N7:0".parse()?;
+    let value: i16 = client.read(&addr).await?;
+    println!("{} = {}", addr, value);
+    client.write(&addr, value.wrapping_add(1)).await?;
+
+    let addr: PcccAddress = "B3:0/5".parse()?;
+    client.write_bit(&addr, true).await?;
+
+    let addr: PcccAddress = "T4:1".parse()?;
+    let value = client.read_value(&addr).await?;
+    println!("{} = {:?}", addr, value);
+
+    client.close().await?;
+    Ok(())
+}
@@ -4,21 +4,45 @@
 // Copyright: 2021, Joylei <leingliu@gmail.com>
 // License: MIT
 
+mod audit;
+mod cache;
+pub mod codegen;
+mod controller;
+mod guard;
 pub(crate) mod interceptor;
+mod micro800;
 mod path;
+pub mod predefined;
+mod reading;
 mod service;
+mod subscription;
 mod symbol;
 pub mod template;
 pub mod value;
 
 use super::*;
+pub use audit::{AuditSink, FileSink, WriteOutcome, WriteRecord};
+pub use cache::{CachedClient, SymbolCache};
+pub use controller::{
+    sync_wall_clocks, AbControllerService, ControllerMode, ControllerStatus, KeySwitch,
+};
 use futures_util::future::BoxFuture;
+pub use guard::{is_write_service, write_attempts, WriteAttempt, WriteGuard, WritePolicy};
+pub use micro800::{
+    Micro800Client, Micro800Connection, Micro800Context, Micro800Driver, MICRO800_CONNECTION_SIZE,
+};
 pub use path::{PathError, PathParser};
+pub use predefined::{Control, Counter, MessageStatus, PredefinedType, Timer};
+pub use reading::{Quality, TagReading};
 use rseip_cip::Status;
 pub use rseip_eip::EipContext;
 pub use service::*;
 use std::net::SocketAddrV4;
-pub use symbol::{GetInstanceAttributeList, SymbolInstance};
+pub use subscription::{ChangeEvent, Subscription, Subscriptions};
+pub use symbol::{
+    GetInstanceAttributeList, ListCursor, SymbolFilter, SymbolInstance, SymbolType,
+    SymbolTypeBuilder,
+};
 pub use template::AbTemplateService;
 use tokio::net::TcpStream;
 pub use value::*;
@@ -0,0 +1,316 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+//! write with verify, and audit trail of writes
+//!
+//! ```rust,ignore
+//! let sink = FileSink::open("writes.log")?;
+//! let record = client.write_verified(EPath::parse_tag("Setpoint")?, 42_i32).await;
+//! sink.record(&record);
+//! if !record.is_verified() {
+//!     println!("write failed: {}", record);
+//! }
+//! ```
+
+use super::*;
+use bytes::BytesMut;
+use rseip_cip::epath::Segment;
+use rseip_eip::ClientCodec;
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    path::Path,
+    sync::{mpsc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// outcome of write with verify
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WriteOutcome {
+    /// value read back matches the written value
+    Verified,
+    /// value read back does not match the written value
+    Mismatch,
+    /// failed to read or write, with error message
+    Failed(String),
+}
+
+impl fmt::Display for WriteOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Verified => write!(f, "verified"),
+            Self::Mismatch => write!(f, "mismatch"),
+            Self::Failed(e) => write!(f, "failed: {}", e),
+        }
+    }
+}
+
+/// record of write with verify
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteRecord {
+    /// tag path
+    pub tag: EPath,
+    /// tag type, `None` if the tag could not be read
+    pub tag_type: Option<TagType>,
+    /// value before the write
+    pub old_value: Option<Bytes>,
+    /// value to write
+    pub new_value: Bytes,
+    /// value read back after the write
+    pub read_back: Option<Bytes>,
+    /// time of the write
+    pub timestamp: SystemTime,
+    /// outcome
+    pub outcome: WriteOutcome,
+}
+
+impl WriteRecord {
+    /// value read back matches the written value?
+    #[inline]
+    pub fn is_verified(&self) -> bool {
+        self.outcome == WriteOutcome::Verified
+    }
+
+    /// tag name, eg. `Program:Main.Recipe[2].Speed`
+    #[inline]
+    pub fn tag_name(&self) -> String {
+        tag_name(&self.tag)
+    }
+}
+
+/// tag name of the path, eg. `Program:Main.Recipe[2].Speed`
+pub(crate) fn tag_name(tag: &EPath) -> String {
+    let mut name = String::new();
+    for item in tag.iter() {
+        match item {
+            Segment::Symbol(v) => {
+                if !name.is_empty() {
+                    name.push('.');
+                }
+                name.push_str(v);
+            }
+            Segment::Element(v) => name.push_str(&format!("[{}]", v)),
+            v => name.push_str(&format!("{:?}", v)),
+        }
+    }
+    name
+}
+
+impl fmt::Display for WriteRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = |v: &Option<Bytes>| match v {
+            Some(v) => v.iter().map(|b| format!("{:02x}", b)).collect(),
+            None => "-".to_owned(),
+        };
+        let millis = self
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .map(|v| v.as_millis())
+            .unwrap_or_default();
+        write!(
+            f,
+            "{} {} {:?} old={} new={} read_back={} {}",
+            millis,
+            self.tag_name(),
+            self.tag_type,
+            hex(&self.old_value),
+            hex(&Some(self.new_value.clone())),
+            hex(&self.read_back),
+            self.outcome
+        )
+    }
+}
+
+/// destination of write records
+pub trait AuditSink: Send + Sync {
+    /// record a write
+    fn record(&self, record: &WriteRecord);
+}
+
+/// callback
+impl<F: Fn(&WriteRecord) + Send + Sync> AuditSink for F {
+    #[inline]
+    fn record(&self, record: &WriteRecord) {
+        self(record)
+    }
+}
+
+/// channel
+impl AuditSink for mpsc::Sender<WriteRecord> {
+    #[inline]
+    fn record(&self, record: &WriteRecord) {
+        if self.send(record.clone()).is_err() {
+            log::warn!("audit - channel closed, record dropped: {}", record);
+        }
+    }
+}
+
+/// bounded channel, records are dropped if the channel is full
+impl AuditSink for mpsc::SyncSender<WriteRecord> {
+    #[inline]
+    fn record(&self, record: &WriteRecord) {
+        if self.try_send(record.clone()).is_err() {
+            log::warn!("audit - channel full or closed, record dropped: {}", record);
+        }
+    }
+}
+
+/// multiple sinks
+impl AuditSink for Vec<Box<dyn AuditSink>> {
+    #[inline]
+    fn record(&self, record: &WriteRecord) {
+        for sink in self {
+            sink.record(record);
+        }
+    }
+}
+
+/// append records to a file, one line per record
+#[derive(Debug)]
+pub struct FileSink {
+    file: Mutex<fs::File>,
+}
+
+impl FileSink {
+    /// open file for appending, create if not exists
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl AuditSink for FileSink {
+    fn record(&self, record: &WriteRecord) {
+        let mut file = match self.file.lock() {
+            Ok(v) => v,
+            Err(e) => e.into_inner(),
+        };
+        if let Err(e) = writeln!(file, "{}", record) {
+            log::warn!("audit - failed to write record: {}, {}", e, record);
+        }
+    }
+}
+
+/// read the tag, write the value, read it back and compare
+pub(crate) async fn write_verified<C, D>(client: &mut C, tag: EPath, value: D) -> WriteRecord
+where
+    C: AbService + ?Sized,
+    D: Encode + Send + Sync,
+{
+    let mut record = WriteRecord {
+        tag: tag.clone(),
+        tag_type: None,
+        old_value: None,
+        new_value: Bytes::new(),
+        read_back: None,
+        timestamp: SystemTime::now(),
+        outcome: WriteOutcome::Verified,
+    };
+    let mut buf = BytesMut::new();
+    if let Err(e) = value.encode(&mut buf, &mut ClientCodec::<ClientError>::new()) {
+        record.outcome = WriteOutcome::Failed(e.to_string());
+        return record;
+    }
+    record.new_value = buf.freeze();
+    if let Err(e) = write_and_verify(client, &mut record).await {
+        record.outcome = WriteOutcome::Failed(e.to_string());
+    }
+    record
+}
+
+async fn write_and_verify<C>(client: &mut C, record: &mut WriteRecord) -> Result<()>
+where
+    C: AbService + ?Sized,
+{
+    let tag = record.tag.clone();
+    let old: TagValue<Bytes> = client.read_tag(tag.clone()).await?;
+    record.tag_type = Some(old.tag_type);
+    let size = old.value.len();
+    let len = record.new_value.len();
+    #[allow(clippy::manual_is_multiple_of)]
+    let mismatch = size == 0 || len == 0 || len % size != 0;
+    if mismatch {
+        record.old_value = Some(old.value);
+        return Err(Error::custom(format!(
+            "write verify - value of {} bytes does not fit elements of {} bytes",
+            len, size
+        )));
+    }
+    let count = match u16::try_from(len / size) {
+        Ok(v) => v,
+        Err(_) => {
+            record.old_value = Some(old.value);
+            return Err(Error::custom(format!(
+                "write verify - too many elements: {}, expected at most {}",
+                len / size,
+                u16::MAX
+            )));
+        }
+    };
+    let old = if count > 1 {
+        client
+            .read_tag::<_, TagValue<Bytes>>((tag.clone(), count))
+            .await?
+    } else {
+        old
+    };
+    record.old_value = Some(old.value);
+
+    record.timestamp = SystemTime::now();
+    client
+        .write_tag(tag.clone(), (old.tag_type, count, record.new_value.clone()))
+        .await?;
+
+    let res: TagValue<Bytes> = client.read_tag((tag, count)).await?;
+    let matched = match old.tag_type {
+        TagType::Bool => {
+            res.value.len() == len
+                && res
+                    .value
+                    .iter()
+                    .zip(record.new_value.iter())
+                    .all(|(a, b)| (*a != 0) == (*b != 0))
+        }
+        _ => res.value == record.new_value,
+    };
+    record.read_back = Some(res.value);
+    if !matched {
+        record.outcome = WriteOutcome::Mismatch;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_record() {
+        let record = WriteRecord {
+            tag: EPath::parse_tag("Recipe[2].Speed").unwrap(),
+            tag_type: Some(TagType::Int),
+            old_value: Some(Bytes::from_static(&[1, 0])),
+            new_value: Bytes::from_static(&[2, 0]),
+            read_back: None,
+            timestamp: UNIX_EPOCH,
+            outcome: WriteOutcome::Mismatch,
+        };
+        assert_eq!(
+            record.to_string(),
+            "0 Recipe[2].Speed Some(Int) old=0100 new=0200 read_back=- mismatch"
+        );
+
+        let (tx, rx) = mpsc::channel();
+        let sinks: Vec<Box<dyn AuditSink>> = vec![Box::new(tx), Box::new(|_: &WriteRecord| {})];
+        sinks.record(&record);
+        assert_eq!(rx.try_recv().unwrap(), record);
+    }
+}
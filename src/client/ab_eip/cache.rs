@@ -0,0 +1,733 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+use super::service::{
+    ab_read_modify_write, ab_read_tag, ab_read_tag_fragmented, ab_write_tag,
+    ab_write_tag_fragmented,
+};
+use super::*;
+use super::{reading::ab_read_tags, symbol::GetInstanceAttributeList};
+use crate::StdResult;
+use bytes::Buf;
+use futures_util::StreamExt;
+use rseip_cip::{
+    epath::Segment, error::cip_error_status, service::MessageService, MessageReplyInterface,
+    MessageRequest, Status,
+};
+use rseip_core::codec::{Decode, Decoder, Encode};
+use std::collections::HashMap;
+
+/// cache of symbol names to symbol instance ids
+#[derive(Debug, Default, Clone)]
+pub struct SymbolCache {
+    items: HashMap<String, (u16, SymbolType)>,
+}
+
+impl SymbolCache {
+    /// new object
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// number of cached symbols
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// no cached symbols?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// remove all cached symbols
+    #[inline]
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    /// cache symbol, ignore case
+    #[inline]
+    pub fn insert(&mut self, name: &str, instance_id: u16, symbol_type: SymbolType) {
+        self.items
+            .insert(name.to_lowercase(), (instance_id, symbol_type));
+    }
+
+    /// get instance id and data type of the symbol, ignore case
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<(u16, SymbolType)> {
+        self.items.get(&name.to_lowercase()).copied()
+    }
+
+    /// map the leading symbolic segment of the path to class 0x6B and the instance id;
+    /// program scoped tags, eg. `Program:MainProgram.tag1`, keep the program segment
+    /// in front of the class segment; member and element segments are kept as they are.
+    ///
+    /// returns `None` if the path does not start with a known symbol.
+    pub fn map_path(&self, path: &EPath) -> Option<EPath> {
+        let (n, instance_id, _) = self.lookup(path)?;
+        let mut res = EPath::new();
+        if n > 1 {
+            res.push(path[0].clone());
+        }
+        let mut res = res.with_class(CLASS_SYMBOL).with_instance(instance_id);
+        for item in path.iter().skip(n) {
+            res.push(item.clone());
+        }
+        Some(res)
+    }
+
+    /// number of leading symbolic segments of the known symbol, with its instance id and type
+    fn lookup(&self, path: &EPath) -> Option<(usize, u16, SymbolType)> {
+        let name = match path.first() {
+            Some(Segment::Symbol(name)) => name,
+            _ => return None,
+        };
+        if name.to_lowercase().starts_with("program:") {
+            let tag = match path.get(1) {
+                Some(Segment::Symbol(tag)) => tag,
+                _ => return None,
+            };
+            let (instance_id, symbol_type) = self.get(&program_key(name, tag))?;
+            return Some((2, instance_id, symbol_type));
+        }
+        let (instance_id, symbol_type) = self.get(name)?;
+        Some((1, instance_id, symbol_type))
+    }
+
+    /// tag type and element size of the path, if the path is a known atomic symbol
+    /// or an element of it
+    fn atomic_type(&self, path: &EPath) -> Option<(TagType, usize)> {
+        let (n, _, symbol_type) = self.lookup(path)?;
+        if !path[n..].iter().all(|v| matches!(v, Segment::Element(_))) {
+            return None;
+        }
+        let tag_type = TagType::from_type_code(symbol_type.type_code()? as u16)?;
+        Some((tag_type, tag_type.size()?))
+    }
+
+    /// template instance id of the path, if the path is a known structure symbol
+    /// or an element of it
+    fn template_id(&self, path: &EPath) -> Option<u16> {
+        let (n, _, symbol_type) = self.lookup(path)?;
+        if !path[n..].iter().all(|v| matches!(v, Segment::Element(_))) {
+            return None;
+        }
+        if symbol_type.is_struct() {
+            symbol_type.instance_id()
+        } else {
+            None
+        }
+    }
+}
+
+/// cache key of program scoped tag, eg. `Program:MainProgram.tag1`
+#[inline]
+fn program_key(program: &str, tag: &str) -> String {
+    format!("{}.{}", program, tag)
+}
+
+/// opt-in layer that addresses tags by symbol instance ids instead of symbolic segments,
+/// which makes request paths much shorter.
+///
+/// The cache is filled from [`AbService::list_tag`] on first use, and refreshed automatically
+/// when the controller reports a path error (general status 0x04 or 0x05), eg. after a download.
+///
+/// Tag types seen by reads are cached as well, so that values can be written with
+/// [`CachedClient::write_value`] without providing the [`TagType`].
+///
+/// ```rust,ignore
+/// let client = AbEipClient::new_host_lookup("192.168.0.83").await?;
+/// let mut client = CachedClient::new(client);
+/// let value: TagValue<i32> = client.read_tag(EPath::parse_tag("test_car1_x")?).await?;
+/// client.write_value(EPath::parse_tag("test_car1_x")?, 10_i32).await?;
+/// ```
+#[derive(Debug)]
+pub struct CachedClient<C> {
+    inner: C,
+    cache: SymbolCache,
+    loaded: bool,
+    /// tag type and element size by tag path
+    types: HashMap<EPath, (TagType, usize)>,
+}
+
+impl<C> CachedClient<C> {
+    /// new object
+    #[inline]
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            cache: Default::default(),
+            loaded: false,
+            types: Default::default(),
+        }
+    }
+
+    /// inner client
+    #[inline]
+    pub fn inner(&mut self) -> &mut C {
+        &mut self.inner
+    }
+
+    /// into inner client
+    #[inline]
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    /// symbol cache
+    #[inline]
+    pub fn cache(&self) -> &SymbolCache {
+        &self.cache
+    }
+
+    /// cached tag type and element size of the tag
+    #[inline]
+    pub fn tag_type(&self, tag: &EPath) -> Option<(TagType, usize)> {
+        self.types.get(tag).copied()
+    }
+
+    /// drop cached symbols and tag types; the cache will be filled again on next request
+    #[inline]
+    pub fn invalidate(&mut self) {
+        self.cache.clear();
+        self.types.clear();
+        self.loaded = false;
+    }
+}
+
+impl<C: MessageService<Error = ClientError>> CachedClient<C> {
+    /// fill the cache from the controller, with the tags scoped to each program
+    pub async fn refresh(&mut self) -> Result<()> {
+        let mut cache = SymbolCache::new();
+        let mut programs = Vec::new();
+        {
+            let stream = GetInstanceAttributeList::new(&mut self.inner).call();
+            futures_util::pin_mut!(stream);
+            while let Some(item) = stream.next().await {
+                let item = item?;
+                if item.name.to_lowercase().starts_with("program:") {
+                    programs.push(item.name.to_string());
+                }
+                cache.insert(&item.name, item.id, item.symbol_type);
+            }
+        }
+        for program in programs {
+            let stream = GetInstanceAttributeList::new(&mut self.inner)
+                .program(&program["program:".len()..])
+                .call();
+            futures_util::pin_mut!(stream);
+            while let Some(item) = stream.next().await {
+                let item = item?;
+                cache.insert(
+                    &program_key(&program, &item.name),
+                    item.id,
+                    item.symbol_type,
+                );
+            }
+        }
+        self.cache = cache;
+        self.types.clear();
+        self.loaded = true;
+        Ok(())
+    }
+
+    /// write value without providing the [`TagType`];
+    /// the tag type is looked up from the cache, or read from the controller once if unknown.
+    ///
+    /// The encoded size of the value must be a multiple of the element size of the tag;
+    /// multiple elements are written for arrays or `Vec`s.
+    ///
+    /// ```rust,ignore
+    /// client.write_value(EPath::parse_tag("test_car1_x")?, 10_i32).await?;
+    /// client.write_value(EPath::parse_tag("test_frag")?, vec![1_i32, 2, 3]).await?;
+    /// ```
+    pub async fn write_value<D>(&mut self, tag: EPath, value: D) -> Result<()>
+    where
+        D: Encode + Send + Sync,
+    {
+        let (tag_type, size) = self.lookup_type(&tag).await?;
+        let bytes_count = value.bytes_count();
+        #[allow(clippy::manual_is_multiple_of)]
+        let mismatch = size == 0 || bytes_count == 0 || bytes_count % size != 0;
+        if mismatch {
+            return Err(Error::custom(format!(
+                "value size mismatch: {} bytes, expected multiple of {} bytes for tag type {:?}",
+                bytes_count, size, tag_type
+            )));
+        }
+        let count = u16::try_from(bytes_count / size).map_err(|_| {
+            ClientError::custom(format!(
+                "too many elements: {}, expected at most {}",
+                bytes_count / size,
+                u16::MAX
+            ))
+        })?;
+        self.write_tag(tag, (tag_type, count, value)).await
+    }
+
+    /// tag type and element size of the tag
+    async fn lookup_type(&mut self, tag: &EPath) -> Result<(TagType, usize)> {
+        if let Some(v) = self.types.get(tag) {
+            return Ok(*v);
+        }
+        if !self.loaded {
+            self.refresh().await?;
+        }
+        let res = if let Some(v) = self.cache.atomic_type(tag) {
+            Some(v)
+        } else if let Some(instance_id) = self.cache.template_id(tag) {
+            let template = self.inner.find_template(instance_id).await?;
+            Some((
+                TagType::Structure(template.handle),
+                template.struct_size as usize,
+            ))
+        } else {
+            None
+        };
+        if let Some(v) = res {
+            self.types.insert(tag.clone(), v);
+            return Ok(v);
+        }
+        // read once, the tag type is cached while reading
+        let _: TagValue<Bytes> = self.read_tag(tag.clone()).await?;
+        self.types
+            .get(tag)
+            .copied()
+            .ok_or_else(|| Error::custom("unknown tag type"))
+    }
+
+    /// map path with the cache, fill the cache if not loaded yet
+    async fn map_path(&mut self, path: &EPath) -> Result<Option<EPath>> {
+        if !self.loaded {
+            self.refresh().await?;
+        }
+        Ok(self.cache.map_path(path))
+    }
+
+    /// read tags in batch with mapped paths, and whether each path was mapped;
+    /// tags are not read if the cache failed to fill
+    async fn read_mapped(&mut self, reqs: &[TagRequest]) -> (Vec<TagReading>, Vec<bool>) {
+        let mut res = Vec::with_capacity(reqs.len());
+        let mut cached = Vec::with_capacity(reqs.len());
+        let mut mapped = Vec::with_capacity(reqs.len());
+        let mut failure: Option<String> = None;
+        for req in reqs.iter() {
+            let path = match failure {
+                Some(ref e) => Err(ClientError::custom(e)),
+                None => self.map_path(&req.tag).await,
+            };
+            match path {
+                Ok(path) => {
+                    cached.push(path.is_some());
+                    mapped.push(TagRequest {
+                        tag: path.unwrap_or_else(|| req.tag.clone()),
+                        count: req.count,
+                    });
+                    res.push(None);
+                }
+                Err(e) => {
+                    let quality = Quality::CommFailure(e.to_string());
+                    failure.get_or_insert_with(|| e.to_string());
+                    cached.push(false);
+                    res.push(Some(TagReading::from_result(
+                        req.tag.clone(),
+                        Err(quality),
+                        Default::default(),
+                    )));
+                }
+            }
+        }
+        let mut readings = ab_read_tags(&mut self.inner, mapped).await.into_iter();
+        let res = res
+            .into_iter()
+            .zip(reqs)
+            .map(|(reading, req)| {
+                let mut reading = reading.or_else(|| readings.next()).unwrap_or_else(|| {
+                    let quality = Quality::CommFailure("no reading".to_owned());
+                    TagReading::from_result(req.tag.clone(), Err(quality), Default::default())
+                });
+                // report with the requested paths
+                reading.tag = req.tag.clone();
+                reading
+            })
+            .collect();
+        (res, cached)
+    }
+}
+
+/// path segment error or path destination unknown
+#[inline]
+fn is_path_error(status: &Status) -> bool {
+    status.general == 0x04 || status.general == 0x05
+}
+
+/// send with the mapped path if available; on path error, refresh the cache and try again
+macro_rules! with_cached_path {
+    ($self:ident, $tag:expr, |$inner:ident, $path:ident| $body:expr) => {{
+        let tag: EPath = $tag;
+        let mut retried = false;
+        loop {
+            let mapped = $self.map_path(&tag).await?;
+            let cached = mapped.is_some();
+            let $path = mapped.unwrap_or_else(|| tag.clone());
+            let $inner = &mut $self.inner;
+            let status = match $body? {
+                Err(status) if cached && !retried && is_path_error(&status) => status,
+                res => break res.map_err(cip_error_status::<ClientError>),
+            };
+            log::debug!("path error with cached symbol, refreshing: {}", status);
+            retried = true;
+            $self.refresh().await?;
+        }
+    }};
+}
+
+#[async_trait::async_trait]
+impl<C: MessageService<Error = ClientError>> AbService for CachedClient<C> {
+    /// Read Tag Service,
+    /// CIP Data Table Read
+    async fn read_tag<'de, P, R>(&mut self, req: P) -> Result<R>
+    where
+        P: Into<TagRequest> + Send + Sync,
+        R: Decode<'de> + 'static,
+    {
+        let req: TagRequest = req.into();
+        let count = req.count;
+        let key = req.tag.clone();
+        let res: TypePeek<R> = with_cached_path!(self, req.tag, |inner, path| {
+            ab_read_tag(inner, (path, count)).await
+        })?;
+        if let Some(tag_type) = res.tag_type {
+            let size = res.data_len / count.max(1) as usize;
+            self.types.insert(key, (tag_type, size));
+        }
+        Ok(res.value)
+    }
+
+    /// Write Tag Service,
+    /// CIP Data Table Write
+    async fn write_tag<D>(&mut self, tag: EPath, value: D) -> Result<()>
+    where
+        D: Encode + Send + Sync,
+    {
+        with_cached_path!(self, tag, |inner, path| {
+            ab_write_tag(inner, path, &value).await
+        })
+    }
+
+    /// Read Tag Fragmented Service
+    async fn read_tag_fragmented(
+        &mut self,
+        req: ReadFragmentedRequest,
+    ) -> Result<(bool, TagValue<Bytes>)> {
+        let ReadFragmentedRequest { tag, count, offset } = req;
+        with_cached_path!(self, tag, |inner, path| {
+            let req = ReadFragmentedRequest::new()
+                .tag(path)
+                .count(count)
+                .offset(offset);
+            ab_read_tag_fragmented(inner, req).await
+        })
+    }
+
+    /// Write Tag Fragmented Service, enables client applications to write to a tag
+    /// in the controller whose data will not fit into a single packet (approximately 500 bytes)
+    async fn write_tag_fragmented<D: Encode + Send + Sync>(
+        &mut self,
+        req: WriteFragmentedRequest<D>,
+    ) -> Result<bool> {
+        let WriteFragmentedRequest {
+            tag,
+            tag_type,
+            count,
+            offset,
+            data,
+        } = req;
+        with_cached_path!(self, tag, |inner, path| {
+            let req = WriteFragmentedRequest::new(&data)
+                .tag(path)
+                .tag_type(tag_type)
+                .count(count)
+                .offset(offset);
+            ab_write_tag_fragmented(inner, req).await
+        })
+    }
+
+    /// Read Modify Write Tag Service, modifies Tag data with individual bit resolution
+    async fn read_modify_write<const N: usize>(
+        &mut self,
+        req: ReadModifyWriteRequest<N>,
+    ) -> Result<()> {
+        let ReadModifyWriteRequest {
+            tag,
+            or_mask,
+            and_mask,
+        } = req;
+        with_cached_path!(self, tag, |inner, path| {
+            let req = ReadModifyWriteRequest::new()
+                .tag(path)
+                .or_mask(or_mask)
+                .and_mask(and_mask);
+            ab_read_modify_write(inner, req).await
+        })
+    }
+
+    /// read tags in batch, with mapped paths if available;
+    /// tags failed with path errors are read again after refreshing the cache
+    async fn read_tags(&mut self, reqs: Vec<TagRequest>) -> Vec<TagReading>
+    where
+        Self: MessageService<Error = ClientError> + Sized,
+    {
+        let (mut res, cached) = self.read_mapped(&reqs).await;
+        let retry: Vec<usize> = (0..res.len())
+            .filter(|&i| {
+                cached[i] && matches!(res[i].quality, Quality::CipError(ref status) if is_path_error(status))
+            })
+            .collect();
+        if retry.is_empty() {
+            return res;
+        }
+        log::debug!("path error with cached symbols, refreshing");
+        self.loaded = false;
+        let reqs: Vec<_> = retry
+            .iter()
+            .map(|&i| TagRequest {
+                tag: reqs[i].tag.clone(),
+                count: reqs[i].count,
+            })
+            .collect();
+        let (readings, _) = self.read_mapped(&reqs).await;
+        for (i, reading) in retry.into_iter().zip(readings) {
+            res[i] = reading;
+        }
+        res
+    }
+
+    /// list tags
+    #[inline]
+    fn list_tag(&mut self) -> GetInstanceAttributeList<'_, Self>
+    where
+        Self: Sized,
+    {
+        GetInstanceAttributeList::new(self)
+    }
+}
+
+#[async_trait::async_trait]
+impl<C: MessageService<Error = ClientError>> MessageService for CachedClient<C> {
+    type Error = ClientError;
+
+    /// send message request as it is, without path mapping
+    #[inline]
+    async fn send<'de, P, D, R>(&mut self, mr: MessageRequest<P, D>) -> Result<R>
+    where
+        P: Encode + Send + Sync,
+        D: Encode + Send + Sync,
+        R: MessageReplyInterface + Decode<'de> + 'static,
+    {
+        self.inner.send(mr).await
+    }
+
+    #[inline]
+    async fn close(&mut self) -> Result<()> {
+        self.inner.close().await
+    }
+
+    #[inline]
+    fn closed(&self) -> bool {
+        self.inner.closed()
+    }
+}
+
+/// decodes the value, and peeks the tag type and data length of the reply
+struct TypePeek<R> {
+    tag_type: Option<TagType>,
+    data_len: usize,
+    value: R,
+}
+
+impl<'de, R: Decode<'de>> Decode<'de> for TypePeek<R> {
+    #[inline]
+    fn decode<D>(mut decoder: D) -> StdResult<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        let chunk = decoder.buf().chunk();
+        let peeked = match chunk {
+            [0xA0, 0x02, a, b, ..] => Some((TagType::Structure(u16::from_le_bytes([*a, *b])), 4)),
+            [a, b, ..] => TagType::from_type_code(u16::from_le_bytes([*a, *b])).map(|v| (v, 2)),
+            _ => None,
+        };
+        let remaining = decoder.remaining();
+        let value = decoder.decode_any()?;
+        Ok(match peeked {
+            Some((tag_type, n)) => Self {
+                tag_type: Some(tag_type),
+                data_len: remaining - n,
+                value,
+            },
+            None => Self {
+                tag_type: None,
+                data_len: 0,
+                value,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_path() {
+        let mut cache = SymbolCache::new();
+        cache.insert("Test_Car1", 0x1234, SymbolType(0xC4));
+        cache.insert("Program:MainProgram", 0x10, SymbolType(0x1068));
+
+        let path = EPath::from_symbol("test_car1")
+            .with_symbol("x")
+            .with_element(2);
+        let res = cache.map_path(&path).unwrap();
+        assert_eq!(
+            res,
+            EPath::new()
+                .with_class(CLASS_SYMBOL)
+                .with_instance(0x1234)
+                .with_symbol("x")
+                .with_element(2)
+        );
+
+        assert!(cache.map_path(&EPath::from_symbol("unknown")).is_none());
+        let path = EPath::from_symbol("Program:MainProgram").with_symbol("tag1");
+        assert!(cache.map_path(&path).is_none());
+        cache.insert("Program:MainProgram.Tag1", 0x20, SymbolType(0xC3));
+        let res = cache.map_path(&path.with_element(1)).unwrap();
+        assert_eq!(
+            res,
+            EPath::from_symbol("Program:MainProgram")
+                .with_class(CLASS_SYMBOL)
+                .with_instance(0x20)
+                .with_element(1)
+        );
+        let path = EPath::from_symbol("program:mainprogram").with_symbol("tag1");
+        assert_eq!(cache.atomic_type(&path), Some((TagType::Int, 2)));
+        let path = EPath::new().with_class(CLASS_SYMBOL).with_instance(1);
+        assert!(cache.map_path(&path).is_none());
+    }
+
+    /// message service that fails every request as sent
+    struct Unreachable;
+
+    #[async_trait::async_trait]
+    impl MessageService for Unreachable {
+        type Error = ClientError;
+
+        async fn send<'de, P, D, R>(&mut self, _mr: MessageRequest<P, D>) -> Result<R>
+        where
+            P: Encode + Send + Sync,
+            D: Encode + Send + Sync,
+            R: MessageReplyInterface + Decode<'de> + 'static,
+        {
+            Err(Error::custom("sent"))
+        }
+
+        async fn close(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn closed(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn test_guarded_write() {
+        crate::test::block_on(async {
+            let policy = WritePolicy::default().deny("*_setpoint");
+            let mut client = CachedClient::new(WriteGuard::new(Unreachable, policy));
+            client.cache.insert("speed", 1, SymbolType(0xC4));
+            client.loaded = true;
+
+            // mapped to symbol instance id, tag name unknown to the guard
+            let err = client
+                .write_tag(
+                    EPath::from_symbol("speed"),
+                    TagValue {
+                        tag_type: TagType::Dint,
+                        value: 1_i32,
+                    },
+                )
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("not permitted"));
+
+            // not mapped, checked by tag name
+            let err = client
+                .write_tag(
+                    EPath::from_symbol("other"),
+                    TagValue {
+                        tag_type: TagType::Dint,
+                        value: 1_i32,
+                    },
+                )
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("sent"));
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_read_tags_failure() {
+        crate::test::block_on(async {
+            let mut client = CachedClient::new(Unreachable);
+            let tags = vec![
+                EPath::from_symbol("a").into(),
+                EPath::from_symbol("b").into(),
+            ];
+            let res = client.read_tags(tags).await;
+            assert_eq!(res.len(), 2);
+            assert_eq!(res[1].tag, EPath::from_symbol("b"));
+            assert!(matches!(res[1].quality, Quality::CommFailure(ref e) if e.contains("sent")));
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_type_peek() {
+        use rseip_core::codec::LittleEndianDecoder;
+
+        let buf = Bytes::from_static(&[0xC4, 0x00, 1, 0, 0, 0, 2, 0, 0, 0]);
+        let res: TypePeek<TagValue<Bytes>> =
+            TypePeek::decode(LittleEndianDecoder::<ClientError>::new(buf)).unwrap();
+        assert_eq!(res.tag_type, Some(TagType::Dint));
+        assert_eq!(res.data_len, 8);
+
+        let buf = Bytes::from_static(&[0xA0, 0x02, 0x34, 0x12, 1, 2, 3]);
+        let res: TypePeek<TagValue<Bytes>> =
+            TypePeek::decode(LittleEndianDecoder::<ClientError>::new(buf)).unwrap();
+        assert_eq!(res.tag_type, Some(TagType::Structure(0x1234)));
+        assert_eq!(res.data_len, 3);
+    }
+
+    #[test]
+    fn test_atomic_type() {
+        let mut cache = SymbolCache::new();
+        cache.insert("a", 1, SymbolType::builder().atomic(0xC3).finish());
+        cache.insert("b", 2, SymbolType::builder().structure(0x0F83).finish());
+        let path = EPath::from_symbol("a").with_element(1);
+        assert_eq!(cache.atomic_type(&path), Some((TagType::Int, 2)));
+        assert!(cache
+            .atomic_type(&EPath::from_symbol("a").with_symbol("x"))
+            .is_none());
+        assert!(cache.atomic_type(&EPath::from_symbol("b")).is_none());
+        assert_eq!(cache.template_id(&EPath::from_symbol("b")), Some(0x0F83));
+    }
+}
@@ -0,0 +1,560 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+//! Rust code generator for UDTs from live templates.
+//!
+//! Templates are fetched from the controller with [`AbTemplateService`], nested structures
+//! are walked recursively, then Rust source is generated with [`generate`].
+//! Each UDT becomes a struct with `Encode` and `Decode` implemented by member offsets.
+//!
+//! ```rust,ignore
+//! let udts = codegen::collect_all_udts(&mut client).await?;
+//! let src = codegen::generate(&udts)?;
+//! std::fs::write("src/udt.rs", src)?;
+//! ```
+//!
+//! The generated code depends on crates `rseip` and `bytes`.
+
+use super::{
+    predefined::PredefinedType, symbol::GetInstanceAttributeList, template::AbTemplateService,
+    SymbolType,
+};
+use crate::{cip::data_type::ElementaryType, ClientError, Result};
+use core::fmt::Write;
+use futures_util::StreamExt;
+use rseip_cip::service::MessageService;
+use rseip_core::Error;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// prefix of hidden host members of BOOLs
+const HIDDEN_PREFIX: &str = "ZZZZZZZZZZ";
+
+/// UDT definition, from template instance and template definition
+#[derive(Debug, Clone)]
+pub struct UdtDefinition {
+    /// template name
+    pub name: String,
+    /// template instance id
+    pub instance_id: u16,
+    /// structure size in bytes
+    pub struct_size: u32,
+    /// members ordered by offset
+    pub members: Vec<UdtMember>,
+}
+
+/// UDT member definition
+#[derive(Debug, Clone)]
+pub struct UdtMember {
+    /// member name
+    pub name: String,
+    /// array size if array, or bit location if BOOL
+    pub array_size: u16,
+    /// member type info
+    pub type_info: SymbolType,
+    /// offset position of data bytes
+    pub offset: u32,
+}
+
+impl UdtMember {
+    /// hidden host member of BOOLs
+    #[inline]
+    pub fn is_hidden(&self) -> bool {
+        self.name.starts_with(HIDDEN_PREFIX)
+    }
+}
+
+/// fetch UDT definitions of the template instances, and nested UDTs;
+/// predefined structures with typed Rust structs are not included
+pub async fn collect_udts<C>(
+    client: &mut C,
+    instance_ids: impl IntoIterator<Item = u16>,
+) -> Result<Vec<UdtDefinition>>
+where
+    C: MessageService<Error = ClientError>,
+{
+    let mut queue: VecDeque<u16> = instance_ids.into_iter().collect();
+    let mut visited = HashSet::new();
+    let mut res = Vec::new();
+    while let Some(instance_id) = queue.pop_front() {
+        if !visited.insert(instance_id) || PredefinedType::from_instance_id(instance_id).is_some() {
+            continue;
+        }
+        let template = client.find_template(instance_id).await?;
+        let mut req = client.read_template(&template);
+        let def = req.call().await?;
+        let mut members: Vec<_> = def
+            .values()
+            .map(|item| UdtMember {
+                name: item.name.to_owned(),
+                array_size: item.array_size,
+                type_info: item.type_info,
+                offset: item.offset,
+            })
+            .collect();
+        members.sort_by_key(|item| (item.offset, item.array_size));
+        for item in members.iter() {
+            if let Some(id) = item.type_info.instance_id() {
+                queue.push_back(id);
+            }
+        }
+        res.push(UdtDefinition {
+            name: def.name().to_owned(),
+            instance_id,
+            struct_size: template.struct_size,
+            members,
+        });
+    }
+    Ok(res)
+}
+
+/// fetch UDT definitions of all structure tags of the controller
+pub async fn collect_all_udts<C>(client: &mut C) -> Result<Vec<UdtDefinition>>
+where
+    C: MessageService<Error = ClientError>,
+{
+    let ids = struct_instance_ids(client, |_| true).await?;
+    collect_udts(client, ids).await
+}
+
+/// fetch UDT definitions of the specified tags, ignore case
+pub async fn collect_udts_for_tags<C>(client: &mut C, tags: &[&str]) -> Result<Vec<UdtDefinition>>
+where
+    C: MessageService<Error = ClientError>,
+{
+    let ids = struct_instance_ids(client, |name| {
+        tags.iter().any(|tag| tag.eq_ignore_ascii_case(name))
+    })
+    .await?;
+    collect_udts(client, ids).await
+}
+
+async fn struct_instance_ids<C, F>(client: &mut C, f: F) -> Result<Vec<u16>>
+where
+    C: MessageService<Error = ClientError>,
+    F: Fn(&str) -> bool,
+{
+    let mut ids = Vec::new();
+    let stream = GetInstanceAttributeList::new(client)
+        .skip_system(true)
+        .call();
+    futures_util::pin_mut!(stream);
+    while let Some(item) = stream.next().await {
+        let item = item?;
+        if let Some(id) = item.symbol_type.instance_id() {
+            if f(&item.name) && !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    Ok(ids)
+}
+
+/// generate Rust source for the UDTs
+///
+/// nested UDTs must be included, except predefined structures with typed Rust structs
+pub fn generate(udts: &[UdtDefinition]) -> Result<String> {
+    let types: HashMap<u16, (String, u32)> = udts
+        .iter()
+        .map(|udt| (udt.instance_id, (type_name(&udt.name), udt.struct_size)))
+        .collect();
+    let mut out = String::new();
+    out.push_str("// generated by rseip codegen, do not edit\n\n");
+    out.push_str("#![allow(clippy::all)]\n\n");
+    out.push_str("use bytes::{Buf, BufMut, BytesMut};\n");
+    out.push_str("use rseip::codec::{Decode, Decoder, Encode, Encoder, LittleEndianDecoder};\n");
+    out.push_str("use rseip::Error as _;\n");
+    for udt in udts {
+        out.push('\n');
+        generate_udt(&mut out, udt, &types)?;
+    }
+    Ok(out)
+}
+
+/// member type for code generation
+enum MemberType {
+    Bool(u16),
+    Atomic(&'static str, usize),
+    Struct(String, u32),
+}
+
+impl MemberType {
+    fn new(member: &UdtMember, types: &HashMap<u16, (String, u32)>) -> Result<Self> {
+        let type_info = member.type_info;
+        if let Some(id) = type_info.instance_id() {
+            if let Some(v) = PredefinedType::from_instance_id(id) {
+                let name = match v {
+                    PredefinedType::Control => "rseip::client::ab_eip::Control",
+                    PredefinedType::Counter => "rseip::client::ab_eip::Counter",
+                    PredefinedType::Timer => "rseip::client::ab_eip::Timer",
+                };
+                return Ok(Self::Struct(name.to_owned(), v.struct_size() as u32));
+            }
+            return match types.get(&id) {
+                Some((name, size)) => Ok(Self::Struct(name.clone(), *size)),
+                None => Err(Error::custom(format!(
+                    "codegen - template {:#x} of member {} not found",
+                    id, member.name
+                ))),
+            };
+        }
+        let code = type_info.type_code().unwrap_or_default();
+        let res = match ElementaryType::from_code(code.into()) {
+            Some(ElementaryType::Bool) => Self::Bool(member.array_size),
+            Some(ty) => Self::Atomic(ty.rust_type(), ty.size()),
+            None => {
+                return Err(Error::custom(format!(
+                    "codegen - unsupported type code {:#x} of member {}",
+                    code, member.name
+                )))
+            }
+        };
+        Ok(res)
+    }
+
+    fn rust_type(&self) -> &str {
+        match self {
+            Self::Bool(_) => "bool",
+            Self::Atomic(name, _) => name,
+            Self::Struct(name, _) => name,
+        }
+    }
+}
+
+/// get/put method suffix of atomic types
+fn buf_method(ty: &str, size: usize) -> String {
+    if size == 1 {
+        ty.to_owned()
+    } else {
+        format!("{}_le", ty)
+    }
+}
+
+fn generate_udt(
+    out: &mut String,
+    udt: &UdtDefinition,
+    types: &HashMap<u16, (String, u32)>,
+) -> Result<()> {
+    let name = type_name(&udt.name);
+    let mut fields = Vec::new();
+    for member in udt.members.iter().filter(|v| !v.is_hidden()) {
+        let ty = MemberType::new(member, types)?;
+        // BOOLs use array size as bit location
+        let array_size = match ty {
+            MemberType::Bool(_) => 0,
+            _ => member.array_size as usize,
+        };
+        fields.push((
+            field_name(&member.name),
+            member.offset as usize,
+            array_size,
+            ty,
+        ));
+    }
+
+    // struct
+    let _ = writeln!(
+        out,
+        "/// UDT `{}`, template instance id {:#x}",
+        udt.name, udt.instance_id
+    );
+    // arrays default to the array size, so that default values can be encoded
+    let has_array = fields.iter().any(|(_, _, array_size, _)| *array_size > 0);
+    if has_array {
+        out.push_str("#[derive(Debug, Clone, PartialEq)]\n");
+    } else {
+        out.push_str("#[derive(Debug, Default, Clone, PartialEq)]\n");
+    }
+    let _ = writeln!(out, "pub struct {} {{", name);
+    for (field, _, array_size, ty) in fields.iter() {
+        if *array_size > 0 {
+            let _ = writeln!(out, "    pub {}: Vec<{}>,", field, ty.rust_type());
+        } else {
+            let _ = writeln!(out, "    pub {}: {},", field, ty.rust_type());
+        }
+    }
+    out.push_str("}\n\n");
+
+    let _ = writeln!(out, "impl {} {{", name);
+    out.push_str("    /// structure size in bytes\n");
+    let _ = writeln!(out, "    pub const SIZE: usize = {};", udt.struct_size);
+    out.push_str("}\n\n");
+
+    if has_array {
+        let _ = writeln!(out, "impl Default for {} {{", name);
+        out.push_str("    fn default() -> Self {\n        Self {\n");
+        for (field, _, array_size, _) in fields.iter() {
+            if *array_size > 0 {
+                let _ = writeln!(
+                    out,
+                    "            {}: vec![Default::default(); {}],",
+                    field, array_size
+                );
+            } else {
+                let _ = writeln!(out, "            {}: Default::default(),", field);
+            }
+        }
+        out.push_str("        }\n    }\n}\n\n");
+    }
+
+    // decode
+    let _ = writeln!(out, "impl<'de> Decode<'de> for {} {{", name);
+    out.push_str("    fn decode<D>(mut decoder: D) -> Result<Self, D::Error>\n");
+    out.push_str("    where\n        D: Decoder<'de>,\n    {\n");
+    out.push_str("        decoder.ensure_size(Self::SIZE)?;\n");
+    out.push_str("        let buf = decoder.buf_mut().copy_to_bytes(Self::SIZE);\n");
+    out.push_str("        Ok(Self {\n");
+    for (field, offset, array_size, ty) in fields.iter() {
+        let expr = match (ty, *array_size) {
+            (MemberType::Bool(bit), _) => format!("buf[{}] & (1 << {}) != 0", offset, bit),
+            (MemberType::Atomic(t, size), 0) => {
+                format!("(&buf[{}..]).get_{}()", offset, buf_method(t, *size))
+            }
+            (MemberType::Atomic(t, size), n) => format!(
+                "(0..{}).map(|i| (&buf[{} + i * {}..]).get_{}()).collect()",
+                n,
+                offset,
+                size,
+                buf_method(t, *size)
+            ),
+            (MemberType::Struct(t, size), 0) => format!(
+                "{}::decode(LittleEndianDecoder::<D::Error>::new(buf.slice({}..{})))?",
+                t,
+                offset,
+                offset + *size as usize
+            ),
+            (MemberType::Struct(t, size), n) => format!(
+                "(0..{n})\n                .map(|i| {{\n                    let start = {offset} + i * {size};\n                    {t}::decode(LittleEndianDecoder::<D::Error>::new(buf.slice(start..start + {size})))\n                }})\n                .collect::<Result<Vec<_>, _>>()?",
+                n = n,
+                offset = offset,
+                size = size,
+                t = t
+            ),
+        };
+        let _ = writeln!(out, "            {}: {},", field, expr);
+    }
+    out.push_str("        })\n    }\n}\n\n");
+
+    // encode
+    let _ = writeln!(out, "impl Encode for {} {{", name);
+    // the encoder is only used by structure members
+    let encoder = if fields
+        .iter()
+        .any(|(_, _, _, ty)| matches!(ty, MemberType::Struct(..)))
+    {
+        "encoder"
+    } else {
+        "_encoder"
+    };
+    let _ = writeln!(
+        out,
+        "    fn encode_by_ref<A: Encoder>(\n        &self,\n        buf: &mut BytesMut,\n        {}: &mut A,\n    ) -> Result<(), A::Error> {{",
+        encoder
+    );
+    // arrays of other lengths are rejected, not truncated or padded
+    for (field, _, array_size, _) in fields.iter() {
+        if *array_size > 0 {
+            let _ = writeln!(
+                out,
+                "        if self.{f}.len() != {n} {{\n            return Err(A::Error::invalid_length(self.{f}.len(), {n}));\n        }}",
+                f = field,
+                n = array_size
+            );
+        }
+    }
+    out.push_str("        let mut data = vec![0_u8; Self::SIZE];\n");
+    for (field, offset, array_size, ty) in fields.iter() {
+        match (ty, *array_size) {
+            (MemberType::Bool(bit), _) => {
+                let _ = writeln!(
+                    out,
+                    "        if self.{} {{\n            data[{}] |= 1 << {};\n        }}",
+                    field, offset, bit
+                );
+            }
+            (MemberType::Atomic(t, size), 0) => {
+                let _ = writeln!(
+                    out,
+                    "        (&mut data[{}..]).put_{}(self.{});",
+                    offset,
+                    buf_method(t, *size),
+                    field
+                );
+            }
+            (MemberType::Atomic(t, size), _) => {
+                let _ = writeln!(
+                    out,
+                    "        for (i, v) in self.{}.iter().enumerate() {{\n            (&mut data[{} + i * {}..]).put_{}(*v);\n        }}",
+                    field,
+                    offset,
+                    size,
+                    buf_method(t, *size)
+                );
+            }
+            (MemberType::Struct(_, size), 0) => {
+                let _ = writeln!(
+                    out,
+                    "        {{\n            let mut tmp = BytesMut::new();\n            self.{}.encode_by_ref(&mut tmp, encoder)?;\n            let len = tmp.len().min({});\n            data[{}..{} + len].copy_from_slice(&tmp[..len]);\n        }}",
+                    field, size, offset, offset
+                );
+            }
+            (MemberType::Struct(_, size), _) => {
+                let _ = writeln!(
+                    out,
+                    "        for (i, v) in self.{}.iter().enumerate() {{\n            let mut tmp = BytesMut::new();\n            v.encode_by_ref(&mut tmp, encoder)?;\n            let start = {} + i * {};\n            let len = tmp.len().min({});\n            data[start..start + len].copy_from_slice(&tmp[..len]);\n        }}",
+                    field, offset, size, size
+                );
+            }
+        }
+    }
+    out.push_str("        buf.put_slice(&data);\n        Ok(())\n    }\n\n");
+    out.push_str("    fn bytes_count(&self) -> usize {\n        Self::SIZE\n    }\n}\n");
+    Ok(())
+}
+
+/// CamelCase type name
+fn type_name(name: &str) -> String {
+    let mut res = String::new();
+    for part in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = part.chars();
+        if let Some(c) = chars.next() {
+            res.push(c.to_ascii_uppercase());
+            res.extend(chars);
+        }
+    }
+    if res.is_empty() || res.starts_with(|c: char| c.is_ascii_digit()) {
+        res.insert(0, 'T');
+    }
+    res
+}
+
+/// snake_case field name
+fn field_name(name: &str) -> String {
+    let mut res = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if !c.is_ascii_alphanumeric() {
+            if !res.is_empty() && !res.ends_with('_') {
+                res.push('_');
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_ascii_uppercase() && prev_lower {
+            res.push('_');
+        }
+        prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        res.push(c.to_ascii_lowercase());
+    }
+    if res.is_empty() || res.starts_with(|c: char| c.is_ascii_digit()) {
+        res.insert(0, '_');
+    }
+    match res.as_str() {
+        "self" | "super" | "crate" | "_" => res.push('_'),
+        "as" | "async" | "await" | "box" | "break" | "const" | "continue" | "dyn" | "else"
+        | "enum" | "extern" | "false" | "fn" | "for" | "if" | "impl" | "in" | "let" | "loop"
+        | "match" | "mod" | "move" | "mut" | "pub" | "ref" | "return" | "static" | "struct"
+        | "trait" | "true" | "type" | "unsafe" | "use" | "where" | "while" | "yield" => {
+            res.insert_str(0, "r#")
+        }
+        _ => {}
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names() {
+        assert_eq!(type_name("my_udt"), "MyUdt");
+        assert_eq!(type_name("MotorData"), "MotorData");
+        assert_eq!(field_name("MotorSpeed"), "motor_speed");
+        assert_eq!(field_name("ACC"), "acc");
+        assert_eq!(field_name("Type"), "r#type");
+        assert_eq!(field_name("x1Pos"), "x1_pos");
+    }
+
+    /// UDTs of the generated code in `tests/codegen/generated.rs`
+    fn udts() -> Vec<UdtDefinition> {
+        let inner = UdtDefinition {
+            name: "Inner".to_owned(),
+            instance_id: 0x101,
+            struct_size: 4,
+            members: vec![UdtMember {
+                name: "Value".to_owned(),
+                array_size: 0,
+                type_info: SymbolType::builder().atomic(0xC4).finish(),
+                offset: 0,
+            }],
+        };
+        let outer = UdtDefinition {
+            name: "Outer".to_owned(),
+            instance_id: 0x100,
+            struct_size: 36,
+            members: vec![
+                UdtMember {
+                    name: "ZZZZZZZZZZOuter0".to_owned(),
+                    array_size: 0,
+                    type_info: SymbolType::builder().atomic(0xC2).finish(),
+                    offset: 0,
+                },
+                UdtMember {
+                    name: "Run".to_owned(),
+                    array_size: 3,
+                    type_info: SymbolType::builder().atomic(0xC1).finish(),
+                    offset: 0,
+                },
+                UdtMember {
+                    name: "Speeds".to_owned(),
+                    array_size: 2,
+                    type_info: SymbolType::builder().atomic(0xCA).finish(),
+                    offset: 4,
+                },
+                UdtMember {
+                    name: "Inner".to_owned(),
+                    array_size: 0,
+                    type_info: SymbolType::builder().structure(0x101).finish(),
+                    offset: 12,
+                },
+                UdtMember {
+                    name: "Delay".to_owned(),
+                    array_size: 0,
+                    type_info: SymbolType::builder().structure(0xF83).finish(),
+                    offset: 16,
+                },
+                UdtMember {
+                    name: "Stages".to_owned(),
+                    array_size: 2,
+                    type_info: SymbolType::builder().structure(0x101).finish(),
+                    offset: 28,
+                },
+            ],
+        };
+        vec![outer, inner]
+    }
+
+    #[test]
+    fn test_generate() {
+        // the generated code is compiled and round-tripped by `tests/codegen.rs`
+        let src = generate(&udts()).unwrap();
+        assert_eq!(src, include_str!("../../../tests/codegen/generated.rs"));
+        assert!(!src.contains("zzzzzzzzzz"));
+
+        let res = generate(&[UdtDefinition {
+            name: "Bad".to_owned(),
+            instance_id: 0x102,
+            struct_size: 4,
+            members: vec![UdtMember {
+                name: "Missing".to_owned(),
+                array_size: 0,
+                type_info: SymbolType::builder().structure(0x103).finish(),
+                offset: 0,
+            }],
+        }]);
+        assert!(res.is_err());
+    }
+}
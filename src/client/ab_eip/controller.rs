@@ -0,0 +1,290 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+use crate::{
+    cip::{
+        epath::EPath,
+        identity::{Identity, IdentityStatus},
+        service::{CommonServices, MessageService},
+    },
+    ClientError, StdResult,
+};
+use futures_util::future;
+use rseip_core::{
+    codec::{Decode, Decoder},
+    Error,
+};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// vendor specific wall clock time object
+const CLASS_WALL_CLOCK: u16 = 0x8B;
+/// wall clock: current UTC value, microseconds since 1970-01-01
+const ATTR_CURRENT_UTC_VALUE: u16 = 0x0B;
+
+/// operating mode of Logix controller, from extended device status of Identity Object
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControllerMode {
+    /// run mode
+    Run,
+    /// program mode
+    Program,
+    /// other modes, vendor specific extended device status
+    Other(u8),
+}
+
+impl ControllerMode {
+    #[inline]
+    fn from_status(status: IdentityStatus) -> Self {
+        match status.extended_status() {
+            0b0110 => Self::Run,
+            0b0111 => Self::Program,
+            v => Self::Other(v),
+        }
+    }
+}
+
+/// keyswitch position of Logix controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeySwitch {
+    /// RUN
+    Run,
+    /// PROG
+    Program,
+    /// REM
+    Remote,
+}
+
+impl KeySwitch {
+    /// keyswitch position from bits 12-13 of status word, `None` if not exposed
+    #[inline]
+    fn from_status(status: IdentityStatus) -> Option<Self> {
+        match (status.0 >> 12) & 0b11 {
+            0b01 => Some(Self::Run),
+            0b10 => Some(Self::Program),
+            0b11 => Some(Self::Remote),
+            _ => None,
+        }
+    }
+}
+
+/// controller state, with identity and decoded status word
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControllerStatus {
+    /// identity object
+    pub identity: Identity,
+    /// operating mode
+    pub mode: ControllerMode,
+    /// keyswitch position, `None` if not exposed
+    pub keyswitch: Option<KeySwitch>,
+}
+
+impl ControllerStatus {
+    /// from identity object
+    #[inline]
+    pub fn new(identity: Identity) -> Self {
+        let status = identity.status;
+        Self {
+            identity,
+            mode: ControllerMode::from_status(status),
+            keyswitch: KeySwitch::from_status(status),
+        }
+    }
+
+    /// raw status word
+    #[inline]
+    pub fn status(&self) -> IdentityStatus {
+        self.identity.status
+    }
+
+    /// the controller has an owner
+    #[inline]
+    pub fn owned(&self) -> bool {
+        self.identity.status.owned()
+    }
+
+    /// the controller has been configured
+    #[inline]
+    pub fn configured(&self) -> bool {
+        self.identity.status.configured()
+    }
+
+    /// minor recoverable or unrecoverable fault
+    #[inline]
+    pub fn minor_fault(&self) -> bool {
+        let status = self.identity.status;
+        status.minor_recoverable_fault() || status.minor_unrecoverable_fault()
+    }
+
+    /// major recoverable or unrecoverable fault
+    #[inline]
+    pub fn major_fault(&self) -> bool {
+        let status = self.identity.status;
+        status.major_recoverable_fault() || status.major_unrecoverable_fault()
+    }
+
+    /// major fault, the controller is not running
+    #[inline]
+    pub fn is_faulted(&self) -> bool {
+        self.major_fault()
+    }
+}
+
+/// controller related operations
+#[async_trait::async_trait]
+pub trait AbControllerService {
+    /// read controller state in one request
+    ///
+    /// ```rust,ignore
+    /// let status = client.controller_status().await?;
+    /// if status.is_faulted() {
+    ///     println!("PLC faulted: {:?}", status);
+    /// }
+    /// ```
+    async fn controller_status(&mut self) -> Result<ControllerStatus, ClientError>;
+
+    /// read controller wall clock time, UTC
+    async fn read_wall_clock(&mut self) -> Result<SystemTime, ClientError>;
+
+    /// set controller wall clock time, UTC; local time of the controller follows
+    /// its time zone offset
+    async fn set_wall_clock(&mut self, time: SystemTime) -> Result<(), ClientError>;
+
+    /// set controller wall clock time to the host clock
+    #[inline]
+    async fn sync_wall_clock(&mut self) -> Result<(), ClientError> {
+        self.set_wall_clock(SystemTime::now()).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: MessageService<Error = ClientError>> AbControllerService for T {
+    async fn controller_status(&mut self) -> Result<ControllerStatus, ClientError> {
+        let identity = self.get_identity().await?;
+        Ok(ControllerStatus::new(identity))
+    }
+
+    async fn read_wall_clock(&mut self) -> Result<SystemTime, ClientError> {
+        let path = EPath::default()
+            .with_class(CLASS_WALL_CLOCK)
+            .with_instance(0x01);
+        let res: AttrReply<u64> = self
+            .get_attribute_list(path, &[ATTR_CURRENT_UTC_VALUE])
+            .await?;
+        Ok(UNIX_EPOCH + Duration::from_micros(res.0))
+    }
+
+    async fn set_wall_clock(&mut self, time: SystemTime) -> Result<(), ClientError> {
+        let micros = time
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| ClientError::custom("wall clock - time before 1970-01-01"))?
+            .as_micros() as u64;
+        let path = EPath::default()
+            .with_class(CLASS_WALL_CLOCK)
+            .with_instance(0x01);
+        let _: AttrReply<()> = self
+            .set_attribute_list(path, (1_u16, ATTR_CURRENT_UTC_VALUE, micros))
+            .await?;
+        Ok(())
+    }
+}
+
+/// set wall clock time of the controllers to the host clock concurrently;
+/// results are in the same order of the clients
+///
+/// ```rust,ignore
+/// let results = sync_wall_clocks(clients.iter_mut()).await;
+/// ```
+pub async fn sync_wall_clocks<'a, T, I>(clients: I) -> Vec<Result<(), ClientError>>
+where
+    T: AbControllerService + Send + 'a,
+    I: IntoIterator<Item = &'a mut T>,
+{
+    future::join_all(clients.into_iter().map(|c| c.sync_wall_clock())).await
+}
+
+/// reply of single attribute for Get_Attribute_List/Set_Attribute_List
+struct AttrReply<R>(R);
+
+impl<'de, R: Decode<'de>> Decode<'de> for AttrReply<R> {
+    #[inline]
+    fn decode<D>(mut decoder: D) -> StdResult<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        decoder.ensure_size(6)?;
+        let count = decoder.decode_u16();
+        if count != 1 {
+            return Err(Error::custom(
+                "attribute - unexpected count of items returned",
+            ));
+        }
+        let id = decoder.decode_u16();
+        let status = decoder.decode_u16();
+        if status != 0 {
+            return Err(Error::custom(format!(
+                "attribute - bad attribute[{}] status: {:#0x}",
+                id, status
+            )));
+        }
+        Ok(Self(decoder.decode_any()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cip::Revision;
+
+    #[test]
+    fn test_controller_status() {
+        let identity = Identity {
+            vendor_id: 1,
+            device_type: 0x0E,
+            product_code: 0x5B,
+            revision: Revision {
+                major: 32,
+                minor: 11,
+            },
+            status: IdentityStatus(0x3065),
+            serial_number: 0x12345678,
+            product_name: "1756-L83E/B".to_owned(),
+            state: None,
+        };
+        let status = ControllerStatus::new(identity);
+        assert_eq!(status.mode, ControllerMode::Run);
+        assert_eq!(status.keyswitch, Some(KeySwitch::Remote));
+        assert!(status.owned() && status.configured());
+        assert!(!status.is_faulted() && !status.minor_fault());
+
+        let mut identity = status.identity.clone();
+        identity.status = IdentityStatus(0x0474);
+        let status = ControllerStatus::new(identity);
+        assert_eq!(status.mode, ControllerMode::Program);
+        assert_eq!(status.keyswitch, None);
+        assert!(status.is_faulted());
+
+        let mut identity = status.identity.clone();
+        identity.status = IdentityStatus(0x3025);
+        let status = ControllerStatus::new(identity);
+        assert_eq!(status.mode, ControllerMode::Other(0b0010));
+    }
+
+    #[test]
+    fn test_attr_reply() {
+        use bytes::Bytes;
+        use rseip_core::codec::LittleEndianDecoder;
+
+        let buf = Bytes::from_static(&[
+            1, 0, 0x0B, 0, 0, 0, 0x40, 0x42, 0x0F, 0, 0, 0, 0, 0,
+        ]);
+        let res = AttrReply::<u64>::decode(LittleEndianDecoder::<ClientError>::new(buf)).unwrap();
+        assert_eq!(res.0, 1_000_000);
+
+        let buf = Bytes::from_static(&[1, 0, 0x06, 0, 0x0E, 0]);
+        let res = AttrReply::<()>::decode(LittleEndianDecoder::<ClientError>::new(buf));
+        assert!(res.is_err());
+    }
+}
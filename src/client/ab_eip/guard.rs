@@ -0,0 +1,631 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+//! reject write services before they reach the wire
+//!
+//! ```rust,ignore
+//! let policy = WritePolicy::default()
+//!     .allow("Analytics_*")
+//!     .deny("*Setpoint*");
+//! let mut client = WriteGuard::new(client, policy).with_context("analytics-job");
+//! // rejected and logged, nothing is sent
+//! client.write_tag(EPath::parse_tag("Line1_Speed")?, value).await?;
+//! ```
+
+use super::service::{
+    ab_read_modify_write, ab_read_tag, ab_read_tag_fragmented, ab_write_tag,
+    ab_write_tag_fragmented,
+};
+use super::symbol::{glob_match, GetInstanceAttributeList};
+use super::*;
+use bytes::BytesMut;
+use rseip_cip::{
+    error::cip_error_status, file::CLASS_FILE, service::MessageService, MessageReplyInterface,
+    MessageRequest,
+};
+use rseip_core::codec::{Decode, Encode};
+use rseip_eip::ClientCodec;
+
+/// Set_Attributes_All
+const SERVICE_SET_ATTRIBUTES_ALL: u8 = 0x02;
+/// Set_Attribute_List
+const SERVICE_SET_ATTRIBUTE_LIST: u8 = 0x04;
+/// Reset
+const SERVICE_RESET: u8 = 0x05;
+/// Start
+const SERVICE_START: u8 = 0x06;
+/// Stop
+const SERVICE_STOP: u8 = 0x07;
+/// Create
+const SERVICE_CREATE: u8 = 0x08;
+/// Delete
+const SERVICE_DELETE: u8 = 0x09;
+/// Multiple_Service_Packet
+const SERVICE_MULTIPLE_SERVICE_PACKET: u8 = 0x0A;
+/// Set_Attribute_Single
+const SERVICE_SET_ATTRIBUTE_SINGLE: u8 = 0x10;
+/// Execute PCCC
+const SERVICE_EXECUTE_PCCC: u8 = 0x4B;
+/// PCCC command: typed commands
+const PCCC_CMD_TYPED: u8 = 0x0F;
+/// File object: Initiate_Download
+const SERVICE_FILE_INITIATE_DOWNLOAD: u8 = 0x4C;
+/// File object: Initiate_Partial_Write
+const SERVICE_FILE_INITIATE_PARTIAL_WRITE: u8 = 0x4E;
+/// File object: Download_Transfer
+const SERVICE_FILE_DOWNLOAD_TRANSFER: u8 = 0x50;
+/// File object: Clear_File
+const SERVICE_FILE_CLEAR: u8 = 0x51;
+
+/// is the service code a write service? File object services are classified by
+/// [`write_attempts`] with the class of the path
+#[inline]
+pub fn is_write_service(service_code: u8) -> bool {
+    matches!(
+        service_code,
+        SERVICE_WRITE_TAG
+            | SERVICE_WRITE_TAG_FRAGMENTED
+            | SERVICE_READ_MODIFY_WRITE_TAG
+            | SERVICE_SET_ATTRIBUTES_ALL
+            | SERVICE_SET_ATTRIBUTE_LIST
+            | SERVICE_SET_ATTRIBUTE_SINGLE
+            | SERVICE_RESET
+            | SERVICE_START
+            | SERVICE_STOP
+            | SERVICE_CREATE
+            | SERVICE_DELETE
+    )
+}
+
+/// is the request a write, by the service code and the class of the encoded path?
+/// File object services share codes with tag services
+fn is_write_request(service_code: u8, path: &[u8]) -> bool {
+    let class_id = match path {
+        [0x20, v, ..] => Some(*v as u16),
+        [0x21, _, lo, hi, ..] => Some(u16::from_le_bytes([*lo, *hi])),
+        _ => None,
+    };
+    match class_id {
+        Some(CLASS_FILE) => {
+            matches!(
+                service_code,
+                SERVICE_FILE_INITIATE_DOWNLOAD
+                    | SERVICE_FILE_INITIATE_PARTIAL_WRITE
+                    | SERVICE_FILE_DOWNLOAD_TRANSFER
+                    | SERVICE_FILE_CLEAR
+            ) || (is_write_service(service_code) && !is_tag_write_service(service_code))
+        }
+        _ => is_write_service(service_code),
+    }
+}
+
+/// is the service code a tag write service?
+#[inline]
+fn is_tag_write_service(service_code: u8) -> bool {
+    matches!(
+        service_code,
+        SERVICE_WRITE_TAG | SERVICE_WRITE_TAG_FRAGMENTED | SERVICE_READ_MODIFY_WRITE_TAG
+    )
+}
+
+/// write request found in a message request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteAttempt {
+    /// service code
+    pub service_code: u8,
+    /// tag name if the path is symbolic, eg. `Program:Main.Recipe[2].Speed`
+    pub tag: Option<String>,
+}
+
+/// policy of write services
+///
+/// - read only: all writes rejected
+/// - denylist: writes to tags matching any pattern rejected
+/// - allowlist: if not empty, only writes to tags matching any pattern permitted;
+///   writes without a tag name, eg. Set_Attribute_Single, are rejected
+/// - tag writes without a tag name, eg. by symbol instance id as [`CachedClient`] does,
+///   are rejected if any pattern is set; wrap the [`CachedClient`] by the guard instead
+///
+/// [`CachedClient`]: super::CachedClient
+///
+/// patterns support `*` and `?`, ignore case
+#[derive(Debug, Default, Clone)]
+pub struct WritePolicy {
+    read_only: bool,
+    allow: Vec<String>,
+    deny: Vec<String>,
+}
+
+impl WritePolicy {
+    /// reject all writes
+    #[inline]
+    pub fn read_only() -> Self {
+        Self {
+            read_only: true,
+            ..Default::default()
+        }
+    }
+
+    /// add pattern of tags permitted to write
+    #[inline]
+    pub fn allow(mut self, pattern: impl Into<String>) -> Self {
+        self.allow.push(pattern.into());
+        self
+    }
+
+    /// add pattern of tags not permitted to write
+    #[inline]
+    pub fn deny(mut self, pattern: impl Into<String>) -> Self {
+        self.deny.push(pattern.into());
+        self
+    }
+
+    /// is it read only?
+    #[inline]
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// is the write permitted?
+    pub fn permits(&self, attempt: &WriteAttempt) -> bool {
+        if self.read_only {
+            return false;
+        }
+        let matches = |patterns: &[String], tag: &str| {
+            patterns
+                .iter()
+                .any(|p| glob_match(p.as_bytes(), tag.as_bytes()))
+        };
+        match attempt.tag {
+            Some(ref tag) if matches(&self.deny, tag) => false,
+            Some(ref tag) if !self.allow.is_empty() => matches(&self.allow, tag),
+            None if is_tag_write_service(attempt.service_code) => {
+                self.allow.is_empty() && self.deny.is_empty()
+            }
+            None => self.allow.is_empty(),
+            _ => true,
+        }
+    }
+}
+
+/// wrapper of message service, rejects write services by [`WritePolicy`];
+/// requests in Multiple_Service_Packet and PCCC writes are inspected too
+#[derive(Debug)]
+pub struct WriteGuard<C> {
+    inner: C,
+    policy: WritePolicy,
+    context: Option<String>,
+}
+
+impl<C> WriteGuard<C> {
+    /// new guard
+    #[inline]
+    pub fn new(inner: C, policy: WritePolicy) -> Self {
+        Self {
+            inner,
+            policy,
+            context: None,
+        }
+    }
+
+    /// calling context for logging of rejected writes, eg. job name
+    #[inline]
+    pub fn with_context(mut self, context: impl Into<String>) -> Self {
+        self.context = Some(context.into());
+        self
+    }
+
+    /// change calling context
+    #[inline]
+    pub fn set_context(&mut self, context: Option<String>) {
+        self.context = context;
+    }
+
+    /// current policy
+    #[inline]
+    pub fn policy(&self) -> &WritePolicy {
+        &self.policy
+    }
+
+    /// inner client
+    #[inline]
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// into inner client
+    #[inline]
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    /// check the request against the policy
+    fn check<P: Encode, D: Encode>(&self, mr: &MessageRequest<P, D>) -> Result<()> {
+        for attempt in write_attempts(mr)? {
+            if !self.policy.permits(&attempt) {
+                let context = self.context.as_deref().unwrap_or("-");
+                let tag = attempt.tag.as_deref().unwrap_or("<unnamed>");
+                log::warn!(
+                    "write guard [{}] - rejected service {:#04x} to {}",
+                    context,
+                    attempt.service_code,
+                    tag
+                );
+                return Err(ClientError::custom(format!(
+                    "write guard - service {:#04x} to {} not permitted",
+                    attempt.service_code, tag
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl<C: MessageService<Error = ClientError>> AbService for WriteGuard<C> {
+    /// Read Tag Service,
+    /// CIP Data Table Read
+    #[inline]
+    async fn read_tag<'de, P, R>(&mut self, req: P) -> Result<R>
+    where
+        P: Into<TagRequest> + Send + Sync,
+        R: Decode<'de> + 'static,
+    {
+        ab_read_tag(self, req).await?.map_err(cip_error_status)
+    }
+
+    /// Write Tag Service,
+    /// CIP Data Table Write
+    #[inline]
+    async fn write_tag<D>(&mut self, tag: EPath, value: D) -> Result<()>
+    where
+        D: Encode + Send + Sync,
+    {
+        ab_write_tag(self, tag, value)
+            .await?
+            .map_err(cip_error_status)
+    }
+
+    /// Read Tag Fragmented Service
+    #[inline]
+    async fn read_tag_fragmented(
+        &mut self,
+        req: ReadFragmentedRequest,
+    ) -> Result<(bool, TagValue<Bytes>)> {
+        ab_read_tag_fragmented(self, req)
+            .await?
+            .map_err(cip_error_status)
+    }
+
+    /// Write Tag Fragmented Service
+    #[inline]
+    async fn write_tag_fragmented<D: Encode + Send + Sync>(
+        &mut self,
+        req: WriteFragmentedRequest<D>,
+    ) -> Result<bool> {
+        ab_write_tag_fragmented(self, req)
+            .await?
+            .map_err(cip_error_status)
+    }
+
+    /// Read Modify Write Tag Service
+    #[inline]
+    async fn read_modify_write<const N: usize>(
+        &mut self,
+        req: ReadModifyWriteRequest<N>,
+    ) -> Result<()> {
+        ab_read_modify_write(self, req)
+            .await?
+            .map_err(cip_error_status)
+    }
+
+    /// list tags
+    #[inline]
+    fn list_tag(&mut self) -> GetInstanceAttributeList<'_, Self>
+    where
+        Self: Sized,
+    {
+        GetInstanceAttributeList::new(self)
+    }
+}
+
+#[async_trait::async_trait]
+impl<C: MessageService<Error = ClientError>> MessageService for WriteGuard<C> {
+    type Error = ClientError;
+
+    /// send message request if permitted
+    #[inline]
+    async fn send<'de, P, D, R>(&mut self, mr: MessageRequest<P, D>) -> Result<R>
+    where
+        P: Encode + Send + Sync,
+        D: Encode + Send + Sync,
+        R: MessageReplyInterface + Decode<'de> + 'static,
+    {
+        self.check(&mr)?;
+        self.inner.send(mr).await
+    }
+
+    #[inline]
+    async fn close(&mut self) -> Result<()> {
+        self.inner.close().await
+    }
+
+    #[inline]
+    fn closed(&self) -> bool {
+        self.inner.closed()
+    }
+}
+
+/// find write requests in the message request
+pub fn write_attempts<P: Encode, D: Encode>(
+    mr: &MessageRequest<P, D>,
+) -> Result<Vec<WriteAttempt>> {
+    let service_code = mr.service_code;
+    let mut res = Vec::new();
+    if service_code == SERVICE_MULTIPLE_SERVICE_PACKET || service_code == SERVICE_EXECUTE_PCCC {
+        let data = encode(&mr.data)?;
+        if service_code == SERVICE_EXECUTE_PCCC {
+            if is_pccc_write(&data) {
+                res.push(WriteAttempt {
+                    service_code,
+                    tag: None,
+                });
+            }
+        } else {
+            multiple_service_attempts(&data, &mut res)?;
+        }
+    } else {
+        let path = encode(&mr.path)?;
+        if is_write_request(service_code, &path) {
+            res.push(WriteAttempt {
+                service_code,
+                tag: tag_name(&path),
+            });
+        }
+    }
+    Ok(res)
+}
+
+#[inline]
+fn encode<T: Encode>(item: &T) -> Result<Bytes> {
+    let mut buf = BytesMut::new();
+    item.encode_by_ref(&mut buf, &mut ClientCodec::<ClientError>::new())?;
+    Ok(buf.freeze())
+}
+
+/// requests embedded in Multiple_Service_Packet, including Execute PCCC and
+/// nested Multiple_Service_Packet requests
+fn multiple_service_attempts(data: &[u8], res: &mut Vec<WriteAttempt>) -> Result<()> {
+    let invalid = || ClientError::custom("write guard - invalid multiple service packet");
+    let read_u16 = |pos: usize| {
+        data.get(pos..pos + 2)
+            .map(|v| u16::from_le_bytes([v[0], v[1]]) as usize)
+    };
+    let count = read_u16(0).ok_or_else(invalid)?;
+    for i in 0..count {
+        let offset = read_u16(2 + i * 2).ok_or_else(invalid)?;
+        let end = if i + 1 < count {
+            read_u16(2 + (i + 1) * 2).ok_or_else(invalid)?
+        } else {
+            data.len()
+        };
+        let service_code = *data.get(offset).ok_or_else(invalid)?;
+        let path_len = *data.get(offset + 1).ok_or_else(invalid)? as usize * 2;
+        let path = data
+            .get(offset + 2..offset + 2 + path_len)
+            .ok_or_else(invalid)?;
+        let request_data = data.get(offset + 2 + path_len..end).ok_or_else(invalid)?;
+        match service_code {
+            SERVICE_MULTIPLE_SERVICE_PACKET => multiple_service_attempts(request_data, res)?,
+            SERVICE_EXECUTE_PCCC if is_pccc_write(request_data) => res.push(WriteAttempt {
+                service_code,
+                tag: None,
+            }),
+            SERVICE_EXECUTE_PCCC => {}
+            _ if is_write_request(service_code, path) => res.push(WriteAttempt {
+                service_code,
+                tag: tag_name(path),
+            }),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// is the PCCC request a write? only known reads are allowed, typed commands (CMD 0x0F)
+/// with functions word range read (0x01), typed read (0x68) and protected typed logical read
+/// with 2 or 3 address fields (0xA1, 0xA2); anything else is taken as a write
+#[inline]
+fn is_pccc_write(data: &[u8]) -> bool {
+    // requestor id, CMD, STS, TNS, FNC
+    let len = match data.first() {
+        Some(len) => *len as usize,
+        None => return true,
+    };
+    let cmd = data.get(len);
+    let fnc = data.get(len + 4);
+    !matches!(
+        (cmd, fnc),
+        (Some(&PCCC_CMD_TYPED), Some(0x01 | 0x68 | 0xA1 | 0xA2))
+    )
+}
+
+/// tag name from encoded path, `None` if the path is not symbolic
+fn tag_name(path: &[u8]) -> Option<String> {
+    let mut name = String::new();
+    let mut rest = path;
+    loop {
+        match rest {
+            [] => break,
+            [0x91, len, tail @ ..] => {
+                let len = *len as usize;
+                let symbol = tail.get(..len)?;
+                if !name.is_empty() {
+                    name.push('.');
+                }
+                name.push_str(core::str::from_utf8(symbol).ok()?);
+                rest = tail.get(len + len % 2..)?;
+            }
+            [0x28, v, tail @ ..] => {
+                name.push_str(&format!("[{}]", v));
+                rest = tail;
+            }
+            [0x29, _, lo, hi, tail @ ..] => {
+                name.push_str(&format!("[{}]", u16::from_le_bytes([*lo, *hi])));
+                rest = tail;
+            }
+            [0x2A, _, a, b, c, d, tail @ ..] => {
+                name.push_str(&format!("[{}]", u32::from_le_bytes([*a, *b, *c, *d])));
+                rest = tail;
+            }
+            _ => return None,
+        }
+    }
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_name() {
+        let path = EPath::parse_tag("Program:Main.Recipe[2].Speed").unwrap();
+        let mr = MessageRequest::new(SERVICE_WRITE_TAG, path, ());
+        let res = write_attempts(&mr).unwrap();
+        assert_eq!(res[0].tag.as_deref(), Some("Program:Main.Recipe[2].Speed"));
+
+        let path = EPath::default().with_class(0x8B).with_instance(1);
+        let mr = MessageRequest::new(SERVICE_SET_ATTRIBUTE_LIST, path, ());
+        let res = write_attempts(&mr).unwrap();
+        assert_eq!(res[0].tag, None);
+
+        let path = EPath::parse_tag("a").unwrap();
+        let mr = MessageRequest::new(SERVICE_READ_TAG, path, 1_u16);
+        assert!(write_attempts(&mr).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_file_services() {
+        let path = EPath::default().with_class(CLASS_FILE).with_instance(0xC8);
+        let mr = MessageRequest::new(SERVICE_FILE_DOWNLOAD_TRANSFER, path.clone(), ());
+        let res = write_attempts(&mr).unwrap();
+        assert_eq!(res[0].tag, None);
+        assert!(!WritePolicy::read_only().permits(&res[0]));
+
+        let mr = MessageRequest::new(SERVICE_FILE_INITIATE_DOWNLOAD, path.clone(), ());
+        assert_eq!(write_attempts(&mr).unwrap().len(), 1);
+
+        // Initiate_Partial_Read
+        let mr = MessageRequest::new(SERVICE_WRITE_TAG, path, ());
+        assert!(write_attempts(&mr).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_multiple_service_packet() {
+        // read a, write b
+        let data: &[u8] = &[
+            2, 0, 6, 0, 14, 0, // count, offsets
+            0x4C, 2, 0x91, 1, b'a', 0, 1, 0, // read a
+            0x4D, 2, 0x91, 1, b'b', 0, 0xC3, 0, 1, 0, 5, 0, // write b
+        ];
+        let path = EPath::default().with_class(0x02).with_instance(1);
+        let mr = MessageRequest::new(SERVICE_MULTIPLE_SERVICE_PACKET, path, data);
+        let res = write_attempts(&mr).unwrap();
+        assert_eq!(
+            res,
+            vec![WriteAttempt {
+                service_code: SERVICE_WRITE_TAG,
+                tag: Some("b".to_owned())
+            }]
+        );
+    }
+
+    #[test]
+    fn test_pccc() {
+        let pccc = |cmd: u8, fnc: u8| -> Vec<u8> {
+            vec![
+                7, 0xFF, 0xFF, 0x50, 0x49, 0x53, 0x52, cmd, 0, 1, 0, fnc, 2, 7, 0x89,
+            ]
+        };
+        let path = EPath::default().with_class(0x67).with_instance(1);
+        let attempts = |cmd: u8, fnc: u8| {
+            let mr = MessageRequest::new(SERVICE_EXECUTE_PCCC, path.clone(), pccc(cmd, fnc));
+            write_attempts(&mr).unwrap().len()
+        };
+        assert_eq!(attempts(0x0F, 0xA2), 0);
+        assert_eq!(attempts(0x0F, 0x68), 0);
+        assert_eq!(attempts(0x0F, 0xAA), 1);
+        // protected typed logical write with 2 address fields
+        assert_eq!(attempts(0x0F, 0xA9), 1);
+        // unprotected write
+        assert_eq!(attempts(0x08, 0xA2), 1);
+        let mr = MessageRequest::new(SERVICE_EXECUTE_PCCC, path, ());
+        assert_eq!(write_attempts(&mr).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_multiple_service_packet_embedded() {
+        // Execute PCCC, read then write
+        let mut data: Vec<u8> = vec![2, 0, 6, 0, 27, 0];
+        for fnc in [0xA2, 0xAA] {
+            data.extend_from_slice(&[0x4B, 2, 0x20, 0x67, 0x24, 0x01]);
+            data.extend_from_slice(&[
+                7, 0xFF, 0xFF, 0x50, 0x49, 0x53, 0x52, 0x0F, 0, 1, 0, fnc,
+            ]);
+            data.extend_from_slice(&[2, 7, 0x89]);
+        }
+        let path = EPath::default().with_class(0x02).with_instance(1);
+        let mr = MessageRequest::new(SERVICE_MULTIPLE_SERVICE_PACKET, path.clone(), data.clone());
+        assert_eq!(
+            write_attempts(&mr).unwrap(),
+            vec![WriteAttempt {
+                service_code: SERVICE_EXECUTE_PCCC,
+                tag: None
+            }]
+        );
+
+        // nested Multiple_Service_Packet
+        let mut nested: Vec<u8> = vec![1, 0, 4, 0, 0x0A, 2, 0x20, 0x02, 0x24, 0x01];
+        nested.extend_from_slice(&data);
+        let mr = MessageRequest::new(SERVICE_MULTIPLE_SERVICE_PACKET, path, nested);
+        assert_eq!(write_attempts(&mr).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_policy() {
+        let attempt = |tag: Option<&str>| WriteAttempt {
+            service_code: SERVICE_WRITE_TAG,
+            tag: tag.map(|v| v.to_owned()),
+        };
+        let policy = WritePolicy::default();
+        assert!(policy.permits(&attempt(Some("a"))));
+        assert!(policy.permits(&attempt(None)));
+
+        let policy = WritePolicy::read_only();
+        assert!(!policy.permits(&attempt(Some("a"))));
+
+        let policy = WritePolicy::default()
+            .allow("analytics_*")
+            .deny("*_setpoint");
+        assert!(policy.permits(&attempt(Some("Analytics_Count"))));
+        assert!(!policy.permits(&attempt(Some("analytics_setpoint"))));
+        assert!(!policy.permits(&attempt(Some("Line1_Speed"))));
+        assert!(!policy.permits(&attempt(None)));
+
+        let policy = WritePolicy::default().deny("Line1_*");
+        assert!(!policy.permits(&attempt(Some("line1_speed"))));
+        assert!(!policy.permits(&attempt(None)));
+        assert!(policy.permits(&WriteAttempt {
+            service_code: SERVICE_SET_ATTRIBUTE_SINGLE,
+            tag: None
+        }));
+    }
+}
@@ -0,0 +1,222 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+//! Micro800 controllers, eg. Micro820, Micro850, Micro870.
+//!
+//! Micro800 controllers do not route messages; requests are sent to the
+//! message router directly, without Unconnected Send.
+//!
+//! ```rust,ignore
+//! let mut client = Micro800Client::new_host_lookup("192.168.0.83").await?;
+//! let value: TagValue<u32> = client.read_tag(EPath::parse_tag("my_udint")?).await?;
+//!
+//! let mut conn =
+//!     Micro800Connection::new_host_lookup("192.168.0.83", Micro800Driver::open_options())
+//!         .await?;
+//! ```
+
+use super::*;
+use crate::{
+    adapters::Service,
+    cip::{
+        connection::{ForwardCloseReply, ForwardCloseRequest, ForwardOpenReply},
+        epath::Segment,
+        service::request::UnconnectedSend,
+    },
+};
+use bytes::BytesMut;
+use rseip_cip::{codec::decode::message_reply, MessageReplyInterface};
+use rseip_core::codec::{Decode, Encode};
+use rseip_eip::ClientCodec;
+
+/// max connection size of Micro800 controllers
+pub const MICRO800_CONNECTION_SIZE: u16 = 500;
+
+/// Micro800 Client
+pub type Micro800Client = Client<Micro800Driver>;
+
+/// Micro800 Connection
+pub type Micro800Connection = Connection<Micro800Driver>;
+
+/// Micro800 driver
+pub struct Micro800Driver;
+
+impl Micro800Driver {
+    /// options to open connection with Micro800 controllers:
+    /// message router without routing, and compatible connection size
+    #[inline]
+    pub fn open_options() -> OpenOptions {
+        OpenOptions::default()
+            .connection_path(EPath::from(vec![Segment::Class(2), Segment::Instance(1)]))
+            .connection_size(MICRO800_CONNECTION_SIZE)
+    }
+}
+
+impl Driver for Micro800Driver {
+    type Endpoint = SocketAddrV4;
+    type Service = Micro800Context;
+
+    #[inline]
+    fn build_service(addr: Self::Endpoint) -> BoxFuture<'static, Result<Self::Service>> {
+        let fut = EipDriver::build_service(addr);
+        Box::pin(async move { Ok(Micro800Context(fut.await?)) })
+    }
+}
+
+/// EIP context for Micro800 controllers, rejects unsupported requests early
+#[derive(Debug)]
+pub struct Micro800Context(EipContext<TcpStream, ClientError>);
+
+impl Micro800Context {
+    /// inner EIP context
+    #[inline]
+    pub fn inner(&self) -> &EipContext<TcpStream, ClientError> {
+        &self.0
+    }
+}
+
+#[async_trait::async_trait]
+impl Service for Micro800Context {
+    #[inline]
+    fn is_open(&self) -> bool {
+        self.0.is_open()
+    }
+
+    #[inline]
+    async fn open(&mut self) -> Result<()> {
+        self.0.open().await
+    }
+
+    #[inline]
+    async fn close(&mut self) -> Result<()> {
+        Service::close(&mut self.0).await
+    }
+
+    #[inline]
+    async fn heartbeat(&mut self) -> Result<()> {
+        self.0.heartbeat().await
+    }
+
+    /// send request to message router directly, routing is not supported
+    async fn unconnected_send<'de, CP, P, D, R>(
+        &mut self,
+        request: UnconnectedSend<CP, MessageRequest<P, D>>,
+    ) -> Result<R>
+    where
+        CP: Encode + Send + Sync,
+        P: Encode + Send + Sync,
+        D: Encode + Send + Sync,
+        R: MessageReplyInterface + Decode<'de> + 'static,
+    {
+        if request.path.bytes_count() > 0 {
+            return Err(Error::custom(
+                "Micro800 - routing not supported, connection path should be empty",
+            ));
+        }
+        let mr = request.data;
+        verify_request(&mr)?;
+        let service_code = mr.service_code;
+        let cpf = self.0.send_rrdata(mr).await?;
+        let reply: R = message_reply::decode_unconnected_send(cpf)?;
+        reply.expect_service::<ClientError>(service_code + REPLY_MASK)?;
+        Ok(reply)
+    }
+
+    #[inline]
+    async fn connected_send<'de, P, D, R>(
+        &mut self,
+        connection_id: u32,
+        sequence_number: u16,
+        request: MessageRequest<P, D>,
+    ) -> Result<R>
+    where
+        P: Encode + Send + Sync,
+        D: Encode + Send + Sync,
+        R: MessageReplyInterface + Decode<'de> + 'static,
+    {
+        verify_request(&request)?;
+        self.0
+            .connected_send(connection_id, sequence_number, request)
+            .await
+    }
+
+    #[inline]
+    async fn forward_open<P>(&mut self, request: OpenOptions<P>) -> Result<ForwardOpenReply>
+    where
+        P: Encode + Send + Sync,
+    {
+        self.0.forward_open(request).await
+    }
+
+    #[inline]
+    async fn forward_close<P>(
+        &mut self,
+        request: ForwardCloseRequest<P>,
+    ) -> Result<ForwardCloseReply>
+    where
+        P: Encode + Send + Sync,
+    {
+        self.0.forward_close(request).await
+    }
+}
+
+/// reject services or objects not supported by Micro800 controllers
+fn verify_request<P: Encode, D>(mr: &MessageRequest<P, D>) -> Result<()> {
+    let service = match mr.service_code {
+        SERVICE_READ_TAG_FRAGMENTED => Some("Read Tag Fragmented"),
+        SERVICE_WRITE_TAG_FRAGMENTED => Some("Write Tag Fragmented"),
+        SERVICE_READ_MODIFY_WRITE_TAG => Some("Read Modify Write Tag"),
+        _ => None,
+    };
+    if let Some(service) = service {
+        return Err(Error::custom(format!(
+            "Micro800 - service not supported: {}",
+            service
+        )));
+    }
+    let mut buf = BytesMut::new();
+    mr.path
+        .encode_by_ref(&mut buf, &mut ClientCodec::<ClientError>::new())?;
+    if leading_class(&buf) == Some(CLASS_TEMPLATE) {
+        return Err(Error::custom(
+            "Micro800 - service not supported: template object",
+        ));
+    }
+    Ok(())
+}
+
+/// class id of the leading logical segment of the encoded path
+#[inline]
+fn leading_class(path: &[u8]) -> Option<u16> {
+    match path {
+        [0x20, v, ..] => Some(*v as u16),
+        [0x21, _, lo, hi, ..] => Some(u16::from_le_bytes([*lo, *hi])),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_request() {
+        let tag = EPath::parse_tag("my_tag").unwrap();
+        let mr = MessageRequest::new(SERVICE_READ_TAG, tag.clone(), 1_u16);
+        assert!(verify_request(&mr).is_ok());
+        let mr = MessageRequest::new(SERVICE_READ_TAG_FRAGMENTED, tag, [1_u32, 0]);
+        assert!(verify_request(&mr).is_err());
+
+        let path = EPath::default()
+            .with_class(CLASS_TEMPLATE)
+            .with_instance(0x0123);
+        let mr = MessageRequest::new(SERVICE_TEMPLATE_READ, path, ());
+        assert!(verify_request(&mr).is_err());
+        let path = EPath::default().with_class(CLASS_SYMBOL).with_instance(0);
+        let mr = MessageRequest::new(0x55, path, ());
+        assert!(verify_request(&mr).is_ok());
+    }
+}
@@ -0,0 +1,303 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+//! predefined structure types of Logix controllers;
+//! status bits are packed in the first DINT of the structures.
+//!
+//! PID is not provided: besides the status bits, it's mostly REAL parameters
+//! followed by internal data without a documented layout;
+//! read the members of interest instead, eg. `Loop1.SP`, `Loop1.PV`.
+//!
+//! ```rust,ignore
+//! let (tag_type, timer): (TagType, Timer) = client.read_tag(tag.clone()).await?;
+//! if timer.dn {
+//!     let timer = Timer { acc: 0, ..timer };
+//!     client.write_tag(tag, (tag_type, 1_u16, timer)).await?;
+//! }
+//! ```
+
+use super::symbol::SymbolType;
+
+/// template instance id of CONTROL
+pub const TEMPLATE_CONTROL: u16 = 0x0F81;
+/// template instance id of COUNTER
+pub const TEMPLATE_COUNTER: u16 = 0x0F82;
+/// template instance id of TIMER
+pub const TEMPLATE_TIMER: u16 = 0x0F83;
+
+/// predefined structure types with typed Rust structs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PredefinedType {
+    /// CONTROL, see [`Control`]
+    Control,
+    /// COUNTER, see [`Counter`]
+    Counter,
+    /// TIMER, see [`Timer`]
+    Timer,
+}
+
+impl PredefinedType {
+    /// from template instance id
+    #[inline]
+    pub fn from_instance_id(instance_id: u16) -> Option<Self> {
+        match instance_id {
+            TEMPLATE_CONTROL => Some(Self::Control),
+            TEMPLATE_COUNTER => Some(Self::Counter),
+            TEMPLATE_TIMER => Some(Self::Timer),
+            _ => None,
+        }
+    }
+
+    /// from symbol type, only if it's predefined struct
+    #[inline]
+    pub fn from_symbol_type(symbol_type: SymbolType) -> Option<Self> {
+        if symbol_type.is_predefined() {
+            symbol_type.instance_id().and_then(Self::from_instance_id)
+        } else {
+            None
+        }
+    }
+
+    /// template instance id
+    #[inline]
+    pub fn instance_id(&self) -> u16 {
+        match self {
+            Self::Control => TEMPLATE_CONTROL,
+            Self::Counter => TEMPLATE_COUNTER,
+            Self::Timer => TEMPLATE_TIMER,
+        }
+    }
+
+    /// structure size in bytes
+    #[inline]
+    pub fn struct_size(&self) -> usize {
+        12
+    }
+}
+
+/// implements Encode & Decode for predefined structures of status word + 2 integers
+/// of the same size, eg. DINTs of Logix or INTs of PCCC data table files
+macro_rules! impl_predefined {
+    ($ty:ident: $word:ty, [$($bit:ident: $pos:expr),+], $a:ident, $b:ident) => {
+        impl $ty {
+            /// packed status word, the raw status word with the bit members patched
+            #[inline]
+            pub fn status_word(&self) -> $word {
+                let mut word = self.raw;
+                $(
+                    if self.$bit {
+                        word |= 1 << $pos;
+                    } else {
+                        word &= !(1 << $pos);
+                    }
+                )+
+                word
+            }
+
+            /// from packed status word, other members default
+            #[inline]
+            pub fn from_status_word(word: $word) -> Self {
+                Self {
+                    $($bit: word & (1 << $pos) != 0,)+
+                    raw: word,
+                    ..Default::default()
+                }
+            }
+        }
+
+        impl $crate::codec::Encode for $ty {
+            #[inline]
+            fn encode_by_ref<A: $crate::codec::Encoder>(
+                &self,
+                buf: &mut bytes::BytesMut,
+                encoder: &mut A,
+            ) -> $crate::StdResult<(), A::Error> {
+                encoder.encode(self.status_word(), buf)?;
+                encoder.encode(self.$a, buf)?;
+                encoder.encode(self.$b, buf)?;
+                Ok(())
+            }
+
+            #[inline]
+            fn bytes_count(&self) -> usize {
+                3 * core::mem::size_of::<$word>()
+            }
+        }
+
+        impl<'de> $crate::codec::Decode<'de> for $ty {
+            #[inline]
+            fn decode<D>(mut decoder: D) -> $crate::StdResult<Self, D::Error>
+            where
+                D: $crate::codec::Decoder<'de>,
+            {
+                decoder.ensure_size(3 * core::mem::size_of::<$word>())?;
+                let word: $word = decoder.decode_any()?;
+                let $a = decoder.decode_any()?;
+                let $b = decoder.decode_any()?;
+                Ok(Self {
+                    $a,
+                    $b,
+                    ..Self::from_status_word(word)
+                })
+            }
+        }
+    };
+}
+
+pub(crate) use impl_predefined;
+
+/// TIMER
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Timer {
+    /// enable bit
+    pub en: bool,
+    /// timing bit
+    pub tt: bool,
+    /// done bit
+    pub dn: bool,
+    /// preset value
+    pub pre: i32,
+    /// accumulated value
+    pub acc: i32,
+    /// raw status word as read, bits without members are kept when writing back
+    pub raw: u32,
+}
+
+impl_predefined!(Timer: u32, [en: 31, tt: 30, dn: 29], pre, acc);
+
+/// COUNTER
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Counter {
+    /// count up enable bit
+    pub cu: bool,
+    /// count down enable bit
+    pub cd: bool,
+    /// done bit
+    pub dn: bool,
+    /// overflow bit
+    pub ov: bool,
+    /// underflow bit
+    pub un: bool,
+    /// preset value
+    pub pre: i32,
+    /// accumulated value
+    pub acc: i32,
+    /// raw status word as read, bits without members are kept when writing back
+    pub raw: u32,
+}
+
+impl_predefined!(Counter: u32, [cu: 31, cd: 30, dn: 29, ov: 28, un: 27], pre, acc);
+
+/// CONTROL
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Control {
+    /// enable bit
+    pub en: bool,
+    /// enable unload bit
+    pub eu: bool,
+    /// done bit
+    pub dn: bool,
+    /// empty bit
+    pub em: bool,
+    /// error bit
+    pub er: bool,
+    /// unload bit
+    pub ul: bool,
+    /// inhibit bit
+    pub inh: bool,
+    /// found bit
+    pub fd: bool,
+    /// length of the array
+    pub len: i32,
+    /// position in the array
+    pub pos: i32,
+    /// raw status word as read, bits without members are kept when writing back
+    pub raw: u32,
+}
+
+impl_predefined!(
+    Control: u32,
+    [en: 31, eu: 30, dn: 29, em: 28, er: 27, ul: 26, inh: 25, fd: 24],
+    len,
+    pos
+);
+
+/// status of MESSAGE, see [`AbService::read_message_status`](super::AbService::read_message_status)
+///
+/// MESSAGE tags cannot be read as a whole, the status members are read one by one.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MessageStatus {
+    /// enable bit
+    pub en: bool,
+    /// enable waiting bit
+    pub ew: bool,
+    /// start bit
+    pub st: bool,
+    /// done bit
+    pub dn: bool,
+    /// error bit
+    pub er: bool,
+    /// timed out bit
+    pub to: bool,
+    /// error code
+    pub err: i16,
+    /// extended error code
+    pub exerr: i32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use rseip_core::{
+        codec::{Decode, LittleEndianDecoder},
+        tests::EncodeExt,
+    };
+
+    #[test]
+    fn test_timer() {
+        let buf = Bytes::from_static(&[0, 0, 0, 0xA0, 0x10, 0x27, 0, 0, 0xE8, 0x03, 0, 0]);
+        let timer =
+            Timer::decode(LittleEndianDecoder::<crate::ClientError>::new(buf.clone())).unwrap();
+        assert_eq!(
+            timer,
+            Timer {
+                en: true,
+                tt: false,
+                dn: true,
+                pre: 10000,
+                acc: 1000,
+                raw: 0xA000_0000,
+            }
+        );
+        assert_eq!(timer.try_into_bytes().unwrap(), buf);
+    }
+
+    #[test]
+    fn test_status_word() {
+        let counter = Counter::from_status_word(0x1800_0000);
+        assert!(counter.ov && counter.un && !counter.dn);
+        assert_eq!(counter.status_word(), 0x1800_0000);
+        let control = Control::from_status_word(1 << 25 | 1 << 24);
+        assert!(control.inh && control.fd && !control.en);
+
+        // unknown bits are kept, known bits are patched
+        let timer = Timer::from_status_word(0xA000_0001);
+        let timer = Timer { dn: false, ..timer };
+        assert_eq!(timer.status_word(), 0x8000_0001);
+    }
+
+    #[test]
+    fn test_predefined_type() {
+        let symbol_type = SymbolType::builder().structure(TEMPLATE_TIMER).finish();
+        assert_eq!(
+            PredefinedType::from_symbol_type(symbol_type),
+            Some(PredefinedType::Timer)
+        );
+        let symbol_type = SymbolType::builder().structure(0x0123).finish();
+        assert_eq!(PredefinedType::from_symbol_type(symbol_type), None);
+    }
+}
@@ -0,0 +1,373 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+//! tag readings with quality, timestamp and latency
+//!
+//! ```rust,ignore
+//! let readings = client
+//!     .read_tags(vec![EPath::parse_tag("Speed")?.into(), (EPath::parse_tag("Alarms")?, 4).into()])
+//!     .await;
+//! for reading in readings {
+//!     match reading.quality {
+//!         Quality::Good => println!("{} = {:?}", reading.tag_name(), reading.value),
+//!         q => println!("{} bad quality: {:?}", reading.tag_name(), q),
+//!     }
+//! }
+//! ```
+
+use super::{audit::tag_name, service::ab_read_tag, *};
+use crate::StdResult;
+use bytes::{Buf, BufMut, BytesMut};
+use rseip_cip::{
+    codec::decode::message_reply::decode_service_and_status,
+    service::{StatusReply, STATUS_EMBEDDED_SERVICE_ERROR},
+    MessageReplyInterface,
+};
+use rseip_core::codec::{BytesHolder, Decoder, Encoder, LittleEndianDecoder};
+use std::{
+    ops::Range,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Multiple Service Packet service
+const SERVICE_MULTIPLE: u8 = 0x0A;
+/// default max size of multiple service request or reply
+pub(crate) const DEFAULT_PACKET_SIZE: usize = 480;
+/// estimated reply data size of tags never read
+pub(crate) const UNKNOWN_REPLY_SIZE: usize = 8;
+
+/// quality of value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Quality {
+    /// value read successfully
+    Good,
+    /// error reply of the controller
+    CipError(Status),
+    /// communication failure, with error message
+    CommFailure(String),
+    /// last good value, not refreshed because of communication failure
+    Stale,
+}
+
+impl Quality {
+    /// value read successfully?
+    #[inline]
+    pub fn is_good(&self) -> bool {
+        matches!(self, Self::Good)
+    }
+}
+
+/// result of reading a tag
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagReading {
+    /// tag path
+    pub tag: EPath,
+    /// value, `None` if no value was read
+    pub value: Option<TagValue<Bytes>>,
+    /// quality
+    pub quality: Quality,
+    /// host time of the reply
+    pub timestamp: SystemTime,
+    /// round-trip time of the request
+    pub latency: Duration,
+}
+
+impl TagReading {
+    /// reading from result of Read Tag Service
+    pub(crate) fn from_result(
+        tag: EPath,
+        res: StdResult<TagValue<Bytes>, Quality>,
+        latency: Duration,
+    ) -> Self {
+        let (value, quality) = match res {
+            Ok(v) => (Some(v), Quality::Good),
+            Err(quality) => (None, quality),
+        };
+        Self {
+            tag,
+            value,
+            quality,
+            timestamp: SystemTime::now(),
+            latency,
+        }
+    }
+
+    /// value read successfully?
+    #[inline]
+    pub fn is_good(&self) -> bool {
+        self.quality.is_good()
+    }
+
+    /// tag name, eg. `Program:Main.Recipe[2].Speed`
+    #[inline]
+    pub fn tag_name(&self) -> String {
+        tag_name(&self.tag)
+    }
+}
+
+/// tag to read in batch
+pub(crate) struct ReadItem<'a> {
+    pub tag: &'a EPath,
+    pub count: u16,
+    /// estimated reply data size
+    pub reply_size: usize,
+}
+
+/// read tags by Multiple Service Packets, split by the packet size;
+/// packets after a communication failure are not sent
+pub(crate) async fn read_batch<C>(
+    client: &mut C,
+    items: &[ReadItem<'_>],
+    packet_size: usize,
+) -> Vec<TagReading>
+where
+    C: MessageService<Error = ClientError>,
+{
+    let mut res = Vec::with_capacity(items.len());
+    let mut failure: Option<String> = None;
+    for range in split_packets(items, packet_size) {
+        let packet = &items[range];
+        let start = Instant::now();
+        let replies = match failure {
+            Some(ref e) => Err(ClientError::custom(e)),
+            None => read_packet(client, packet).await,
+        };
+        let latency = start.elapsed();
+        let timestamp = SystemTime::now();
+        let replies = match replies {
+            Ok(v) => v,
+            Err(e) => {
+                let quality = Quality::CommFailure(e.to_string());
+                failure.get_or_insert_with(|| e.to_string());
+                vec![Err(quality); packet.len()]
+            }
+        };
+        for (item, reply) in packet.iter().zip(replies) {
+            let (value, quality) = match reply {
+                Ok(v) => (Some(v), Quality::Good),
+                Err(q) => (None, q),
+            };
+            res.push(TagReading {
+                tag: item.tag.clone(),
+                value,
+                quality,
+                timestamp,
+                latency,
+            });
+        }
+    }
+    res
+}
+
+/// read tags in batch
+pub(crate) async fn ab_read_tags<C>(client: &mut C, reqs: Vec<TagRequest>) -> Vec<TagReading>
+where
+    C: MessageService<Error = ClientError>,
+{
+    let items: Vec<_> = reqs
+        .iter()
+        .map(|req| ReadItem {
+            tag: &req.tag,
+            count: req.count,
+            reply_size: UNKNOWN_REPLY_SIZE * req.count.max(1) as usize,
+        })
+        .collect();
+    read_batch(client, &items, DEFAULT_PACKET_SIZE).await
+}
+
+/// split tags into packets not exceeding the packet size in request or estimated reply
+fn split_packets(items: &[ReadItem<'_>], packet_size: usize) -> Vec<Range<usize>> {
+    let mut res = Vec::new();
+    let mut start = 0;
+    let (mut request_size, mut reply_size) = (2, 2);
+    for (i, item) in items.iter().enumerate() {
+        let item_request =
+            2 + MessageRequest::new(SERVICE_READ_TAG, item.tag, item.count).bytes_count();
+        let item_reply = 2 + 4 + item.reply_size;
+        if i > start
+            && (request_size + item_request > packet_size || reply_size + item_reply > packet_size)
+        {
+            res.push(start..i);
+            start = i;
+            request_size = 2;
+            reply_size = 2;
+        }
+        request_size += item_request;
+        reply_size += item_reply;
+    }
+    if start < items.len() {
+        res.push(start..items.len());
+    }
+    res
+}
+
+/// read tags, by Multiple Service Packet if more than one;
+/// error replies of the controller are returned per tag
+async fn read_packet<C>(
+    client: &mut C,
+    items: &[ReadItem<'_>],
+) -> Result<Vec<StdResult<TagValue<Bytes>, Quality>>>
+where
+    C: MessageService<Error = ClientError>,
+{
+    if let [item] = items {
+        let res = ab_read_tag(client, (item.tag.clone(), item.count)).await?;
+        return Ok(vec![res.map_err(Quality::CipError)]);
+    }
+    let requests = items
+        .iter()
+        .map(|item| MessageRequest::new(SERVICE_READ_TAG, item.tag, item.count))
+        .collect();
+    let mr = MessageRequest::new(
+        SERVICE_MULTIPLE,
+        EPath::default().with_class(2).with_instance(1),
+        ReadPacket(requests),
+    );
+    let reply: StatusReply<BytesHolder, STATUS_EMBEDDED_SERVICE_ERROR> = client.send(mr).await?;
+    reply.expect_service::<ClientError>(SERVICE_MULTIPLE + REPLY_MASK)?;
+    match reply.into_value() {
+        Ok(data) => decode_packet(data.into(), items.len()),
+        Err(status) => Ok(vec![Err(Quality::CipError(status)); items.len()]),
+    }
+}
+
+/// decode replies of multiple service packet, sliced by offsets
+fn decode_packet(data: Bytes, count: usize) -> Result<Vec<StdResult<TagValue<Bytes>, Quality>>> {
+    let invalid = || ClientError::custom("multiple service - invalid reply");
+    let mut buf = data.clone();
+    if buf.remaining() < 2 {
+        return Err(invalid());
+    }
+    if buf.get_u16_le() as usize != count {
+        return Err(ClientError::custom(format!(
+            "multiple service - expected {} replies",
+            count
+        )));
+    }
+    if buf.remaining() < 2 * count {
+        return Err(invalid());
+    }
+    let offsets: Vec<usize> = (0..count).map(|_| buf.get_u16_le() as usize).collect();
+    let mut res = Vec::with_capacity(count);
+    for (i, start) in offsets.iter().enumerate() {
+        let end = offsets.get(i + 1).copied().unwrap_or(data.len());
+        if *start > end || end > data.len() {
+            return Err(invalid());
+        }
+        let mut decoder = LittleEndianDecoder::<ClientError>::new(data.slice(*start..end));
+        let (reply_service, status) = decode_service_and_status(&mut decoder)?;
+        if reply_service != SERVICE_READ_TAG + REPLY_MASK {
+            return Err(invalid());
+        }
+        if status.is_err() {
+            res.push(Err(Quality::CipError(status)));
+        } else {
+            let value: TagValue<Bytes> = decoder.decode_any()?;
+            res.push(Ok(value));
+        }
+    }
+    Ok(res)
+}
+
+/// request data of multiple service packet
+struct ReadPacket<T>(Vec<T>);
+
+impl<T: Encode> Encode for ReadPacket<T> {
+    #[inline]
+    fn encode_by_ref<A: Encoder>(
+        &self,
+        buf: &mut BytesMut,
+        encoder: &mut A,
+    ) -> StdResult<(), A::Error> {
+        buf.put_u16_le(self.0.len() as u16);
+        let mut offset = 2 + 2 * self.0.len();
+        for item in self.0.iter() {
+            buf.put_u16_le(offset as u16);
+            offset += item.bytes_count();
+        }
+        for item in self.0.iter() {
+            item.encode_by_ref(buf, encoder)?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn bytes_count(&self) -> usize {
+        2 + 2 * self.0.len() + self.0.iter().map(|v| v.bytes_count()).sum::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rseip_core::tests::EncodeExt;
+
+    #[test]
+    fn test_encode_read_packet() {
+        let tag = EPath::parse_tag("a").unwrap();
+        let items = vec![
+            MessageRequest::new(SERVICE_READ_TAG, &tag, 1_u16),
+            MessageRequest::new(SERVICE_READ_TAG, &tag, 2_u16),
+        ];
+        let buf = ReadPacket(items).try_into_bytes().unwrap();
+        assert_eq!(
+            &buf[..],
+            &[2, 0, 6, 0, 14, 0, 0x4C, 2, 0x91, 1, b'a', 0, 1, 0, 0x4C, 2, 0x91, 1, b'a', 0, 2, 0]
+                [..]
+        );
+    }
+
+    #[test]
+    fn test_decode_packet() {
+        let data = Bytes::from_static(&[
+            2, 0, 6, 0, 14, 0, // offsets
+            0xCC, 0, 0, 0, 0xC3, 0, 5, 0, // INT 5
+            0xCC, 0, 5, 1, 0, 0, // path destination unknown
+        ]);
+        let res = decode_packet(data, 2).unwrap();
+        assert_eq!(
+            res[0],
+            Ok(TagValue {
+                tag_type: TagType::Int,
+                value: Bytes::from_static(&[5, 0])
+            })
+        );
+        assert_eq!(
+            res[1],
+            Err(Quality::CipError(Status {
+                general: 5,
+                extended: Some(0)
+            }))
+        );
+    }
+
+    #[test]
+    fn test_split_packets() {
+        let tag = EPath::parse_tag("a").unwrap();
+        let items: Vec<_> = (0..3)
+            .map(|_| ReadItem {
+                tag: &tag,
+                count: 1,
+                reply_size: 100,
+            })
+            .collect();
+        // 2 + 3 * (2 + 4 + 100) reply bytes
+        assert_eq!(split_packets(&items, 500), vec![0..3]);
+        assert_eq!(split_packets(&items, 300), vec![0..2, 2..3]);
+        assert_eq!(split_packets(&items, 10), vec![0..1, 1..2, 2..3]);
+
+        let res = TagReading::from_result(
+            tag,
+            Err(Quality::CipError(Status {
+                general: 5,
+                extended: None,
+            })),
+            Duration::ZERO,
+        );
+        assert!(!res.is_good());
+        assert!(matches!(res.quality, Quality::CipError(_)));
+    }
+}
@@ -4,10 +4,16 @@
 // Copyright: 2021, Joylei <leingliu@gmail.com>
 // License: MIT
 
-use super::symbol::GetInstanceAttributeList;
 use super::*;
-use crate::{client::ab_eip::interceptor::HasMoreInterceptor, StdResult};
+use super::{reading::ab_read_tags, symbol::GetInstanceAttributeList};
+use crate::StdResult;
 use bytes::{BufMut, BytesMut};
+use rseip_cip::{
+    epath::Segment,
+    error::cip_error_status,
+    service::{send_and_extract_status, StatusReply, STATUS_PARTIAL_TRANSFER},
+    Status,
+};
 use rseip_core::codec::{Encode, Encoder};
 
 /// AB related operations
@@ -90,6 +96,19 @@ pub trait AbService: Send + Sync {
         req: ReadModifyWriteRequest<N>,
     ) -> Result<()>;
 
+    /// read tags in batch by Multiple Service Packets, with quality, timestamp and latency;
+    /// failed reads are reported by quality, not by error
+    ///
+    /// ```rust,ignore
+    /// let readings = client.read_tags(vec![tag1.into(), (tag2, 4).into()]).await;
+    /// ```
+    async fn read_tags(&mut self, reqs: Vec<TagRequest>) -> Vec<TagReading>
+    where
+        Self: MessageService<Error = ClientError> + Sized,
+    {
+        ab_read_tags(self, reqs).await
+    }
+
     /// list tags
     ///
     /// ```rust,ignore
@@ -102,13 +121,221 @@ pub trait AbService: Send + Sync {
     /// })
     /// .await;
     /// ```
-    fn list_tag(&mut self) -> GetInstanceAttributeList<Self>
+    fn list_tag(&mut self) -> GetInstanceAttributeList<'_, Self>
     where
         Self: Sized;
+
+    /// read tag with quality, timestamp and latency;
+    /// failed reads are reported by quality, not by error
+    ///
+    /// ```rust,ignore
+    /// let reading = client.read_tag_with_quality(tag).await;
+    /// if reading.is_good() {
+    ///     println!("{:?} at {:?}", reading.value, reading.timestamp);
+    /// }
+    /// ```
+    async fn read_tag_with_quality<P>(&mut self, req: P) -> TagReading
+    where
+        Self: MessageService<Error = ClientError> + Sized,
+        P: Into<TagRequest> + Send + Sync,
+    {
+        let req: TagRequest = req.into();
+        let tag = req.tag.clone();
+        let mut res = self.read_tags(vec![req]).await;
+        res.pop().unwrap_or_else(|| {
+            let quality = Quality::CommFailure("no reading".to_owned());
+            TagReading::from_result(tag, Err(quality), Default::default())
+        })
+    }
+
+    /// read a single bit of a BOOL, SINT, INT, DINT, LINT or DWORD tag
+    ///
+    /// ```rust,ignore
+    /// let on = client.read_bit(EPath::parse_tag("status")?, 3).await?;
+    /// ```
+    async fn read_bit(&mut self, tag: EPath, bit: u8) -> Result<bool> {
+        let value: TagValue<Bytes> = self.read_tag(tag).await?;
+        bit_value(&value, bit)
+    }
+
+    /// write a single bit of a BOOL, SINT, INT, DINT, LINT or DWORD tag;
+    /// other bits are kept intact by Read Modify Write Tag Service
+    ///
+    /// ```rust,ignore
+    /// client.write_bit(EPath::parse_tag("status")?, 3, true).await?;
+    /// ```
+    async fn write_bit(&mut self, tag: EPath, bit: u8, value: bool) -> Result<()> {
+        let current: TagValue<Bytes> = self.read_tag(tag.clone()).await?;
+        let width = bit_width(current.tag_type, bit)?;
+        match current.tag_type {
+            TagType::Bool => {
+                let value = TagValue {
+                    tag_type: TagType::Bool,
+                    value,
+                };
+                self.write_tag(tag, value).await
+            }
+            _ => match width {
+                8 => {
+                    self.read_modify_write(bit_request::<1>(tag, bit, value))
+                        .await
+                }
+                16 => {
+                    self.read_modify_write(bit_request::<2>(tag, bit, value))
+                        .await
+                }
+                32 => {
+                    self.read_modify_write(bit_request::<4>(tag, bit, value))
+                        .await
+                }
+                _ => {
+                    self.read_modify_write(bit_request::<8>(tag, bit, value))
+                        .await
+                }
+            },
+        }
+    }
+
+    /// read an element of a BOOL array, eg. `Flags[37]`;
+    /// BOOL arrays are stored as DWORDs, so `Flags[37]` is bit 5 of `Flags[1]`
+    ///
+    /// ```rust,ignore
+    /// let on = client.read_bool_element(EPath::parse_tag("Flags[37]")?).await?;
+    /// ```
+    async fn read_bool_element(&mut self, tag: EPath) -> Result<bool> {
+        let (tag, bit) = bool_element_path(tag)?;
+        self.read_bit(tag, bit).await
+    }
+
+    /// write an element of a BOOL array, eg. `Flags[37]`;
+    /// BOOL arrays are stored as DWORDs, so `Flags[37]` is bit 5 of `Flags[1]`
+    ///
+    /// ```rust,ignore
+    /// client.write_bool_element(EPath::parse_tag("Flags[37]")?, true).await?;
+    /// ```
+    async fn write_bool_element(&mut self, tag: EPath, value: bool) -> Result<()> {
+        let (tag, bit) = bool_element_path(tag)?;
+        self.write_bit(tag, bit, value).await
+    }
+
+    /// read the tag, write the value, read it back and compare;
+    /// the record can go to an [`AuditSink`]
+    ///
+    /// ```rust,ignore
+    /// let record = client.write_verified(EPath::parse_tag("Setpoint")?, 42_i32).await;
+    /// sink.record(&record);
+    /// ```
+    async fn write_verified<D>(&mut self, tag: EPath, value: D) -> WriteRecord
+    where
+        D: Encode + Send + Sync,
+    {
+        super::audit::write_verified(self, tag, value).await
+    }
+
+    /// read status members of a MESSAGE tag
+    ///
+    /// ```rust,ignore
+    /// let status = client.read_message_status(EPath::parse_tag("msg1")?).await?;
+    /// if status.er {
+    ///     println!("error: {:#x}, {:#x}", status.err, status.exerr);
+    /// }
+    /// ```
+    async fn read_message_status(&mut self, tag: EPath) -> Result<MessageStatus> {
+        let mut status = MessageStatus::default();
+        for (name, val) in [
+            ("EN", &mut status.en),
+            ("EW", &mut status.ew),
+            ("ST", &mut status.st),
+            ("DN", &mut status.dn),
+            ("ER", &mut status.er),
+            ("TO", &mut status.to),
+        ] {
+            let res: TagValue<bool> = self.read_tag(tag.clone().with_symbol(name)).await?;
+            *val = res.value;
+        }
+        let res: TagValue<i16> = self.read_tag(tag.clone().with_symbol("ERR")).await?;
+        status.err = res.value;
+        let res: TagValue<i32> = self.read_tag(tag.with_symbol("EXERR")).await?;
+        status.exerr = res.value;
+        Ok(status)
+    }
+}
+
+/// bit width of the tag type, check that the bit is in range
+fn bit_width(tag_type: TagType, bit: u8) -> Result<usize> {
+    let width = match tag_type {
+        TagType::Bool => 1,
+        TagType::Sint => 8,
+        TagType::Int => 16,
+        TagType::Dint | TagType::Dword => 32,
+        TagType::Lint => 64,
+        _ => {
+            return Err(Error::custom(format!(
+                "bit access not supported for tag type: {:?}",
+                tag_type
+            )))
+        }
+    };
+    if bit as usize >= width {
+        return Err(Error::custom(format!(
+            "bit {} out of range for tag type: {:?}",
+            bit, tag_type
+        )));
+    }
+    Ok(width)
+}
+
+/// the bit of the value read, error if the reply is too short
+fn bit_value(value: &TagValue<Bytes>, bit: u8) -> Result<bool> {
+    bit_width(value.tag_type, bit)?;
+    let index = (bit / 8) as usize;
+    if value.value.len() <= index {
+        return Err(Error::invalid_length(value.value.len(), index + 1));
+    }
+    Ok(value.value[index] & (1 << (bit % 8)) != 0)
+}
+
+/// Read Modify Write request to set or reset the bit
+fn bit_request<const N: usize>(tag: EPath, bit: u8, value: bool) -> ReadModifyWriteRequest<N> {
+    let mut req = ReadModifyWriteRequest::new().tag(tag);
+    let idx = (bit / 8) as usize;
+    let mask = 1 << (bit % 8);
+    if value {
+        req.or_mask_mut()[idx] |= mask;
+    } else {
+        req.and_mask_mut()[idx] &= !mask;
+    }
+    req
+}
+
+/// map BOOL array element to DWORD element and bit
+fn bool_element_path(mut tag: EPath) -> Result<(EPath, u8)> {
+    match tag.last_mut() {
+        Some(Segment::Element(idx)) => {
+            let bit = (*idx % 32) as u8;
+            *idx /= 32;
+            Ok((tag, bit))
+        }
+        _ => Err(Error::custom("BOOL array element index expected")),
+    }
 }
 
 macro_rules! impl_service {
     ($t:ty) => {
+        impl_service!(@impl $t, {});
+    };
+    // Read Modify Write Tag Service not supported, eg. Micro800 controllers
+    ($t:ty, no_read_modify_write) => {
+        impl_service!(@impl $t, {
+            /// write a single bit of a BOOL, SINT, INT, DINT, LINT or DWORD tag;
+            /// Read Modify Write Tag Service is not supported, so the tag is read and
+            /// written back with the bit patched, which is not atomic
+            async fn write_bit(&mut self, tag: EPath, bit: u8, value: bool) -> Result<()> {
+                write_bit_unmasked(self, tag, bit, value).await
+            }
+        });
+    };
+    (@impl $t:ty, { $($extra:tt)* }) => {
         #[async_trait::async_trait]
         impl AbService for $t {
             /// Read Tag Service,
@@ -119,8 +346,7 @@ macro_rules! impl_service {
                 P: Into<TagRequest> + Send + Sync,
                 R: Decode<'de> + 'static,
             {
-                let res = ab_read_tag(self, req).await?;
-                Ok(res)
+                ab_read_tag(self, req).await?.map_err(cip_error_status)
             }
 
             /// Write Tag Service,
@@ -130,8 +356,9 @@ macro_rules! impl_service {
             where
                 D: Encode + Send + Sync,
             {
-                ab_write_tag(self, tag, value).await?;
-                Ok(())
+                ab_write_tag(self, tag, value)
+                    .await?
+                    .map_err(cip_error_status)
             }
 
             /// Read Tag Fragmented Service
@@ -140,8 +367,9 @@ macro_rules! impl_service {
                 &mut self,
                 req: ReadFragmentedRequest,
             ) -> Result<(bool, TagValue<Bytes>)> {
-                let res = ab_read_tag_fragmented(self, req).await?;
-                Ok(res)
+                ab_read_tag_fragmented(self, req)
+                    .await?
+                    .map_err(cip_error_status)
             }
 
             /// Write Tag Fragmented Service, enables client applications to write to a tag
@@ -151,8 +379,9 @@ macro_rules! impl_service {
                 &mut self,
                 req: WriteFragmentedRequest<D>,
             ) -> Result<bool> {
-                let res = ab_write_tag_fragmented(self, req).await?;
-                Ok(res)
+                ab_write_tag_fragmented(self, req)
+                    .await?
+                    .map_err(cip_error_status)
             }
 
             /// Read Modify Write Tag Service, modifies Tag data with individual bit resolution
@@ -161,8 +390,9 @@ macro_rules! impl_service {
                 &mut self,
                 req: ReadModifyWriteRequest<N>,
             ) -> Result<()> {
-                ab_read_modify_write(self, req).await?;
-                Ok(())
+                ab_read_modify_write(self, req)
+                    .await?
+                    .map_err(cip_error_status)
             }
 
             /// list tags
@@ -178,12 +408,14 @@ macro_rules! impl_service {
             /// .await;
             /// ```
             #[inline]
-            fn list_tag(&mut self) -> GetInstanceAttributeList<Self>
+            fn list_tag(&mut self) -> GetInstanceAttributeList<'_, Self>
             where
                 Self: Sized,
             {
                 GetInstanceAttributeList::new(self)
             }
+
+            $($extra)*
         }
     };
 }
@@ -191,40 +423,76 @@ macro_rules! impl_service {
 impl_service!(AbEipClient);
 impl_service!(AbEipConnection);
 impl_service!(MaybeConnected<AbEipDriver>);
+impl_service!(Micro800Client, no_read_modify_write);
+impl_service!(Micro800Connection, no_read_modify_write);
+impl_service!(MaybeConnected<Micro800Driver>, no_read_modify_write);
+
+/// write a single bit by reading the tag and writing it back with the bit patched
+async fn write_bit_unmasked<C>(client: &mut C, tag: EPath, bit: u8, value: bool) -> Result<()>
+where
+    C: AbService + Send,
+{
+    let current: TagValue<Bytes> = client.read_tag(tag.clone()).await?;
+    let width = bit_width(current.tag_type, bit)?;
+    if current.tag_type == TagType::Bool {
+        let value = TagValue {
+            tag_type: TagType::Bool,
+            value,
+        };
+        return client.write_tag(tag, value).await;
+    }
+    if current.value.len() < width / 8 {
+        return Err(Error::invalid_length(current.value.len(), width / 8));
+    }
+    let mut data = current.value[..width / 8].to_vec();
+    let mask = 1 << (bit % 8);
+    if value {
+        data[(bit / 8) as usize] |= mask;
+    } else {
+        data[(bit / 8) as usize] &= !mask;
+    }
+    client
+        .write_tag(tag, (current.tag_type, 1_u16, Bytes::from(data)))
+        .await
+}
 
 /// Read Tag Service,
-/// CIP Data Table Read
-async fn ab_read_tag<'de, C, P, R>(client: &mut C, req: P) -> Result<R>
+/// CIP Data Table Read;
+/// error status of the reply is returned as `Err(status)`
+pub(crate) async fn ab_read_tag<'de, C, P, R>(
+    client: &mut C,
+    req: P,
+) -> Result<StdResult<R, Status>>
 where
     C: MessageService<Error = ClientError>,
     P: Into<TagRequest> + Send + Sync,
     R: Decode<'de> + 'static,
 {
     let req: TagRequest = req.into();
-    let mr = MessageRequest::new(SERVICE_READ_TAG, req.tag, req.count);
-    let resp: MessageReply<_> = client.send(mr).await?;
-    resp.expect_service::<ClientError>(SERVICE_READ_TAG + REPLY_MASK)?;
-    Ok(resp.data)
+    send_and_extract_status(client, SERVICE_READ_TAG, req.tag, req.count).await
 }
 
 /// Write Tag Service,
-/// CIP Data Table Write
-async fn ab_write_tag<C, D>(client: &mut C, tag: EPath, value: D) -> Result<()>
+/// CIP Data Table Write;
+/// error status of the reply is returned as `Err(status)`
+pub(crate) async fn ab_write_tag<C, D>(
+    client: &mut C,
+    tag: EPath,
+    value: D,
+) -> Result<StdResult<(), Status>>
 where
     C: MessageService<Error = ClientError>,
     D: Encode + Send + Sync,
 {
-    let mr = MessageRequest::new(SERVICE_WRITE_TAG, tag, value);
-    let resp: MessageReply<()> = client.send(mr).await?;
-    resp.expect_service::<ClientError>(SERVICE_WRITE_TAG + REPLY_MASK)?;
-    Ok(())
+    send_and_extract_status(client, SERVICE_WRITE_TAG, tag, value).await
 }
 
-/// Read Tag Fragmented Service
-async fn ab_read_tag_fragmented<C>(
+/// Read Tag Fragmented Service;
+/// error status of the reply is returned as `Err(status)`
+pub(crate) async fn ab_read_tag_fragmented<C>(
     client: &mut C,
     req: ReadFragmentedRequest,
-) -> Result<(bool, TagValue<Bytes>)>
+) -> Result<StdResult<(bool, TagValue<Bytes>), Status>>
 where
     C: MessageService<Error = ClientError>,
 {
@@ -232,19 +500,19 @@ where
     let ReadFragmentedRequest { tag, count, offset } = req;
 
     let mr = MessageRequest::new(SERVICE_READ_TAG_FRAGMENTED, tag, [count, offset, 0]);
-    let resp: HasMoreInterceptor<TagValue<Bytes>> = client.send(mr).await?;
-    resp.0
-        .expect_service::<ClientError>(SERVICE_READ_TAG_FRAGMENTED + REPLY_MASK)?;
-
-    Ok((resp.0.status.has_more(), resp.0.data))
+    let resp: StatusReply<TagValue<Bytes>, STATUS_PARTIAL_TRANSFER> = client.send(mr).await?;
+    resp.expect_service::<ClientError>(SERVICE_READ_TAG_FRAGMENTED + REPLY_MASK)?;
+    let has_more = resp.status().has_more();
+    Ok(resp.into_value().map(|v| (has_more, v)))
 }
 
 /// Write Tag Fragmented Service, enables client applications to write to a tag
-/// in the controller whose data will not fit into a single packet (approximately 500 bytes)
-async fn ab_write_tag_fragmented<C, D>(
+/// in the controller whose data will not fit into a single packet (approximately 500 bytes);
+/// error status of the reply is returned as `Err(status)`
+pub(crate) async fn ab_write_tag_fragmented<C, D>(
     client: &mut C,
     req: WriteFragmentedRequest<D>,
-) -> Result<bool>
+) -> Result<StdResult<bool, Status>>
 where
     C: MessageService<Error = ClientError>,
     D: Encode + Send + Sync,
@@ -307,16 +575,18 @@ where
             data,
         },
     );
-    let resp: HasMoreInterceptor<()> = client.send(mr).await?;
+    let resp: StatusReply<(), STATUS_PARTIAL_TRANSFER> = client.send(mr).await?;
     resp.expect_service::<ClientError>(SERVICE_WRITE_TAG_FRAGMENTED + REPLY_MASK)?;
-    Ok(resp.0.status.has_more())
+    let has_more = resp.status().has_more();
+    Ok(resp.into_value().map(|_| has_more))
 }
 
-/// Read Modify Write Tag Service, modifies Tag data with individual bit resolution
-async fn ab_read_modify_write<C, const N: usize>(
+/// Read Modify Write Tag Service, modifies Tag data with individual bit resolution;
+/// error status of the reply is returned as `Err(status)`
+pub(crate) async fn ab_read_modify_write<C, const N: usize>(
     client: &mut C,
     req: ReadModifyWriteRequest<N>,
-) -> Result<()>
+) -> Result<StdResult<(), Status>>
 where
     C: MessageService<Error = ClientError>,
 {
@@ -349,21 +619,20 @@ where
         and_mask,
     } = req;
 
-    let mr_request = MessageRequest::new(
+    send_and_extract_status(
+        client,
         SERVICE_READ_MODIFY_WRITE_TAG,
         tag,
         DataHolder { and_mask, or_mask },
-    );
-    let resp: MessageReply<()> = client.send(mr_request).await?;
-    resp.expect_service::<ClientError>(SERVICE_READ_MODIFY_WRITE_TAG + REPLY_MASK)?;
-    Ok(())
+    )
+    .await
 }
 
 /// N: only 1,2,4,8,12 accepted
 pub struct ReadModifyWriteRequest<const N: usize> {
-    tag: EPath,
-    or_mask: [u8; N],
-    and_mask: [u8; N],
+    pub(crate) tag: EPath,
+    pub(crate) or_mask: [u8; N],
+    pub(crate) and_mask: [u8; N],
 }
 
 impl<const N: usize> ReadModifyWriteRequest<N> {
@@ -411,11 +680,11 @@ impl<const N: usize> Default for ReadModifyWriteRequest<N> {
 }
 
 pub struct WriteFragmentedRequest<D> {
-    tag: EPath,
-    tag_type: TagType,
-    count: u16,
-    offset: u16,
-    data: D,
+    pub(crate) tag: EPath,
+    pub(crate) tag_type: TagType,
+    pub(crate) count: u16,
+    pub(crate) offset: u16,
+    pub(crate) data: D,
 }
 
 impl<D> WriteFragmentedRequest<D> {
@@ -457,9 +726,9 @@ impl<D> WriteFragmentedRequest<D> {
 }
 
 pub struct ReadFragmentedRequest {
-    tag: EPath,
-    count: u16,
-    offset: u16,
+    pub(crate) tag: EPath,
+    pub(crate) count: u16,
+    pub(crate) offset: u16,
 }
 
 impl ReadFragmentedRequest {
@@ -496,8 +765,8 @@ impl Default for ReadFragmentedRequest {
 }
 
 pub struct TagRequest {
-    tag: EPath,
-    count: u16,
+    pub(crate) tag: EPath,
+    pub(crate) count: u16,
 }
 
 impl From<EPath> for TagRequest {
@@ -516,3 +785,58 @@ impl From<(EPath, u16)> for TagRequest {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bit_value() {
+        let value = TagValue {
+            tag_type: TagType::Bool,
+            value: Bytes::new(),
+        };
+        assert!(bit_value(&value, 0).is_err());
+        let value = TagValue {
+            tag_type: TagType::Bool,
+            value: Bytes::from_static(&[0x01]),
+        };
+        assert!(bit_value(&value, 0).unwrap());
+        let value = TagValue {
+            tag_type: TagType::Dint,
+            value: Bytes::from_static(&[0, 0x02]),
+        };
+        assert!(bit_value(&value, 17).is_err());
+        let value = TagValue {
+            tag_type: TagType::Int,
+            value: Bytes::from_static(&[0, 0x02]),
+        };
+        assert!(bit_value(&value, 9).unwrap());
+    }
+
+    #[test]
+    fn test_bool_element_path() {
+        let (path, bit) = bool_element_path(EPath::from_symbol("Flags").with_element(37)).unwrap();
+        assert_eq!(path, EPath::from_symbol("Flags").with_element(1));
+        assert_eq!(bit, 5);
+        assert!(bool_element_path(EPath::from_symbol("Flags")).is_err());
+    }
+
+    #[test]
+    fn test_bit_request() {
+        let req = bit_request::<4>(EPath::from_symbol("a"), 13, true);
+        assert_eq!(req.or_mask, [0, 0x20, 0, 0]);
+        assert_eq!(req.and_mask, [0xFF; 4]);
+        let req = bit_request::<2>(EPath::from_symbol("a"), 2, false);
+        assert_eq!(req.or_mask, [0; 2]);
+        assert_eq!(req.and_mask, [0xFB, 0xFF]);
+    }
+
+    #[test]
+    fn test_bit_width() {
+        assert_eq!(bit_width(TagType::Int, 15).unwrap(), 16);
+        assert!(bit_width(TagType::Int, 16).is_err());
+        assert!(bit_width(TagType::Real, 0).is_err());
+        assert_eq!(bit_width(TagType::Bool, 0).unwrap(), 1);
+    }
+}
@@ -0,0 +1,356 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+//! polling subscriptions with change detection
+//!
+//! Tags of the same scan rate are read together by Multiple Service Packets.
+//! The stream only polls the controller when it is consumed; scans missed by a
+//! slow consumer are skipped instead of queued. When the connection is lost,
+//! the last good values are reported as stale and the client reconnects on the
+//! next scan, with backoff.
+//!
+//! ```rust,ignore
+//! let client = AbEipClient::new_host_lookup("192.168.0.83").await?;
+//! let stream = Subscriptions::new(client)
+//!     .subscribe(
+//!         Subscription::new(EPath::parse_tag("Speed")?, Duration::from_millis(100)).deadband(0.5),
+//!     )
+//!     .subscribe(Subscription::new(EPath::parse_tag("Alarms")?, Duration::from_secs(1)).count(4))
+//!     .into_stream();
+//! futures_util::pin_mut!(stream);
+//! while let Some(event) = stream.next().await {
+//!     println!("{} {:?} {:?}", event.tag_name(), event.quality, event.value);
+//! }
+//! ```
+
+use super::reading::{read_batch, ReadItem, DEFAULT_PACKET_SIZE, UNKNOWN_REPLY_SIZE};
+use super::*;
+use crate::cip::{assembly::FieldValue, data_type::ElementaryType};
+use futures_util::{stream, Stream};
+use std::{collections::VecDeque, time::Duration};
+use tokio::time::{self, Instant};
+
+/// default max delay between retries after communication failures
+const DEFAULT_MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+/// minimum scan rate, faster rates are clamped
+const MIN_SCAN_RATE: Duration = Duration::from_millis(1);
+
+/// subscription of a tag
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subscription {
+    /// tag path
+    pub tag: EPath,
+    /// number of elements to read
+    pub count: u16,
+    /// scan rate, clamped to 1ms at least
+    pub rate: Duration,
+    /// changes within the deadband are not reported, for numeric tags of single element
+    pub deadband: Option<f64>,
+}
+
+impl Subscription {
+    /// subscribe the tag at the scan rate
+    #[inline]
+    pub fn new(tag: EPath, rate: Duration) -> Self {
+        Self {
+            tag,
+            count: 1,
+            rate,
+            deadband: None,
+        }
+    }
+
+    /// number of elements to read, default 1
+    #[inline]
+    pub fn count(mut self, count: u16) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// deadband for numeric tags of single element
+    #[inline]
+    pub fn deadband(mut self, deadband: f64) -> Self {
+        self.deadband = Some(deadband);
+        self
+    }
+}
+
+/// change of subscribed tag, in value or quality;
+/// the last good value is kept as [`Quality::Stale`] on communication failures
+pub type ChangeEvent = TagReading;
+
+/// polling subscription engine
+#[derive(Debug)]
+pub struct Subscriptions<C> {
+    client: C,
+    subscriptions: Vec<Subscription>,
+    packet_size: usize,
+    max_retry_delay: Duration,
+}
+
+impl<C> Subscriptions<C> {
+    /// new subscription engine on the client
+    #[inline]
+    pub fn new(client: C) -> Self {
+        Self {
+            client,
+            subscriptions: Default::default(),
+            packet_size: DEFAULT_PACKET_SIZE,
+            max_retry_delay: DEFAULT_MAX_RETRY_DELAY,
+        }
+    }
+
+    /// register subscription
+    #[inline]
+    pub fn subscribe(mut self, subscription: Subscription) -> Self {
+        self.subscriptions.push(subscription);
+        self
+    }
+
+    /// max size of multiple service request or reply, default 480;
+    /// should not exceed connection size
+    #[inline]
+    pub fn packet_size(mut self, packet_size: usize) -> Self {
+        self.packet_size = packet_size;
+        self
+    }
+
+    /// max delay between retries after communication failures, default 30s
+    #[inline]
+    pub fn max_retry_delay(mut self, delay: Duration) -> Self {
+        self.max_retry_delay = delay;
+        self
+    }
+
+    /// subscriptions
+    #[inline]
+    pub fn subscriptions(&self) -> &[Subscription] {
+        &self.subscriptions
+    }
+}
+
+impl<C> Subscriptions<C>
+where
+    C: MessageService<Error = ClientError>,
+{
+    /// stream of change events; the first scan of every tag is always reported
+    pub fn into_stream(self) -> impl Stream<Item = ChangeEvent> {
+        let engine = Engine::new(self);
+        stream::unfold(engine, |mut engine| async move {
+            let event = engine.next_event().await?;
+            Some((event, engine))
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Slot {
+    sub: Subscription,
+    quality: Option<Quality>,
+    /// last reported value
+    value: Option<TagValue<Bytes>>,
+    reply_size: usize,
+}
+
+/// tags of the same scan rate
+#[derive(Debug)]
+struct Group {
+    rate: Duration,
+    slots: Vec<usize>,
+    due: Instant,
+    failures: u32,
+}
+
+struct Engine<C> {
+    client: C,
+    slots: Vec<Slot>,
+    groups: Vec<Group>,
+    events: VecDeque<ChangeEvent>,
+    packet_size: usize,
+    max_retry_delay: Duration,
+}
+
+impl<C> Engine<C>
+where
+    C: MessageService<Error = ClientError>,
+{
+    fn new(subscriptions: Subscriptions<C>) -> Self {
+        let now = Instant::now();
+        let mut groups: Vec<Group> = Vec::new();
+        let mut slots = Vec::with_capacity(subscriptions.subscriptions.len());
+        for (i, sub) in subscriptions.subscriptions.into_iter().enumerate() {
+            let rate = sub.rate.max(MIN_SCAN_RATE);
+            match groups.iter_mut().find(|g| g.rate == rate) {
+                Some(group) => group.slots.push(i),
+                None => groups.push(Group {
+                    rate,
+                    slots: vec![i],
+                    due: now,
+                    failures: 0,
+                }),
+            }
+            slots.push(Slot {
+                sub,
+                quality: None,
+                value: None,
+                reply_size: UNKNOWN_REPLY_SIZE,
+            });
+        }
+        Self {
+            client: subscriptions.client,
+            slots,
+            groups,
+            events: Default::default(),
+            packet_size: subscriptions.packet_size,
+            max_retry_delay: subscriptions.max_retry_delay,
+        }
+    }
+
+    async fn next_event(&mut self) -> Option<ChangeEvent> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Some(event);
+            }
+            let i = (0..self.groups.len()).min_by_key(|i| self.groups[*i].due)?;
+            time::sleep_until(self.groups[i].due).await;
+            self.scan(i).await;
+        }
+    }
+
+    /// read all tags of the group
+    async fn scan(&mut self, i: usize) {
+        let readings = {
+            let items: Vec<_> = self.groups[i]
+                .slots
+                .iter()
+                .map(|idx| {
+                    let slot = &self.slots[*idx];
+                    ReadItem {
+                        tag: &slot.sub.tag,
+                        count: slot.sub.count,
+                        reply_size: slot.reply_size,
+                    }
+                })
+                .collect();
+            read_batch(&mut self.client, &items, self.packet_size).await
+        };
+        let failure = readings.iter().find_map(|v| match v.quality {
+            Quality::CommFailure(ref e) => Some(e.clone()),
+            _ => None,
+        });
+        if let Some(ref e) = failure {
+            log::warn!("subscription - scan failed: {}", e);
+            // reconnect on next scan
+            let _ = self.client.close().await;
+        }
+        for (j, reading) in readings.into_iter().enumerate() {
+            let idx = self.groups[i].slots[j];
+            self.update(idx, reading);
+        }
+
+        let now = Instant::now();
+        let group = &mut self.groups[i];
+        if failure.is_some() {
+            group.failures = group.failures.saturating_add(1);
+            let backoff = group
+                .rate
+                .saturating_mul(1 << group.failures.min(16))
+                .min(self.max_retry_delay)
+                .max(group.rate);
+            group.due = now + backoff;
+        } else {
+            group.failures = 0;
+            group.due = next_due(group.due, group.rate, now);
+        }
+    }
+
+    /// record scan result, queue event if changed
+    fn update(&mut self, idx: usize, mut reading: TagReading) {
+        let slot = &mut self.slots[idx];
+        if let Some(ref v) = reading.value {
+            slot.reply_size = v.value.len() + 6;
+        }
+        if matches!(reading.quality, Quality::CommFailure(_)) && slot.value.is_some() {
+            reading.quality = Quality::Stale;
+            reading.value = slot.value.clone();
+        }
+        let changed = slot.quality.as_ref() != Some(&reading.quality)
+            || match (&slot.value, &reading.value) {
+                (Some(old), Some(new)) => is_changed(old, new, slot.sub.deadband),
+                _ => false,
+            };
+        if !changed {
+            return;
+        }
+        slot.quality = Some(reading.quality.clone());
+        slot.value = reading.value.clone();
+        self.events.push_back(reading);
+    }
+}
+
+/// next scan time on the grid of the scan rate; missed scans are skipped
+fn next_due(due: Instant, rate: Duration, now: Instant) -> Instant {
+    let rate = rate.max(MIN_SCAN_RATE);
+    let next = due + rate;
+    if next > now {
+        return next;
+    }
+    let missed = (now - due).as_nanos() / rate.as_nanos() + 1;
+    due + Duration::from_nanos((rate.as_nanos() * missed) as u64)
+}
+
+/// value changed, beyond the deadband if any?
+fn is_changed(old: &TagValue<Bytes>, new: &TagValue<Bytes>, deadband: Option<f64>) -> bool {
+    if old.tag_type != new.tag_type {
+        return true;
+    }
+    if let Some(deadband) = deadband {
+        if let (Some(a), Some(b)) = (to_f64(old), to_f64(new)) {
+            return (a - b).abs() > deadband;
+        }
+    }
+    old.value != new.value
+}
+
+/// numeric value of single element, `None` for BOOL, bit strings and structures
+fn to_f64(v: &TagValue<Bytes>) -> Option<f64> {
+    match v.tag_type {
+        TagType::Bool | TagType::Dword | TagType::Other(0xD1..=0xD4) | TagType::Structure(_) => {
+            None
+        }
+        ty => {
+            let ty = ElementaryType::from_code(ty.type_code())?;
+            FieldValue::from_le_bytes(ty, &v.value).map(|v| v.as_f64())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_changed() {
+        let real = |v: f32| TagValue {
+            tag_type: TagType::Real,
+            value: Bytes::copy_from_slice(&v.to_le_bytes()),
+        };
+        assert!(!is_changed(&real(1.0), &real(1.4), Some(0.5)));
+        assert!(is_changed(&real(1.0), &real(1.6), Some(0.5)));
+        assert!(is_changed(&real(1.0), &real(1.4), None));
+        assert!(!is_changed(&real(1.0), &real(1.0), None));
+    }
+
+    #[test]
+    fn test_next_due() {
+        let now = Instant::now();
+        let rate = Duration::from_millis(100);
+        assert_eq!(next_due(now, rate, now), now + rate);
+        let late = now + Duration::from_millis(250);
+        assert_eq!(next_due(now, rate, late), now + Duration::from_millis(300));
+        assert_eq!(next_due(now, Duration::ZERO, now), now + MIN_SCAN_RATE);
+    }
+}
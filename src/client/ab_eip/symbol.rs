@@ -4,7 +4,9 @@
 // Copyright: 2021, Joylei <leingliu@gmail.com>
 // License: MIT
 
-use super::{interceptor::HasMoreInterceptor, HasMore, CLASS_SYMBOL, REPLY_MASK};
+use super::{
+    interceptor::HasMoreInterceptor, predefined::PredefinedType, HasMore, CLASS_SYMBOL, REPLY_MASK,
+};
 use crate::{
     cip::{epath::EPath, service::MessageService, MessageRequest},
     ClientError, Result,
@@ -14,7 +16,13 @@ use core::{convert::TryFrom, fmt, slice, str};
 use futures_util::{stream, Stream};
 use rseip_cip::MessageReplyInterface;
 use rseip_core::{codec::BytesHolder, hex::AsHex, Error};
-use std::borrow::Cow;
+use std::{
+    borrow::Cow,
+    sync::{
+        atomic::{AtomicBool, AtomicU16, Ordering},
+        Arc,
+    },
+};
 
 /// symbol instance
 #[derive(Clone, Hash, PartialEq, Eq)]
@@ -38,10 +46,19 @@ impl fmt::Debug for SymbolInstance<'_> {
 }
 
 impl SymbolInstance<'_> {
-    /// symbol name that contains `:`
+    /// symbol name that contains `:`, eg. `Local:1:I`, except program scoped
+    /// symbols `Program:<name>`
     #[inline]
     pub fn is_module_defined(&self) -> bool {
-        self.name.contains(':')
+        let name = self.name.as_bytes();
+        let program = name.len() >= 8 && name[..8].eq_ignore_ascii_case(b"program:");
+        !program && name.contains(&b':')
+    }
+
+    /// system symbol, symbol name that starts with `__` or system flag set in symbol type
+    #[inline]
+    pub fn is_system(&self) -> bool {
+        self.name.starts_with("__") || self.symbol_type.is_system()
     }
 }
 
@@ -118,12 +135,25 @@ impl SymbolType {
         !self.is_struct()
     }
 
+    /// system symbol, bit 12 set
+    #[inline]
+    pub fn is_system(&self) -> bool {
+        const MASK: u16 = 1 << 12;
+        self.0 & MASK == MASK
+    }
+
     /// system predefined struct
     #[inline]
     pub fn is_predefined(&self) -> bool {
         self.instance_id().map(|v| v > 0xEFF).unwrap_or_default()
     }
 
+    /// predefined struct with typed Rust struct, eg. TIMER
+    #[inline]
+    pub fn predefined_type(&self) -> Option<PredefinedType> {
+        PredefinedType::from_symbol_type(*self)
+    }
+
     /// type code if atomic; range from 0x01-0xFF
     #[inline]
     pub fn type_code(&self) -> Option<u8> {
@@ -202,6 +232,10 @@ pub struct GetInstanceAttributeList<'a, T> {
     inner: &'a mut T,
     start_instance: u16,
     all: bool,
+    filter: SymbolFilter,
+    cursor: Option<ListCursor>,
+    skip_invalid: bool,
+    program: Option<String>,
 }
 
 impl<'a, T> GetInstanceAttributeList<'a, T> {
@@ -210,6 +244,10 @@ impl<'a, T> GetInstanceAttributeList<'a, T> {
             inner,
             start_instance: 0,
             all: true,
+            filter: Default::default(),
+            cursor: None,
+            skip_invalid: false,
+            program: None,
         }
     }
 
@@ -226,38 +264,109 @@ impl<'a, T> GetInstanceAttributeList<'a, T> {
         self.all = all;
         self
     }
+
+    /// resume browsing from the position of the cursor, and keep the cursor updated while browsing
+    ///
+    /// ```rust,ignore
+    /// let cursor = ListCursor::new();
+    /// while !cursor.is_done() {
+    ///     let stream = client.list_tag().cursor(&cursor).call();
+    ///     // consume the stream; on error, reconnect and try again
+    /// }
+    /// ```
+    pub fn cursor(mut self, cursor: &ListCursor) -> Self {
+        self.start_instance = cursor.next_instance();
+        self.cursor = Some(cursor.clone());
+        self
+    }
+
+    /// only symbols whose name starts with the prefix, ignore case
+    pub fn name_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.filter.prefix = Some(prefix.into());
+        self
+    }
+
+    /// only symbols whose name matches the glob pattern, ignore case;
+    /// `*` matches any sequence of characters, `?` matches any single character
+    pub fn name_glob(mut self, pattern: impl Into<String>) -> Self {
+        self.filter.glob = Some(pattern.into());
+        self
+    }
+
+    /// skip system symbols, see [`SymbolInstance::is_system`]
+    ///
+    /// default false
+    pub fn skip_system(mut self, skip: bool) -> Self {
+        self.filter.skip_system = skip;
+        self
+    }
+
+    /// skip module defined symbols, see [`SymbolInstance::is_module_defined`]
+    ///
+    /// default false
+    pub fn skip_module_defined(mut self, skip: bool) -> Self {
+        self.filter.skip_module_defined = skip;
+        self
+    }
+
+    /// yield the error of a malformed symbol instance, and continue with the next instance
+    /// instead of ending the stream
+    ///
+    /// default false
+    pub fn skip_invalid(mut self, skip: bool) -> Self {
+        self.skip_invalid = skip;
+        self
+    }
+
+    /// list the tags scoped to the program, eg. `MainProgram`, instead of controller scoped tags
+    pub fn program(mut self, name: impl Into<String>) -> Self {
+        self.program = Some(name.into());
+        self
+    }
 }
 
 impl<'a, T: MessageService<Error = ClientError>> GetInstanceAttributeList<'a, T> {
     pub fn call(self) -> impl Stream<Item = Result<SymbolInstance<'a>>> {
-        let all = self.all;
-        stream::try_unfold(
-            State::Request {
-                ctx: self.inner,
-                start_instance: self.start_instance,
-            },
-            move |mut state| async move {
+        let options = ListOptions {
+            all: self.all,
+            filter: self.filter,
+            cursor: self.cursor,
+            skip_invalid: self.skip_invalid,
+            program: self.program,
+        };
+        stream::unfold(
+            (
+                State::Request {
+                    ctx: self.inner,
+                    start_instance: self.start_instance,
+                },
+                options,
+            ),
+            move |(mut state, options)| async move {
                 loop {
                     match state {
                         State::Request {
                             ctx,
                             start_instance,
-                        } => {
-                            match get_attribute_list(ctx, start_instance).await {
-                                Ok((has_more, data)) => {
-                                    state = State::HasData {
-                                        ctx,
-                                        start_instance,
-                                        has_more,
-                                        data,
-                                    }
-                                }
-                                Err(e) => {
-                                    //state = State::End;
-                                    return Err(e);
+                        } => match get_attribute_list(
+                            ctx,
+                            options.program.as_deref(),
+                            start_instance,
+                        )
+                        .await
+                        {
+                            Ok((has_more, data)) => {
+                                state = State::HasData {
+                                    ctx,
+                                    start_instance,
+                                    has_more,
+                                    data,
                                 }
                             }
-                        }
+                            Err(e) => {
+                                return Some((Err(e), (State::End, options)));
+                            }
+                        },
                         State::HasData {
                             ctx,
                             start_instance,
@@ -265,36 +374,71 @@ impl<'a, T: MessageService<Error = ClientError>> GetInstanceAttributeList<'a, T>
                             mut data,
                         } => {
                             if !data.is_empty() {
+                                // instance id is the leading field of each item
+                                let instance_id = if data.len() >= 2 {
+                                    Some(u16::from_le_bytes([data[0], data[1]]))
+                                } else {
+                                    None
+                                };
                                 match SymbolInstance::try_from(&mut data) {
                                     Ok(item) => {
                                         let start_instance = item.id; // update start instance
-                                        return Ok(Some((
-                                            item,
-                                            State::HasData {
-                                                ctx,
-                                                start_instance,
-                                                has_more,
-                                                data,
-                                            },
-                                        )));
+                                        options.advance(start_instance);
+                                        let next = State::HasData {
+                                            ctx,
+                                            start_instance,
+                                            has_more,
+                                            data,
+                                        };
+                                        if options.filter.matches(&item) {
+                                            return Some((Ok(item), (next, options)));
+                                        }
+                                        state = next;
                                     }
                                     Err(e) => {
-                                        //dbg!(&e);
-                                        //state = State::End;
-                                        return Err(e);
+                                        let next = match instance_id {
+                                            // remaining data is not reliable, request from the next instance
+                                            Some(id) if options.skip_invalid => {
+                                                log::warn!(
+                                                    "list tag - skip malformed instance {:#0x}: {}",
+                                                    id,
+                                                    e
+                                                );
+                                                options.advance(id);
+                                                match id.checked_add(1) {
+                                                    Some(start_instance) => State::Request {
+                                                        ctx,
+                                                        start_instance,
+                                                    },
+                                                    None => State::End,
+                                                }
+                                            }
+                                            _ => State::End,
+                                        };
+                                        return Some((Err(e), (next, options)));
+                                    }
+                                }
+                            } else if has_more && options.all {
+                                match start_instance.checked_add(1) {
+                                    Some(start_instance) => {
+                                        state = State::Request {
+                                            ctx,
+                                            start_instance,
+                                        };
+                                    }
+                                    None => {
+                                        options.finish();
+                                        state = State::End;
                                     }
                                 }
-                            } else if has_more && all {
-                                //dbg!(has_more, "new request");
-                                state = State::Request {
-                                    ctx,
-                                    start_instance: start_instance + 1,
-                                };
                             } else {
+                                if !has_more {
+                                    options.finish();
+                                }
                                 state = State::End;
                             }
                         }
-                        State::End => return Ok(None),
+                        State::End => return None,
                     }
                 }
             },
@@ -316,14 +460,155 @@ enum State<'a, T> {
     End,
 }
 
+struct ListOptions {
+    all: bool,
+    filter: SymbolFilter,
+    cursor: Option<ListCursor>,
+    skip_invalid: bool,
+    program: Option<String>,
+}
+
+impl ListOptions {
+    /// instance received
+    #[inline]
+    fn advance(&self, instance_id: u16) {
+        if let Some(ref cursor) = self.cursor {
+            cursor.advance(instance_id);
+        }
+    }
+
+    /// no more instances
+    #[inline]
+    fn finish(&self) {
+        if let Some(ref cursor) = self.cursor {
+            cursor.0.done.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// browsing position of [`GetInstanceAttributeList`]
+///
+/// Keep the cursor to resume browsing after an error or a reconnect;
+/// clones share the same position.
+#[derive(Debug, Clone, Default)]
+pub struct ListCursor(Arc<CursorState>);
+
+#[derive(Debug, Default)]
+struct CursorState {
+    next_instance: AtomicU16,
+    done: AtomicBool,
+}
+
+impl ListCursor {
+    /// new cursor, starting from instance 0
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// new cursor, starting from specified instance
+    #[inline]
+    pub fn from_instance(instance_id: u16) -> Self {
+        let cursor = Self::default();
+        cursor.0.next_instance.store(instance_id, Ordering::Relaxed);
+        cursor
+    }
+
+    /// the instance id to request for next browsing
+    #[inline]
+    pub fn next_instance(&self) -> u16 {
+        self.0.next_instance.load(Ordering::Relaxed)
+    }
+
+    /// all symbol instances have been retrieved
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        self.0.done.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    fn advance(&self, instance_id: u16) {
+        self.0
+            .next_instance
+            .store(instance_id.wrapping_add(1), Ordering::Relaxed);
+    }
+}
+
+/// filter for symbol instances
+#[derive(Debug, Clone, Default)]
+pub struct SymbolFilter {
+    prefix: Option<String>,
+    glob: Option<String>,
+    skip_system: bool,
+    skip_module_defined: bool,
+}
+
+impl SymbolFilter {
+    /// does the symbol instance pass the filter
+    pub fn matches(&self, item: &SymbolInstance) -> bool {
+        if self.skip_system && item.is_system() {
+            return false;
+        }
+        if self.skip_module_defined && item.is_module_defined() {
+            return false;
+        }
+        if let Some(ref prefix) = self.prefix {
+            let name = item.name.as_bytes();
+            if name.len() < prefix.len()
+                || !name[..prefix.len()].eq_ignore_ascii_case(prefix.as_bytes())
+            {
+                return false;
+            }
+        }
+        if let Some(ref pattern) = self.glob {
+            if !glob_match(pattern.as_bytes(), item.name.as_bytes()) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// glob matching ignore case, supports `*` and `?`
+pub(crate) fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // position of last `*` in pattern, and the text position it matched up to
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == b'?' || c.eq_ignore_ascii_case(&text[t]) => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((sp, st)) => {
+                    // let the last `*` match one more character
+                    p = sp + 1;
+                    t = st + 1;
+                    star = Some((sp, st + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
 async fn get_attribute_list<T: MessageService<Error = ClientError>>(
     ctx: &mut T,
+    program: Option<&str>,
     start_instance: u16,
 ) -> Result<(bool, Bytes)> {
     const SERVICE_GET_INSTANCE_ATTRIBUTE_LIST: u8 = 0x55;
-    let path = EPath::default()
-        .with_class(CLASS_SYMBOL)
-        .with_instance(start_instance);
+    let path = match program {
+        Some(name) => EPath::from_symbol(format!("Program:{}", name)),
+        None => EPath::default(),
+    };
+    let path = path.with_class(CLASS_SYMBOL).with_instance(start_instance);
     let data: &[u8] = &[
         0x02, 0x00, // number of attributes
         0x01, 0x00, // attribute 1 - symbol name
@@ -379,5 +664,56 @@ mod tests {
         let sym_type = SymbolType(0x82E9);
         assert!(sym_type.is_struct());
         assert!(!sym_type.is_atomic());
+        assert!(!sym_type.is_system());
+        assert!(SymbolType(0x10C4).is_system());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match(b"*", b""));
+        assert!(glob_match(b"*", b"abc"));
+        assert!(glob_match(b"test_*", b"Test_Car1_x"));
+        assert!(glob_match(b"test_car?_x", b"test_car1_x"));
+        assert!(glob_match(b"*car*_x", b"test_car1_x"));
+        assert!(glob_match(b"a*b*c", b"aXXbYYc"));
+        assert!(!glob_match(b"a*b*c", b"aXXbYY"));
+        assert!(!glob_match(b"test_?", b"test_"));
+        assert!(!glob_match(b"abc", b"abcd"));
+    }
+
+    #[test]
+    fn test_symbol_filter() {
+        let item = |name: &'static str, symbol_type: u16| SymbolInstance {
+            id: 1,
+            name: Cow::Borrowed(name),
+            symbol_type: SymbolType(symbol_type),
+        };
+        let filter = SymbolFilter {
+            prefix: Some("test".into()),
+            glob: None,
+            skip_system: true,
+            skip_module_defined: true,
+        };
+        assert!(filter.matches(&item("TEST_car1_x", 0xC4)));
+        assert!(!filter.matches(&item("car1_x", 0xC4)));
+        assert!(!filter.matches(&item("test:I", 0xC4)));
+        assert!(!filter.matches(&item("Local:1:I", 0xC4)));
+        assert!(!filter.matches(&item("test_sys", 0x10C4)));
+        assert!(!filter.matches(&item("__test", 0xC4)));
+
+        let filter = SymbolFilter {
+            glob: Some("*_x".into()),
+            ..Default::default()
+        };
+        assert!(filter.matches(&item("test_car1_x", 0xC4)));
+        assert!(!filter.matches(&item("test_car1_y", 0xC4)));
+
+        let filter = SymbolFilter {
+            skip_module_defined: true,
+            ..Default::default()
+        };
+        assert!(filter.matches(&item("Program:Main.X", 0xC4)));
+        assert!(filter.matches(&item("program:Main", 0x1068)));
+        assert!(!filter.matches(&item("Local:1:I", 0xC4)));
     }
 }
@@ -4,7 +4,7 @@
 // Copyright: 2021, Joylei <leingliu@gmail.com>
 // License: MIT
 
-use crate::ClientError;
+use crate::{cip::data_type::ElementaryType, ClientError};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 use core::marker::PhantomData;
 use rseip_core::{codec::*, Error};
@@ -56,6 +56,9 @@ pub enum TagType {
     Lint,
     /// atomic data type: REAL, 32-bit float
     Real,
+    /// other types by type code, eg. USINT (0xC6), UINT (0xC7), UDINT (0xC8), ULINT (0xC9),
+    /// LREAL (0xCB) and STRING (0xDA, 1 byte length + characters) of Micro800
+    Other(u16),
     /// structured tag
     Structure(u16),
 }
@@ -72,6 +75,7 @@ impl TagType {
             Self::Dint => 0xC4,
             Self::Lint => 0xC5,
             Self::Real => 0xCA,
+            Self::Other(v) => *v,
             Self::Structure { .. } => 0x02A0,
         }
     }
@@ -96,6 +100,32 @@ impl TagType {
             _ => None,
         }
     }
+
+    /// atomic type from type code
+    pub fn from_type_code(type_code: u16) -> Option<Self> {
+        let val = match type_code {
+            0xD3 => TagType::Dword,
+            0xDA => TagType::Other(type_code),
+            _ => match ElementaryType::from_code(type_code)? {
+                ElementaryType::Bool => TagType::Bool,
+                ElementaryType::Sint => TagType::Sint,
+                ElementaryType::Int => TagType::Int,
+                ElementaryType::Dint => TagType::Dint,
+                ElementaryType::Lint => TagType::Lint,
+                ElementaryType::Real => TagType::Real,
+                _ => TagType::Other(type_code),
+            },
+        };
+        Some(val)
+    }
+
+    /// size in bytes of atomic types, `None` for structures and strings
+    pub fn size(&self) -> Option<usize> {
+        match self {
+            Self::Structure(_) => None,
+            _ => ElementaryType::from_code(self.type_code()).map(|v| v.size()),
+        }
+    }
 }
 
 impl Encode for TagType {
@@ -127,6 +157,9 @@ impl Encode for TagType {
             Self::Lint => {
                 encoder.encode_u16(0xC5, buf)?;
             }
+            Self::Other(type_code) => {
+                encoder.encode_u16(*type_code, buf)?;
+            }
             Self::Structure(handle) => {
                 encoder.encode(&[0xA0, 0x02], buf)?;
                 encoder.encode_u16(*handle, buf)?;
@@ -152,26 +185,12 @@ impl<'de> Decode<'de> for TagType {
     {
         decoder.ensure_size(3)?;
         let type_code = decoder.decode_u16();
-        let val = match type_code {
-            0xC2 => TagType::Sint,
-            0xC3 => TagType::Int,
-            0xC4 => TagType::Dint,
-            0xCA => TagType::Real,
-            0xD3 => TagType::Dword,
-            0xC5 => TagType::Lint,
-            0xC1 => TagType::Bool,
-            0x02A0 => {
-                decoder.ensure_size(2)?;
-                TagType::Structure(decoder.decode_u16())
-            }
-            _ => {
-                return Err(Error::custom(format!(
-                    "unexpected type code: {}",
-                    type_code
-                )))
-            }
-        };
-        Ok(val)
+        if type_code == 0x02A0 {
+            decoder.ensure_size(2)?;
+            return Ok(TagType::Structure(decoder.decode_u16()));
+        }
+        TagType::from_type_code(type_code)
+            .ok_or_else(|| Error::custom(format!("unexpected type code: {}", type_code)))
     }
 }
 
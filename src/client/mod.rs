@@ -8,16 +8,24 @@
 pub mod ab_eip;
 /// generic EIP
 pub mod eip;
+/// PCCC over EIP
+pub mod pccc;
+/// controller profile detection
+pub mod profile;
+mod store;
 
 use crate::{adapters::Service, ClientError, Result};
-pub use ab_eip::{AbEipClient, AbEipConnection, AbEipDriver, AbService, AbTemplateService};
-use bytes::Bytes;
+pub use ab_eip::{
+    AbControllerService, AbEipClient, AbEipConnection, AbEipDriver, AbService, AbTemplateService,
+};
+use bytes::{Bytes, BytesMut};
 use core::{
     fmt,
     ops::{Deref, DerefMut},
 };
 pub use eip::*;
 use futures_util::future::BoxFuture;
+pub use profile::{ControllerFamily, ControllerProfile, DetectedClient, DetectedConnection};
 /// reexport
 pub use rseip_cip::connection::OpenOptions;
 use rseip_cip::{
@@ -30,7 +38,12 @@ use rseip_core::{
     codec::{Decode, Encode},
     Either, Error,
 };
-use std::{io, sync::atomic::AtomicU16};
+use rseip_eip::ClientCodec;
+use std::{
+    io,
+    sync::{atomic::AtomicU16, Arc},
+};
+pub use store::{ConnectionRecord, ConnectionStore, FileStore};
 
 /// driver for specified protocol
 pub trait Driver: Send + Sync {
@@ -161,6 +174,25 @@ pub struct Connection<B: Driver> {
     service: Option<B::Service>,
     /// sequence number
     seq_id: AtomicU16,
+    /// persisted connections, with the endpoint of records
+    store: Option<(Arc<dyn ConnectionStore>, String)>,
+    /// connections of previous run closed?
+    stale_closed: bool,
+}
+
+impl<B: Driver> Connection<B>
+where
+    B::Endpoint: fmt::Display,
+{
+    /// persist opened connections in the store, and close connections
+    /// recorded by a previous run of the same originator on first open;
+    /// records are keyed by the displayed endpoint, eg. `192.168.0.83:44818`
+    #[inline]
+    pub fn with_store(mut self, store: Arc<dyn ConnectionStore>) -> Self {
+        let endpoint = self.addr.to_string();
+        self.store = Some((store, endpoint));
+        self
+    }
 }
 
 impl<B: Driver> Connection<B> {
@@ -173,6 +205,8 @@ impl<B: Driver> Connection<B> {
             connected_options: None,
             service: None,
             seq_id: Default::default(),
+            store: None,
+            stale_closed: false,
         }
     }
 
@@ -236,6 +270,10 @@ impl<B: Driver> Connection<B> {
     async fn open_connection(&mut self) -> Result<u32> {
         // create service if not created
         self.ensure_service().await?;
+        if !self.stale_closed {
+            self.close_stale_connections().await;
+            self.stale_closed = true;
+        }
         let service = self.service.as_mut().expect("expected service");
         if self.connected_options.is_none() {
             let reply = service.forward_open(self.origin_options.clone()).await?;
@@ -248,6 +286,11 @@ impl<B: Driver> Connection<B> {
                         .connection_serial_number(reply.connection_serial_number)
                         .o_t_rpi(reply.o_t_api)
                         .t_o_rpi(reply.t_o_api);
+                    if let Some((ref store, _)) = self.store {
+                        if let Some(record) = self.record_of(&opts) {
+                            store.save(&record);
+                        }
+                    }
                     self.connected_options = Some(opts);
                 }
                 Either::Right(_) => return Err(Error::custom("forward open failed")),
@@ -260,6 +303,11 @@ impl<B: Driver> Connection<B> {
     #[inline]
     async fn close_connection(&mut self) -> Result<()> {
         if let Some(conn) = self.connected_options.take() {
+            if let Some((ref store, _)) = self.store {
+                if let Some(record) = self.record_of(&conn) {
+                    store.remove(&record);
+                }
+            }
             if let Some(service) = self.service.as_mut() {
                 let request = ForwardCloseRequest {
                     priority_time_ticks: self.origin_options.priority_tick_time,
@@ -274,6 +322,59 @@ impl<B: Driver> Connection<B> {
         }
         Ok(())
     }
+
+    /// record of the connection to persist
+    fn record_of(&self, opts: &OpenOptions) -> Option<ConnectionRecord> {
+        let (_, endpoint) = self.store.as_ref()?;
+        let mut buf = BytesMut::new();
+        let mut encoder = ClientCodec::<ClientError>::new();
+        if let Err(e) = opts.connection_path.encode_by_ref(&mut buf, &mut encoder) {
+            log::warn!("connection store - failed to encode connection path: {}", e);
+            return None;
+        }
+        Some(ConnectionRecord {
+            endpoint: endpoint.clone(),
+            connection_serial_number: opts.connection_serial_number,
+            vendor_id: opts.vendor_id,
+            originator_serial_number: opts.originator_serial_number,
+            connection_path: buf.freeze(),
+        })
+    }
+
+    /// best-effort Forward_Close of connections recorded by a previous run
+    /// of the same originator to the same endpoint
+    async fn close_stale_connections(&mut self) {
+        let (store, endpoint) = match self.store {
+            Some((ref store, ref endpoint)) => (store.clone(), endpoint.clone()),
+            None => return,
+        };
+        let service = self.service.as_mut().expect("expected service");
+        for record in store.load() {
+            if record.endpoint != endpoint
+                || record.vendor_id != self.origin_options.vendor_id
+                || record.originator_serial_number != self.origin_options.originator_serial_number
+            {
+                continue;
+            }
+            let request = ForwardCloseRequest {
+                priority_time_ticks: self.origin_options.priority_tick_time,
+                timeout_ticks: self.origin_options.timeout_ticks,
+                connection_serial_number: record.connection_serial_number,
+                originator_serial_number: record.originator_serial_number,
+                originator_vendor_id: record.vendor_id,
+                connection_path: record.connection_path.clone(),
+            };
+            match service.forward_close(request).await {
+                Ok(_) => log::info!("connection store - closed stale connection: {}", record),
+                Err(e) => log::warn!(
+                    "connection store - failed to close stale connection: {}, {}",
+                    record,
+                    e
+                ),
+            }
+            store.remove(&record);
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -0,0 +1,379 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+//! PCCC over EtherNet/IP for SLC 500, PLC-5 and MicroLogix controllers,
+//! by Execute PCCC service of PCCC object.
+//!
+//! ```rust,ignore
+//! let client = EipClient::new_host_lookup("192.168.0.83").await?;
+//! let mut client = PcccClient::new(client);
+//! let addr: PcccAddress = "N7:0".parse()?;
+//! let value: i16 = client.read(&addr).await?;
+//! client.write(&addr, value + 1).await?;
+//! let dn = client.read_bit(&"T4:1.DN".parse()?).await?;
+//! ```
+
+mod address;
+mod value;
+
+pub use address::{FileType, PcccAddress};
+pub use value::{PcccControl, PcccCounter, PcccString, PcccTimer, PcccValue, STRING_MAX_LEN};
+
+use super::EipClient;
+use crate::{
+    cip::{
+        epath::EPath,
+        service::{send_and_extract, MessageService},
+    },
+    ClientError, Result, StdResult,
+};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use rseip_core::{
+    codec::{Decode, Decoder, Encode, Encoder, LittleEndianDecoder},
+    Error,
+};
+
+/// PCCC object
+const CLASS_PCCC: u16 = 0x67;
+/// Execute PCCC service
+const SERVICE_EXECUTE_PCCC: u8 = 0x4B;
+/// command of typed logical read/write
+const CMD_TYPED: u8 = 0x0F;
+/// protected typed logical read with 3 address fields
+const FNC_READ: u8 = 0xA2;
+/// protected typed logical write with 3 address fields
+const FNC_WRITE: u8 = 0xAA;
+/// protected typed logical masked write with 3 address fields
+const FNC_MASKED_WRITE: u8 = 0xAB;
+/// STS when EXT STS follows
+const STS_EXTENDED: u8 = 0xF0;
+
+/// PCCC client for SLC 500, PLC-5 and MicroLogix
+#[derive(Debug)]
+pub struct PcccClient<C = EipClient> {
+    inner: C,
+    vendor_id: u16,
+    serial_number: u32,
+    tns: u16,
+}
+
+impl<C> PcccClient<C> {
+    /// new PCCC client
+    #[inline]
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            vendor_id: 0xFFFF,
+            serial_number: 0x5253_4950,
+            tns: 0,
+        }
+    }
+
+    /// requestor id, vendor id and serial number
+    #[inline]
+    pub fn with_requestor(mut self, vendor_id: u16, serial_number: u32) -> Self {
+        self.vendor_id = vendor_id;
+        self.serial_number = serial_number;
+        self
+    }
+
+    /// inner client
+    #[inline]
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// inner client
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut C {
+        &mut self.inner
+    }
+
+    /// into inner client
+    #[inline]
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    #[inline]
+    fn next_tns(&mut self) -> u16 {
+        self.tns = self.tns.wrapping_add(1);
+        self.tns
+    }
+}
+
+impl<C: MessageService<Error = ClientError>> PcccClient<C> {
+    /// read raw bytes of the address
+    pub async fn read_raw(&mut self, addr: &PcccAddress, size: u8) -> Result<Bytes> {
+        self.execute(FNC_READ, addr, size, ()).await
+    }
+
+    /// write raw bytes to the address
+    pub async fn write_raw(&mut self, addr: &PcccAddress, data: &[u8]) -> Result<()> {
+        let size = byte_size(data.len())?;
+        self.execute(FNC_WRITE, addr, size, data).await?;
+        Ok(())
+    }
+
+    /// read the element or sub-element of the address
+    pub async fn read<R>(&mut self, addr: &PcccAddress) -> Result<R>
+    where
+        R: Decode<'static> + 'static,
+    {
+        let size = byte_size(addr.data_size())?;
+        let data = self.read_raw(addr, size).await?;
+        R::decode(LittleEndianDecoder::<ClientError>::new(data))
+    }
+
+    /// write the element or sub-element of the address
+    pub async fn write<D>(&mut self, addr: &PcccAddress, value: D) -> Result<()>
+    where
+        D: Encode + Send + Sync,
+    {
+        let size = byte_size(value.bytes_count())?;
+        self.execute(FNC_WRITE, addr, size, value).await?;
+        Ok(())
+    }
+
+    /// read the bit of the address, eg. `B3:0/5`, `T4:1.DN`
+    pub async fn read_bit(&mut self, addr: &PcccAddress) -> Result<bool> {
+        let bit = addr.bit.ok_or_else(|| no_bit(addr))?;
+        let word: u16 = self.read(addr).await?;
+        Ok(word & (1 << bit) != 0)
+    }
+
+    /// write the bit of the address, eg. `B3:0/5`, `T4:1.DN`;
+    /// other bits of the word are not affected
+    pub async fn write_bit(&mut self, addr: &PcccAddress, value: bool) -> Result<()> {
+        let bit = addr.bit.ok_or_else(|| no_bit(addr))?;
+        let mask = 1_u16 << bit;
+        let data = if value { mask } else { 0 };
+        self.execute(FNC_MASKED_WRITE, addr, 2, (mask, data))
+            .await?;
+        Ok(())
+    }
+
+    /// read the value of the address, typed by the file type
+    pub async fn read_value(&mut self, addr: &PcccAddress) -> Result<PcccValue> {
+        if addr.bit.is_some() {
+            return self.read_bit(addr).await.map(PcccValue::Bit);
+        }
+        if addr.has_sub_element {
+            return self.read(addr).await.map(PcccValue::Integer);
+        }
+        let res = match addr.file_type {
+            FileType::Float => PcccValue::Float(self.read(addr).await?),
+            FileType::Long => PcccValue::Long(self.read(addr).await?),
+            FileType::Timer => PcccValue::Timer(self.read(addr).await?),
+            FileType::Counter => PcccValue::Counter(self.read(addr).await?),
+            FileType::Control => PcccValue::Control(self.read(addr).await?),
+            FileType::String => {
+                let PcccString(v) = self.read(addr).await?;
+                PcccValue::String(v)
+            }
+            _ => PcccValue::Integer(self.read(addr).await?),
+        };
+        Ok(res)
+    }
+
+    /// write the value to the address
+    pub async fn write_value(&mut self, addr: &PcccAddress, value: &PcccValue) -> Result<()> {
+        match value {
+            PcccValue::Bit(v) => self.write_bit(addr, *v).await,
+            _ => self.write(addr, value).await,
+        }
+    }
+
+    /// close the inner client
+    #[inline]
+    pub async fn close(&mut self) -> Result<()> {
+        self.inner.close().await
+    }
+
+    async fn execute<D>(&mut self, fnc: u8, addr: &PcccAddress, size: u8, data: D) -> Result<Bytes>
+    where
+        D: Encode + Send + Sync,
+    {
+        let tns = self.next_tns();
+        let req = PcccRequest {
+            vendor_id: self.vendor_id,
+            serial_number: self.serial_number,
+            tns,
+            fnc,
+            size,
+            addr,
+            data,
+        };
+        let path = EPath::default().with_class(CLASS_PCCC).with_instance(0x01);
+        let reply: PcccReply =
+            send_and_extract(&mut self.inner, SERVICE_EXECUTE_PCCC, path, req).await?;
+        if reply.tns != tns {
+            return Err(ClientError::custom(format!(
+                "PCCC - unexpected TNS: {}, expected: {}",
+                reply.tns, tns
+            )));
+        }
+        Ok(reply.data)
+    }
+}
+
+#[inline]
+fn byte_size(size: usize) -> Result<u8> {
+    u8::try_from(size).map_err(|_| ClientError::custom("PCCC - data too large"))
+}
+
+#[inline]
+fn no_bit(addr: &PcccAddress) -> ClientError {
+    ClientError::custom(format!("PCCC - not a bit address: {}", addr))
+}
+
+/// encode address field, 1 byte if less than 255, otherwise 0xFF + 2 bytes
+#[inline]
+fn put_address_field(buf: &mut BytesMut, v: u16) {
+    if v < 0xFF {
+        buf.put_u8(v as u8);
+    } else {
+        buf.put_u8(0xFF);
+        buf.put_u16_le(v);
+    }
+}
+
+#[inline]
+fn address_field_size(v: u16) -> usize {
+    if v < 0xFF {
+        1
+    } else {
+        3
+    }
+}
+
+/// Execute PCCC request data
+struct PcccRequest<'a, D> {
+    vendor_id: u16,
+    serial_number: u32,
+    tns: u16,
+    fnc: u8,
+    size: u8,
+    addr: &'a PcccAddress,
+    data: D,
+}
+
+impl<D: Encode> Encode for PcccRequest<'_, D> {
+    #[inline]
+    fn encode_by_ref<A: Encoder>(
+        &self,
+        buf: &mut BytesMut,
+        encoder: &mut A,
+    ) -> StdResult<(), A::Error> {
+        // requestor id
+        buf.put_u8(7);
+        buf.put_u16_le(self.vendor_id);
+        buf.put_u32_le(self.serial_number);
+        // command
+        buf.put_u8(CMD_TYPED);
+        buf.put_u8(0);
+        buf.put_u16_le(self.tns);
+        buf.put_u8(self.fnc);
+        buf.put_u8(self.size);
+        put_address_field(buf, self.addr.file_number);
+        buf.put_u8(self.addr.file_type.code());
+        put_address_field(buf, self.addr.element);
+        put_address_field(buf, self.addr.sub_element);
+        self.data.encode_by_ref(buf, encoder)
+    }
+
+    #[inline]
+    fn bytes_count(&self) -> usize {
+        14 + address_field_size(self.addr.file_number)
+            + address_field_size(self.addr.element)
+            + address_field_size(self.addr.sub_element)
+            + self.data.bytes_count()
+    }
+}
+
+/// Execute PCCC reply data
+struct PcccReply {
+    tns: u16,
+    data: Bytes,
+}
+
+impl<'de> Decode<'de> for PcccReply {
+    #[inline]
+    fn decode<D>(mut decoder: D) -> StdResult<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        decoder.ensure_size(1)?;
+        let len = decoder.decode_u8() as usize;
+        if len == 0 {
+            return Err(Error::invalid_value("requestor id length 0", 7));
+        }
+        decoder.ensure_size(len - 1 + 4)?;
+        decoder.buf_mut().advance(len - 1);
+        let _cmd = decoder.decode_u8();
+        let sts = decoder.decode_u8();
+        let tns = decoder.decode_u16();
+        if sts != 0 {
+            let ext_sts = if sts == STS_EXTENDED && decoder.has_remaining() {
+                decoder.decode_u8()
+            } else {
+                0
+            };
+            return Err(Error::custom(format!(
+                "PCCC - bad status: {:#04x}, extended status: {:#04x}",
+                sts, ext_sts
+            )));
+        }
+        let remaining = decoder.remaining();
+        let data = decoder.buf_mut().copy_to_bytes(remaining);
+        Ok(Self { tns, data })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rseip_core::tests::EncodeExt;
+
+    #[test]
+    fn test_encode_request() {
+        let addr: PcccAddress = "N7:300".parse().unwrap();
+        let req = PcccRequest {
+            vendor_id: 0xFFFF,
+            serial_number: 0x5253_4950,
+            tns: 1,
+            fnc: FNC_WRITE,
+            size: 2,
+            addr: &addr,
+            data: 5_i16,
+        };
+        assert_eq!(req.bytes_count(), 21);
+        let buf = req.try_into_bytes().unwrap();
+        assert_eq!(
+            &buf[..],
+            &[
+                7, 0xFF, 0xFF, 0x50, 0x49, 0x53, 0x52, 0x0F, 0, 1, 0, 0xAA, 2, 7, 0x89, 0xFF, 0x2C,
+                0x01, 0, 5, 0
+            ][..]
+        );
+    }
+
+    #[test]
+    fn test_decode_reply() {
+        let buf = Bytes::from_static(&[
+            7, 0xFF, 0xFF, 0x50, 0x49, 0x53, 0x52, 0x4F, 0, 1, 0, 5, 0,
+        ]);
+        let reply = PcccReply::decode(LittleEndianDecoder::<ClientError>::new(buf)).unwrap();
+        assert_eq!(reply.tns, 1);
+        assert_eq!(&reply.data[..], &[5, 0]);
+
+        let buf = Bytes::from_static(&[
+            7, 0xFF, 0xFF, 0x50, 0x49, 0x53, 0x52, 0x4F, 0xF0, 1, 0, 6,
+        ]);
+        let res = PcccReply::decode(LittleEndianDecoder::<ClientError>::new(buf));
+        assert!(res.is_err());
+    }
+}
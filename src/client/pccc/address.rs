@@ -0,0 +1,382 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+use crate::client::ab_eip::PathError;
+use core::{fmt, str::FromStr};
+
+/// data table file type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileType {
+    /// O, output
+    Output,
+    /// I, input
+    Input,
+    /// S, status
+    Status,
+    /// B, bit
+    Bit,
+    /// T, timer
+    Timer,
+    /// C, counter
+    Counter,
+    /// R, control
+    Control,
+    /// N, integer
+    Integer,
+    /// F, float
+    Float,
+    /// ST, string
+    String,
+    /// L, long integer
+    Long,
+}
+
+impl FileType {
+    /// file type code used in PCCC commands
+    #[inline]
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::Output => 0x82,
+            Self::Input => 0x83,
+            Self::Status => 0x84,
+            Self::Bit => 0x85,
+            Self::Timer => 0x86,
+            Self::Counter => 0x87,
+            Self::Control => 0x88,
+            Self::Integer => 0x89,
+            Self::Float => 0x8A,
+            Self::String => 0x8D,
+            Self::Long => 0x91,
+        }
+    }
+
+    /// element size in bytes
+    #[inline]
+    pub fn element_size(&self) -> usize {
+        match self {
+            Self::Timer | Self::Counter | Self::Control => 6,
+            Self::Float | Self::Long => 4,
+            Self::String => 84,
+            _ => 2,
+        }
+    }
+
+    /// address prefix
+    #[inline]
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Self::Output => "O",
+            Self::Input => "I",
+            Self::Status => "S",
+            Self::Bit => "B",
+            Self::Timer => "T",
+            Self::Counter => "C",
+            Self::Control => "R",
+            Self::Integer => "N",
+            Self::Float => "F",
+            Self::String => "ST",
+            Self::Long => "L",
+        }
+    }
+
+    /// default file number, `None` if file number is required
+    #[inline]
+    fn default_file_number(&self) -> Option<u16> {
+        match self {
+            Self::Output => Some(0),
+            Self::Input => Some(1),
+            Self::Status => Some(2),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn from_prefix(prefix: &str) -> Option<Self> {
+        let res = match prefix {
+            "O" => Self::Output,
+            "I" => Self::Input,
+            "S" => Self::Status,
+            "B" => Self::Bit,
+            "T" => Self::Timer,
+            "C" => Self::Counter,
+            "R" => Self::Control,
+            "N" => Self::Integer,
+            "F" => Self::Float,
+            "ST" => Self::String,
+            "L" => Self::Long,
+            _ => return None,
+        };
+        Some(res)
+    }
+
+    /// name of the member at the sub-element word and bit, eg. `ACC`, `DN`
+    #[inline]
+    fn member_name(&self, sub_element: u16, bit: Option<u8>) -> Option<&'static str> {
+        const NAMES: &[&str] = &[
+            "PRE", "ACC", "EN", "TT", "DN", "CU", "CD", "OV", "UN", "LEN", "POS", "EU", "EM", "ER",
+            "UL", "IN", "FD",
+        ];
+        NAMES
+            .iter()
+            .find(|name| self.member(name) == Some((sub_element, bit)))
+            .copied()
+    }
+
+    /// I/O files, addressed by slot and word, eg. `I:1.0/3`
+    #[inline]
+    fn is_io(&self) -> bool {
+        matches!(self, Self::Input | Self::Output)
+    }
+
+    /// sub-element word and bit of named members, eg. `ACC`, `DN`
+    #[inline]
+    fn member(&self, name: &str) -> Option<(u16, Option<u8>)> {
+        let res = match (self, name) {
+            (Self::Timer | Self::Counter, "PRE") => (1, None),
+            (Self::Timer | Self::Counter, "ACC") => (2, None),
+            (Self::Timer, "EN") => (0, Some(15)),
+            (Self::Timer, "TT") => (0, Some(14)),
+            (Self::Timer | Self::Counter | Self::Control, "DN") => (0, Some(13)),
+            (Self::Counter, "CU") => (0, Some(15)),
+            (Self::Counter, "CD") => (0, Some(14)),
+            (Self::Counter, "OV") => (0, Some(12)),
+            (Self::Counter, "UN") => (0, Some(11)),
+            (Self::Control, "LEN") => (1, None),
+            (Self::Control, "POS") => (2, None),
+            (Self::Control, "EN") => (0, Some(15)),
+            (Self::Control, "EU") => (0, Some(14)),
+            (Self::Control, "EM") => (0, Some(12)),
+            (Self::Control, "ER") => (0, Some(11)),
+            (Self::Control, "UL") => (0, Some(10)),
+            (Self::Control, "IN") => (0, Some(9)),
+            (Self::Control, "FD") => (0, Some(8)),
+            (Self::String, "LEN") => (0, None),
+            _ => return None,
+        };
+        Some(res)
+    }
+}
+
+/// data table address, eg. `N7:0`, `F8:10`, `B3:0/5`, `B3/37`, `T4:1.ACC`, `ST9:0`;
+/// I/O addresses are slot and word, eg. `I:1.0/3`, `O:2.1`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PcccAddress {
+    /// file type
+    pub file_type: FileType,
+    /// file number
+    pub file_number: u16,
+    /// element number, slot for I/O files
+    pub element: u16,
+    /// sub-element number, eg. 2 for `T4:1.ACC`, or word for I/O files, eg. 1 for `O:2.1`
+    pub sub_element: u16,
+    /// true if sub-element specified
+    pub has_sub_element: bool,
+    /// bit number, eg. 5 for `B3:0/5`
+    pub bit: Option<u8>,
+}
+
+impl PcccAddress {
+    /// parse data table address
+    pub fn parse(address: impl AsRef<str>) -> Result<Self, PathError> {
+        let buf = address.as_ref().trim().to_ascii_uppercase();
+        if buf.is_empty() {
+            return Err(PathError::Empty);
+        }
+        let prefix_len = buf
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(buf.len());
+        let file_type =
+            FileType::from_prefix(&buf[..prefix_len]).ok_or(PathError::NameParseError)?;
+        let rest = &buf[prefix_len..];
+        let num_len = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let file_number = if num_len > 0 {
+            parse_number(&rest[..num_len])?
+        } else {
+            file_type
+                .default_file_number()
+                .ok_or(PathError::NumberParseError)?
+        };
+        let rest = &rest[num_len..];
+
+        let mut res = Self {
+            file_type,
+            file_number,
+            element: 0,
+            sub_element: 0,
+            has_sub_element: false,
+            bit: None,
+        };
+        if let Some(bit) = rest.strip_prefix('/') {
+            // bit addressing, eg. B3/37
+            let bit = parse_number(bit)?;
+            res.element = bit / 16;
+            res.bit = Some((bit % 16) as u8);
+            return Ok(res);
+        }
+        let rest = rest.strip_prefix(':').ok_or(PathError::SyntaxError)?;
+        let num_len = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        res.element = parse_number(&rest[..num_len])?;
+        let rest = &rest[num_len..];
+        if rest.is_empty() {
+            return Ok(res);
+        }
+        if let Some(bit) = rest.strip_prefix('/') {
+            let bit = parse_number(bit)?;
+            if bit >= 16 {
+                return Err(PathError::SyntaxError);
+            }
+            res.bit = Some(bit as u8);
+        } else if let Some(name) = rest.strip_prefix('.') {
+            let (sub_element, bit) = if file_type.is_io() {
+                // word of the slot, with optional bit
+                let (word, bit) = match name.split_once('/') {
+                    Some((word, bit)) => (word, Some(parse_number(bit)?)),
+                    None => (name, None),
+                };
+                if matches!(bit, Some(v) if v >= 16) {
+                    return Err(PathError::SyntaxError);
+                }
+                (parse_number(word)?, bit.map(|v| v as u8))
+            } else {
+                file_type.member(name).ok_or(PathError::NameParseError)?
+            };
+            res.sub_element = sub_element;
+            res.has_sub_element = true;
+            res.bit = bit;
+        } else {
+            return Err(PathError::SyntaxError);
+        }
+        Ok(res)
+    }
+
+    /// number of bytes to read or write for one element
+    #[inline]
+    pub fn data_size(&self) -> usize {
+        if self.has_sub_element || self.bit.is_some() {
+            2
+        } else {
+            self.file_type.element_size()
+        }
+    }
+}
+
+impl FromStr for PcccAddress {
+    type Err = PathError;
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for PcccAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}:{}",
+            self.file_type.prefix(),
+            self.file_number,
+            self.element
+        )?;
+        if self.has_sub_element && !self.file_type.is_io() {
+            if let Some(name) = self.file_type.member_name(self.sub_element, self.bit) {
+                return write!(f, ".{}", name);
+            }
+        }
+        if self.has_sub_element {
+            write!(f, ".{}", self.sub_element)?;
+        }
+        if let Some(bit) = self.bit {
+            write!(f, "/{}", bit)?;
+        }
+        Ok(())
+    }
+}
+
+#[inline]
+fn parse_number(buf: &str) -> Result<u16, PathError> {
+    if buf.is_empty() {
+        return Err(PathError::Eof);
+    }
+    buf.parse().map_err(|_| PathError::NumberParseError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_addresses() {
+        let addr = PcccAddress::parse("N7:0").unwrap();
+        assert_eq!(addr.file_type, FileType::Integer);
+        assert_eq!((addr.file_number, addr.element), (7, 0));
+        assert_eq!(addr.data_size(), 2);
+
+        let addr = PcccAddress::parse("f8:10").unwrap();
+        assert_eq!(addr.file_type, FileType::Float);
+        assert_eq!((addr.file_number, addr.element), (8, 10));
+        assert_eq!(addr.data_size(), 4);
+
+        let addr = PcccAddress::parse("B3:0/5").unwrap();
+        assert_eq!(addr.file_type, FileType::Bit);
+        assert_eq!((addr.element, addr.bit), (0, Some(5)));
+
+        let addr = PcccAddress::parse("B3/37").unwrap();
+        assert_eq!((addr.element, addr.bit), (2, Some(5)));
+
+        let addr = PcccAddress::parse("T4:1.ACC").unwrap();
+        assert_eq!(addr.file_type, FileType::Timer);
+        assert_eq!((addr.element, addr.sub_element), (1, 2));
+        assert!(addr.has_sub_element);
+        assert_eq!(addr.data_size(), 2);
+
+        let addr = PcccAddress::parse("T4:1.DN").unwrap();
+        assert_eq!((addr.sub_element, addr.bit), (0, Some(13)));
+
+        let addr = PcccAddress::parse("ST9:0").unwrap();
+        assert_eq!(addr.file_type, FileType::String);
+        assert_eq!(addr.data_size(), 84);
+
+        let addr = PcccAddress::parse("S:1").unwrap();
+        assert_eq!((addr.file_number, addr.element), (2, 1));
+
+        let addr = PcccAddress::parse("I:1.0/3").unwrap();
+        assert_eq!(addr.file_type, FileType::Input);
+        assert_eq!((addr.file_number, addr.element), (1, 1));
+        assert_eq!((addr.sub_element, addr.bit), (0, Some(3)));
+        assert_eq!(addr.data_size(), 2);
+
+        let addr = PcccAddress::parse("O:2.1").unwrap();
+        assert_eq!((addr.file_number, addr.element), (0, 2));
+        assert_eq!((addr.sub_element, addr.bit), (1, None));
+    }
+
+    #[test]
+    fn test_display() {
+        let items = [
+            "N7:0", "B3:0/5", "T4:1.ACC", "T4:1.DN", "C5:0.PRE", "R6:2.IN", "I1:1.0/3", "O0:2.1",
+        ];
+        for item in items {
+            let addr = PcccAddress::parse(item).unwrap();
+            assert_eq!(addr.to_string(), item);
+            assert_eq!(PcccAddress::parse(addr.to_string()).unwrap(), addr);
+        }
+    }
+
+    #[test]
+    fn test_invalid_addresses() {
+        let items = [
+            "", "X7:0", "N:0", "N7", "N7:", "N7:a", "N7:0/16", "T4:1.XYZ", "N7:0.ACC", "N7-0",
+            "I:1.x", "I:1.0/16",
+        ];
+        for item in items {
+            assert!(PcccAddress::parse(item).is_err(), "{}", item);
+        }
+    }
+}
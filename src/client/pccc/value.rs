@@ -0,0 +1,229 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+use crate::{client::ab_eip::predefined::impl_predefined, StdResult};
+use bytes::{BufMut, BytesMut};
+use rseip_core::codec::{Decode, Decoder, Encode, Encoder};
+
+/// max length of `ST` string
+pub const STRING_MAX_LEN: usize = 82;
+
+/// timer element, `T` file
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PcccTimer {
+    /// enable bit
+    pub en: bool,
+    /// timing bit
+    pub tt: bool,
+    /// done bit
+    pub dn: bool,
+    /// preset value
+    pub pre: i16,
+    /// accumulated value
+    pub acc: i16,
+    /// raw control word as read, bits without members are kept when writing back
+    pub raw: u16,
+}
+
+impl_predefined!(PcccTimer: u16, [en: 15, tt: 14, dn: 13], pre, acc);
+
+/// counter element, `C` file
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PcccCounter {
+    /// count up enable bit
+    pub cu: bool,
+    /// count down enable bit
+    pub cd: bool,
+    /// done bit
+    pub dn: bool,
+    /// overflow bit
+    pub ov: bool,
+    /// underflow bit
+    pub un: bool,
+    /// preset value
+    pub pre: i16,
+    /// accumulated value
+    pub acc: i16,
+    /// raw control word as read, bits without members are kept when writing back
+    pub raw: u16,
+}
+
+impl_predefined!(PcccCounter: u16, [cu: 15, cd: 14, dn: 13, ov: 12, un: 11], pre, acc);
+
+/// control element, `R` file
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PcccControl {
+    /// enable bit
+    pub en: bool,
+    /// enable unload bit
+    pub eu: bool,
+    /// done bit
+    pub dn: bool,
+    /// empty bit
+    pub em: bool,
+    /// error bit
+    pub er: bool,
+    /// unload bit
+    pub ul: bool,
+    /// inhibit bit
+    pub inh: bool,
+    /// found bit
+    pub fd: bool,
+    /// length
+    pub len: i16,
+    /// position
+    pub pos: i16,
+    /// raw control word as read, bits without members are kept when writing back
+    pub raw: u16,
+}
+
+impl_predefined!(
+    PcccControl: u16,
+    [en: 15, eu: 14, dn: 13, em: 12, er: 11, ul: 10, inh: 9, fd: 8],
+    len,
+    pos
+);
+
+/// string element, `ST` file;
+/// characters are stored in words, with bytes of each word swapped
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PcccString(pub String);
+
+impl Encode for PcccString {
+    #[inline]
+    fn encode_by_ref<A: Encoder>(
+        &self,
+        buf: &mut BytesMut,
+        _encoder: &mut A,
+    ) -> StdResult<(), A::Error> {
+        let data = self.0.as_bytes();
+        if data.len() > STRING_MAX_LEN {
+            return Err(rseip_core::Error::invalid_length(
+                data.len(),
+                STRING_MAX_LEN,
+            ));
+        }
+        buf.put_u16_le(data.len() as u16);
+        let mut chars = [0_u8; STRING_MAX_LEN];
+        chars[..data.len()].copy_from_slice(data);
+        for pair in chars.chunks(2) {
+            buf.put_u8(pair[1]);
+            buf.put_u8(pair[0]);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn bytes_count(&self) -> usize {
+        2 + STRING_MAX_LEN
+    }
+}
+
+impl<'de> Decode<'de> for PcccString {
+    #[inline]
+    fn decode<D>(mut decoder: D) -> StdResult<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        decoder.ensure_size(2 + STRING_MAX_LEN)?;
+        let len = (decoder.decode_u16() as usize).min(STRING_MAX_LEN);
+        let mut chars = [0_u8; STRING_MAX_LEN];
+        for pair in chars.chunks_mut(2) {
+            pair[1] = decoder.decode_u8();
+            pair[0] = decoder.decode_u8();
+        }
+        let text = String::from_utf8_lossy(&chars[..len]).into_owned();
+        Ok(Self(text))
+    }
+}
+
+/// value of data table element
+#[derive(Debug, Clone, PartialEq)]
+pub enum PcccValue {
+    /// bit, eg. `B3:0/5`, `T4:0.DN`
+    Bit(bool),
+    /// 16-bit integer, `N`, `B`, `S`, `I`, `O` files, or sub-elements like `T4:0.ACC`
+    Integer(i16),
+    /// 32-bit integer, `L` file
+    Long(i32),
+    /// 32-bit float, `F` file
+    Float(f32),
+    /// `T` file
+    Timer(PcccTimer),
+    /// `C` file
+    Counter(PcccCounter),
+    /// `R` file
+    Control(PcccControl),
+    /// `ST` file
+    String(String),
+}
+
+impl Encode for PcccValue {
+    #[inline]
+    fn encode_by_ref<A: Encoder>(
+        &self,
+        buf: &mut BytesMut,
+        encoder: &mut A,
+    ) -> StdResult<(), A::Error> {
+        match self {
+            Self::Bit(v) => buf.put_u16_le(if *v { 1 } else { 0 }),
+            Self::Integer(v) => buf.put_i16_le(*v),
+            Self::Long(v) => buf.put_i32_le(*v),
+            Self::Float(v) => buf.put_f32_le(*v),
+            Self::Timer(v) => v.encode_by_ref(buf, encoder)?,
+            Self::Counter(v) => v.encode_by_ref(buf, encoder)?,
+            Self::Control(v) => v.encode_by_ref(buf, encoder)?,
+            Self::String(v) => PcccString(v.clone()).encode(buf, encoder)?,
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn bytes_count(&self) -> usize {
+        match self {
+            Self::Bit(_) | Self::Integer(_) => 2,
+            Self::Long(_) | Self::Float(_) => 4,
+            Self::Timer(_) | Self::Counter(_) | Self::Control(_) => 6,
+            Self::String(_) => 2 + STRING_MAX_LEN,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use rseip_core::{codec::LittleEndianDecoder, tests::EncodeExt};
+
+    #[test]
+    fn test_timer() {
+        let buf = Bytes::from_static(&[0, 0xA0, 0x64, 0, 0x0A, 0]);
+        let timer =
+            PcccTimer::decode(LittleEndianDecoder::<crate::ClientError>::new(buf.clone())).unwrap();
+        assert_eq!(
+            timer,
+            PcccTimer {
+                en: true,
+                tt: false,
+                dn: true,
+                pre: 100,
+                acc: 10,
+                raw: 0xA000,
+            }
+        );
+        assert_eq!(timer.try_into_bytes().unwrap(), buf);
+    }
+
+    #[test]
+    fn test_string() {
+        let value = PcccString("HELLO".to_owned());
+        let buf = value.clone().try_into_bytes().unwrap();
+        assert_eq!(buf.len(), 84);
+        assert_eq!(&buf[..8], &[5, 0, b'E', b'H', b'L', b'L', 0, b'O']);
+        let res = PcccString::decode(LittleEndianDecoder::<crate::ClientError>::new(buf)).unwrap();
+        assert_eq!(res, value);
+    }
+}
@@ -0,0 +1,479 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+//! controller profile detection from identity
+//!
+//! ```rust,ignore
+//! let profile = ControllerProfile::detect("192.168.0.83").await?;
+//! println!("{:?}: {}", profile.family, profile.identity.product_name);
+//! match profile.client() {
+//!     DetectedClient::Logix(mut client) => { /* AbService */ }
+//!     DetectedClient::Micro800(mut client) => { /* AbService */ }
+//!     DetectedClient::Pccc(mut client) => { /* PCCC */ }
+//!     DetectedClient::Generic(mut client) => { /* CommonServices */ }
+//! }
+//! // or connected messages, with the connection size of the family
+//! let conn = profile.connection();
+//! ```
+
+use super::{
+    ab_eip::{AbEipClient, AbEipConnection, Micro800Client, Micro800Connection, Micro800Driver},
+    pccc::PcccClient,
+    Driver, EipClient, EipConnection, EipDriver,
+};
+use crate::adapters::Service;
+use crate::{
+    cip::{
+        epath::{EPath, PortSegment, Segment},
+        identity::{Identity, IdentityObject},
+        service::CommonServices,
+    },
+    ClientError, Result,
+};
+use bytes::Bytes;
+use rseip_core::cip::CommonPacketItem;
+use rseip_core::Error;
+use std::net::SocketAddrV4;
+
+/// vendor id of Rockwell Automation/Allen-Bradley
+const VENDOR_ROCKWELL: u16 = 0x01;
+/// device type: communications adapter
+const DEVICE_COMM_ADAPTER: u16 = 0x0C;
+/// device type: programmable logic controller
+const DEVICE_PLC: u16 = 0x0E;
+/// product codes of 1756 EtherNet/IP bridges, eg. 1756-ENBT, 1756-EN2T;
+/// their product names are checked as well
+const PRODUCT_1756_BRIDGES: &[u16] = &[58, 166];
+/// last slot of the largest 1756 chassis, 17 slots
+const MAX_BACKPLANE_SLOT: u8 = 16;
+
+/// controller family
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControllerFamily {
+    /// ControlLogix, 1756 chassis
+    ControlLogix,
+    /// CompactLogix, including 5380/5480
+    CompactLogix,
+    /// Micro820/850/870
+    Micro800,
+    /// SLC 500, PLC-5 and MicroLogix, directly or via a bridge, eg. 1761-NET-ENI
+    Plc5Slc,
+    /// generic CIP device
+    Generic,
+}
+
+impl ControllerFamily {
+    /// identify controller family from vendor id, device type and catalog number of product name
+    pub fn from_identity(identity: &Identity) -> Self {
+        if identity.vendor_id != VENDOR_ROCKWELL {
+            return Self::Generic;
+        }
+        let name = identity.product_name.trim_start().to_ascii_uppercase();
+        let catalog = name.split('/').next().unwrap_or_default();
+        match identity.device_type {
+            DEVICE_PLC => {
+                if catalog.starts_with("1756-") {
+                    Self::ControlLogix
+                } else if ["1768-", "1769-", "5069-"]
+                    .iter()
+                    .any(|v| catalog.starts_with(v))
+                {
+                    Self::CompactLogix
+                } else if catalog.starts_with("2080-") {
+                    Self::Micro800
+                } else if [
+                    "1747-", "1785-", "1761-", "1762-", "1763-", "1764-", "1766-",
+                ]
+                .iter()
+                .any(|v| catalog.starts_with(v))
+                {
+                    Self::Plc5Slc
+                } else {
+                    Self::Generic
+                }
+            }
+            DEVICE_COMM_ADAPTER
+                if ["1761-NET-ENI", "1747-AENTR", "1785-ENET"]
+                    .iter()
+                    .any(|v| catalog.starts_with(v)) =>
+            {
+                Self::Plc5Slc
+            }
+            _ => Self::Generic,
+        }
+    }
+
+    /// 1756 EtherNet/IP bridge, eg. 1756-EN2T, identified by product code or catalog number;
+    /// the controller is behind the bridge, in the backplane
+    pub fn is_backplane_bridge(identity: &Identity) -> bool {
+        if identity.vendor_id != VENDOR_ROCKWELL || identity.device_type != DEVICE_COMM_ADAPTER {
+            return false;
+        }
+        PRODUCT_1756_BRIDGES.contains(&identity.product_code)
+            || identity
+                .product_name
+                .trim_start()
+                .to_ascii_uppercase()
+                .starts_with("1756-EN")
+    }
+
+    /// route path for unconnected messages
+    #[inline]
+    pub fn connection_path(&self) -> EPath {
+        match self {
+            Self::ControlLogix | Self::CompactLogix => PortSegment::default().into(),
+            _ => EPath::default(),
+        }
+    }
+
+    /// max connection size for connected messages
+    #[inline]
+    pub fn connection_size(&self) -> u16 {
+        match self {
+            Self::ControlLogix | Self::CompactLogix => 4002,
+            Self::Micro800 => super::ab_eip::MICRO800_CONNECTION_SIZE,
+            _ => 504,
+        }
+    }
+
+    /// supports large forward open?
+    #[inline]
+    pub fn large_open(&self) -> bool {
+        matches!(self, Self::ControlLogix | Self::CompactLogix)
+    }
+
+    /// supports Logix symbolic services, eg. Read Tag
+    #[inline]
+    pub fn has_symbolic_services(&self) -> bool {
+        matches!(
+            self,
+            Self::ControlLogix | Self::CompactLogix | Self::Micro800
+        )
+    }
+
+    /// supports fragmented services and templates
+    #[inline]
+    pub fn has_fragmented_services(&self) -> bool {
+        matches!(self, Self::ControlLogix | Self::CompactLogix)
+    }
+}
+
+/// detected controller profile
+#[derive(Debug, Clone)]
+pub struct ControllerProfile {
+    /// remote endpoint
+    pub addr: SocketAddrV4,
+    /// identity of the device
+    pub identity: Identity,
+    /// controller family
+    pub family: ControllerFamily,
+    /// backplane slot of ControlLogix or CompactLogix controllers
+    pub slot: u8,
+}
+
+impl ControllerProfile {
+    /// detect controller profile of specified host, with default port if port not specified;
+    /// ListIdentity first, then Identity Object if ListIdentity fails.
+    ///
+    /// For 1756 EtherNet/IP bridges, the backplane is scanned for the first controller;
+    /// see [`ControllerProfile::detect_in_slot`] for the controller in a known slot.
+    #[inline]
+    pub async fn detect(host: impl AsRef<str>) -> Result<Self> {
+        Self::detect_with(host, None).await
+    }
+
+    /// detect controller profile of specified host, like [`ControllerProfile::detect`];
+    /// for 1756 EtherNet/IP bridges, the identity of the controller in specified slot
+    /// of the backplane is read instead of scanning the backplane.
+    #[inline]
+    pub async fn detect_in_slot(host: impl AsRef<str>, slot: u8) -> Result<Self> {
+        Self::detect_with(host, Some(slot)).await
+    }
+
+    async fn detect_with(host: impl AsRef<str>, slot: Option<u8>) -> Result<Self> {
+        let client = EipClient::new_host_lookup(host).await?;
+        let addr = *client.remote_endpoint();
+        let identity = match list_identity(addr).await {
+            Ok(v) => v,
+            Err(e) => {
+                log::debug!("ListIdentity failed: {}, read Identity Object instead", e);
+                read_identity(client).await?
+            }
+        };
+        if !ControllerFamily::is_backplane_bridge(&identity) {
+            return Ok(Self::new(addr, identity));
+        }
+        let found = match slot {
+            Some(slot) => {
+                let client = EipClient::new(addr).with_connection_path(backplane_slot(slot));
+                Some((slot, read_identity(client).await?))
+            }
+            None => scan_backplane(addr).await,
+        };
+        match found {
+            Some((slot, identity)) => Ok(Self::new(addr, identity).with_slot(slot)),
+            None => {
+                log::debug!("no controller in the backplane of {}", addr);
+                Ok(Self::new(addr, identity))
+            }
+        }
+    }
+
+    /// profile from known identity, the controller in slot 0
+    #[inline]
+    pub fn new(addr: SocketAddrV4, identity: Identity) -> Self {
+        let family = ControllerFamily::from_identity(&identity);
+        Self {
+            addr,
+            identity,
+            family,
+            slot: 0,
+        }
+    }
+
+    /// set backplane slot of the controller
+    #[inline]
+    pub fn with_slot(mut self, slot: u8) -> Self {
+        self.slot = slot;
+        self
+    }
+
+    /// route path for unconnected messages, to the slot of the controller
+    #[inline]
+    pub fn connection_path(&self) -> EPath {
+        match self.family {
+            ControllerFamily::ControlLogix | ControllerFamily::CompactLogix => {
+                backplane_slot(self.slot).into()
+            }
+            _ => EPath::default(),
+        }
+    }
+
+    /// options to open connection with the controller
+    pub fn open_options(&self) -> super::OpenOptions {
+        let options = match self.family {
+            // message router of the controller in its slot
+            ControllerFamily::ControlLogix | ControllerFamily::CompactLogix => {
+                super::OpenOptions::default()
+                    .connection_path(self.connection_path().with_class(2).with_instance(1))
+            }
+            ControllerFamily::Micro800 => Micro800Driver::open_options(),
+            // message router of the device itself
+            _ => super::OpenOptions::default()
+                .connection_path(EPath::from(vec![Segment::Class(2), Segment::Instance(1)])),
+        };
+        options
+            .connection_size(self.family.connection_size())
+            .large_open(self.family.large_open())
+    }
+
+    /// client configured for the controller family, with unconnected messages;
+    /// see [`ControllerProfile::connection`] for connected messages
+    pub fn client(&self) -> DetectedClient {
+        match self.family {
+            ControllerFamily::ControlLogix | ControllerFamily::CompactLogix => {
+                DetectedClient::Logix(
+                    AbEipClient::new(self.addr).with_connection_path(self.connection_path()),
+                )
+            }
+            ControllerFamily::Micro800 => DetectedClient::Micro800(Micro800Client::new(self.addr)),
+            ControllerFamily::Plc5Slc => {
+                DetectedClient::Pccc(PcccClient::new(EipClient::new(self.addr)))
+            }
+            ControllerFamily::Generic => DetectedClient::Generic(EipClient::new(self.addr)),
+        }
+    }
+
+    /// connection configured for the controller family, with the connection size
+    /// and large forward open of [`ControllerProfile::open_options`]
+    pub fn connection(&self) -> DetectedConnection {
+        let options = self.open_options();
+        match self.family {
+            ControllerFamily::ControlLogix | ControllerFamily::CompactLogix => {
+                DetectedConnection::Logix(AbEipConnection::new(self.addr, options))
+            }
+            ControllerFamily::Micro800 => {
+                DetectedConnection::Micro800(Micro800Connection::new(self.addr, options))
+            }
+            ControllerFamily::Plc5Slc => {
+                DetectedConnection::Pccc(PcccClient::new(EipConnection::new(self.addr, options)))
+            }
+            ControllerFamily::Generic => {
+                DetectedConnection::Generic(EipConnection::new(self.addr, options))
+            }
+        }
+    }
+}
+
+/// client configured by detected controller profile
+pub enum DetectedClient {
+    /// ControlLogix or CompactLogix
+    Logix(AbEipClient),
+    /// Micro800
+    Micro800(Micro800Client),
+    /// SLC 500, PLC-5 or MicroLogix
+    Pccc(PcccClient),
+    /// generic CIP device
+    Generic(EipClient),
+}
+
+/// connection configured by detected controller profile
+pub enum DetectedConnection {
+    /// ControlLogix or CompactLogix
+    Logix(AbEipConnection),
+    /// Micro800
+    Micro800(Micro800Connection),
+    /// SLC 500, PLC-5 or MicroLogix
+    Pccc(PcccClient<EipConnection>),
+    /// generic CIP device
+    Generic(EipConnection),
+}
+
+impl DetectedClient {
+    /// detect controller profile and create client for it
+    #[inline]
+    pub async fn new_host_lookup(host: impl AsRef<str>) -> Result<Self> {
+        let profile = ControllerProfile::detect(host).await?;
+        Ok(profile.client())
+    }
+}
+
+/// ListIdentity over TCP, no session required
+async fn list_identity(addr: SocketAddrV4) -> Result<Identity> {
+    let mut service = EipDriver::build_service(addr).await?;
+    let res = async {
+        let mut cpf = service.list_identity().await?;
+        if let Some(item) = cpf.next_typed() {
+            let item: CommonPacketItem<IdentityObject> = item?;
+            item.ensure_type_code::<ClientError>(0x0C)?;
+            return Ok(item.data.into());
+        }
+        Err(ClientError::custom("ListIdentity - no identity item"))
+    }
+    .await;
+    let _ = Service::close(&mut service).await;
+    res
+}
+
+/// scan the backplane behind a bridge for the first controller, with one session
+async fn scan_backplane(addr: SocketAddrV4) -> Option<(u8, Identity)> {
+    let mut client = EipClient::new(addr);
+    let mut found = None;
+    for slot in 0..=MAX_BACKPLANE_SLOT {
+        client = client.with_connection_path(backplane_slot(slot));
+        match client.get_identity().await {
+            Ok(identity) if identity.device_type == DEVICE_PLC => {
+                found = Some((slot, identity));
+                break;
+            }
+            Ok(identity) => log::debug!("slot {}: {}", slot, identity.product_name),
+            Err(e) => log::debug!("slot {}: {}", slot, e),
+        }
+    }
+    let _ = crate::cip::service::MessageService::close(&mut client).await;
+    found
+}
+
+/// port segment to specified slot of the backplane
+#[inline]
+fn backplane_slot(slot: u8) -> PortSegment {
+    PortSegment {
+        port: 1,
+        link: Bytes::copy_from_slice(&[slot]),
+    }
+}
+
+/// Identity Object, with the connection path of the client
+async fn read_identity(mut client: EipClient) -> Result<Identity> {
+    let res = client.get_identity().await;
+    let _ = crate::cip::service::MessageService::close(&mut client).await;
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cip::{identity::IdentityStatus, Revision};
+
+    fn identity(vendor_id: u16, device_type: u16, name: &str) -> Identity {
+        Identity {
+            vendor_id,
+            device_type,
+            product_code: 0,
+            revision: Revision { major: 1, minor: 1 },
+            status: IdentityStatus(0),
+            serial_number: 0,
+            product_name: name.to_owned(),
+            state: None,
+        }
+    }
+
+    #[test]
+    fn test_controller_family() {
+        let items = [
+            (1, 0x0E, "1756-L83E/B", ControllerFamily::ControlLogix),
+            (
+                1,
+                0x0E,
+                "1769-L33ER/A LOGIX5333ER",
+                ControllerFamily::CompactLogix,
+            ),
+            (1, 0x0E, "5069-L320ER", ControllerFamily::CompactLogix),
+            (1, 0x0E, "2080-LC50-24QWB", ControllerFamily::Micro800),
+            (1, 0x0E, "1766-L32BWA", ControllerFamily::Plc5Slc),
+            (1, 0x0C, "1761-NET-ENI", ControllerFamily::Plc5Slc),
+            // the bridge itself, the controller behind it is detected by `detect`
+            (1, 0x0C, "1756-EN2T/D", ControllerFamily::Generic),
+            (0x2A, 0x0E, "1756-L83E/B", ControllerFamily::Generic),
+        ];
+        for (vendor_id, device_type, name, family) in items {
+            let res = ControllerFamily::from_identity(&identity(vendor_id, device_type, name));
+            assert_eq!(res, family, "{}", name);
+        }
+        assert!(ControllerFamily::ControlLogix.large_open());
+
+        assert!(ControllerFamily::is_backplane_bridge(&identity(
+            1,
+            0x0C,
+            "1756-EN2T/D"
+        )));
+        let mut bridge = identity(1, 0x0C, "ETHERNET BRIDGE");
+        bridge.product_code = 166;
+        assert!(ControllerFamily::is_backplane_bridge(&bridge));
+        assert!(!ControllerFamily::is_backplane_bridge(&identity(
+            1,
+            0x0C,
+            "1761-NET-ENI"
+        )));
+        assert!(!ControllerFamily::is_backplane_bridge(&identity(
+            1,
+            0x0E,
+            "1756-L83E/B"
+        )));
+        assert!(ControllerFamily::Micro800.connection_path().is_empty());
+    }
+
+    #[test]
+    fn test_profile_slot() {
+        let addr = SocketAddrV4::new([192, 168, 0, 83].into(), 0xAF12);
+        let profile = ControllerProfile::new(addr, identity(1, 0x0E, "1756-L83E/B")).with_slot(3);
+        assert_eq!(
+            profile.connection_path(),
+            EPath::from(vec![Segment::Port(backplane_slot(3))])
+        );
+        assert_eq!(
+            profile.open_options().connection_path,
+            EPath::from(vec![
+                Segment::Port(backplane_slot(3)),
+                Segment::Class(2),
+                Segment::Instance(1)
+            ])
+        );
+        let profile =
+            ControllerProfile::new(addr, identity(1, 0x0E, "2080-LC50-24QWB")).with_slot(3);
+        assert!(profile.connection_path().is_empty());
+    }
+}
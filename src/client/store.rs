@@ -0,0 +1,209 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+//! persisted CIP connections, to close connections left by a crashed run
+//!
+//! Connections opened with a store are recorded until they are closed. On the
+//! first open, connections recorded by a previous run of the same originator
+//! (same vendor id and originator serial number) to the same endpoint are
+//! closed by best-effort Forward_Close.
+//!
+//! ```rust,ignore
+//! let store = Arc::new(FileStore::new("connections.txt"));
+//! let options = OpenOptions::default().originator_serial_number(0x12345678);
+//! let mut conn = AbEipConnection::new_host_lookup("192.168.0.83", options)
+//!     .await?
+//!     .with_store(store);
+//! ```
+
+use bytes::Bytes;
+use core::fmt;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// record of an opened CIP connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionRecord {
+    /// remote endpoint, driver specific
+    pub endpoint: String,
+    /// originator connection serial number
+    pub connection_serial_number: u16,
+    /// originator vendor id
+    pub vendor_id: u16,
+    /// originator serial number
+    pub originator_serial_number: u32,
+    /// encoded connection path
+    pub connection_path: Bytes,
+}
+
+impl ConnectionRecord {
+    /// parse record from text line
+    fn parse(line: &str) -> Option<Self> {
+        let mut parts = line.split_whitespace();
+        let endpoint = parts.next()?.to_owned();
+        let connection_serial_number = u16::from_str_radix(parts.next()?, 16).ok()?;
+        let vendor_id = u16::from_str_radix(parts.next()?, 16).ok()?;
+        let originator_serial_number = u32::from_str_radix(parts.next()?, 16).ok()?;
+        let path = parts.next().unwrap_or_default();
+        if parts.next().is_some() {
+            return None;
+        }
+        let connection_path = (0..path.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(path.get(i..i + 2)?, 16).ok())
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self {
+            endpoint,
+            connection_serial_number,
+            vendor_id,
+            originator_serial_number,
+            connection_path: connection_path.into(),
+        })
+    }
+}
+
+impl fmt::Display for ConnectionRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {:04x} {:04x} {:08x} ",
+            self.endpoint,
+            self.connection_serial_number,
+            self.vendor_id,
+            self.originator_serial_number
+        )?;
+        for b in self.connection_path.iter() {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+/// persistent storage of opened CIP connections;
+/// should not be shared by processes running at the same time
+pub trait ConnectionStore: fmt::Debug + Send + Sync {
+    /// all recorded connections
+    fn load(&self) -> Vec<ConnectionRecord>;
+
+    /// record an opened connection
+    fn save(&self, record: &ConnectionRecord);
+
+    /// remove a closed connection
+    fn remove(&self, record: &ConnectionRecord);
+}
+
+/// store connections in a text file, one line per connection
+#[derive(Debug)]
+pub struct FileStore {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileStore {
+    /// store in the file, created on first save
+    #[inline]
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn read(&self) -> Vec<ConnectionRecord> {
+        let text = match fs::read_to_string(&self.path) {
+            Ok(v) => v,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Vec::new(),
+            Err(e) => {
+                log::warn!("connection store - failed to read {:?}: {}", self.path, e);
+                return Vec::new();
+            }
+        };
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| {
+                let res = ConnectionRecord::parse(line);
+                if res.is_none() {
+                    log::warn!("connection store - invalid record: {}", line);
+                }
+                res
+            })
+            .collect()
+    }
+
+    fn write(&self, records: &[ConnectionRecord]) {
+        let text: String = records.iter().map(|v| format!("{}\n", v)).collect();
+        if let Err(e) = fs::write(&self.path, text) {
+            log::warn!("connection store - failed to write {:?}: {}", self.path, e);
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Vec<ConnectionRecord>)) {
+        let _guard = match self.lock.lock() {
+            Ok(v) => v,
+            Err(e) => e.into_inner(),
+        };
+        let mut records = self.read();
+        f(&mut records);
+        self.write(&records);
+    }
+}
+
+impl ConnectionStore for FileStore {
+    fn load(&self) -> Vec<ConnectionRecord> {
+        let _guard = match self.lock.lock() {
+            Ok(v) => v,
+            Err(e) => e.into_inner(),
+        };
+        self.read()
+    }
+
+    fn save(&self, record: &ConnectionRecord) {
+        self.update(|records| {
+            records.retain(|v| v != record);
+            records.push(record.clone());
+        });
+    }
+
+    fn remove(&self, record: &ConnectionRecord) {
+        self.update(|records| records.retain(|v| v != record));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_store() {
+        let path = std::env::temp_dir().join(format!("rseip-store-{}.txt", std::process::id()));
+        let store = FileStore::new(&path);
+        let record = ConnectionRecord {
+            endpoint: "192.168.0.83:44818".to_owned(),
+            connection_serial_number: 0x1234,
+            vendor_id: 0xFF,
+            originator_serial_number: 0xFFFFFFFF,
+            connection_path: Bytes::from_static(&[0x01, 0x00, 0x20, 0x02, 0x24, 0x01]),
+        };
+        assert_eq!(
+            record.to_string(),
+            "192.168.0.83:44818 1234 00ff ffffffff 010020022401"
+        );
+        assert_eq!(
+            ConnectionRecord::parse(&record.to_string()),
+            Some(record.clone())
+        );
+        assert!(store.load().is_empty());
+        store.save(&record);
+        store.save(&record);
+        assert_eq!(store.load(), vec![record.clone()]);
+        store.remove(&record);
+        assert!(store.load().is_empty());
+        let _ = fs::remove_file(path);
+    }
+}
@@ -233,19 +233,26 @@ mod error;
 #[doc(inline)]
 pub use error::ClientError;
 pub use rseip_cip as cip;
+/// reexport codec, eg. for implementing `Decode` and `Encode`
+pub use rseip_core::codec;
 /// library result
 pub type Result<T> = core::result::Result<T, ClientError>;
 pub use core::result::Result as StdResult;
 pub use rseip_core::{
     codec::BytesHolder,
     codec::{Decode, Encode},
-    Either, String, StringExt,
+    Either, Error, String, StringExt,
 };
 
 /// reexport types for easy usage
 pub mod precludes {
     pub use crate::{
-        cip::{epath::*, service::*},
+        cip::{
+            assembly::AssemblyService, browser::BrowseService,
+            connection_manager::ConnectionManagerService, epath::*,
+            ethernet_link::EthernetLinkService, file::FileService, parameter::ParameterService,
+            service::*, tcpip::TcpIpService, time_sync::TimeSyncService,
+        },
         client::*,
     };
     pub use rseip_core::{
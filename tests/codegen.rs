@@ -0,0 +1,60 @@
+// rseip
+//
+// rseip - Ethernet/IP (CIP) in pure Rust.
+// Copyright: 2021, Joylei <leingliu@gmail.com>
+// License: MIT
+
+//! compiles the code generated by `rseip::client::ab_eip::codegen`,
+//! kept in sync with the generator by its unit tests
+
+#[rustfmt::skip]
+#[path = "codegen/generated.rs"]
+mod generated;
+
+use bytes::Bytes;
+use generated::{Inner, Outer};
+use rseip::client::ab_eip::Timer;
+use rseip::codec::{Decode, LittleEndianDecoder};
+use rseip_core::tests::{CodecError, EncodeExt};
+
+#[test]
+fn test_round_trip() {
+    let value = Outer {
+        run: true,
+        speeds: vec![1.5, -2.0],
+        inner: Inner { value: 7 },
+        delay: Timer {
+            en: true,
+            pre: 1000,
+            acc: 10,
+            raw: 1 << 31,
+            ..Default::default()
+        },
+        stages: vec![Inner { value: 1 }, Inner { value: -1 }],
+    };
+    let buf = value.clone().try_into_bytes().unwrap();
+    assert_eq!(buf.len(), Outer::SIZE);
+    assert_eq!(buf[0], 1 << 3);
+    assert_eq!(&buf[28..36], &[1, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF]);
+
+    let res = Outer::decode(LittleEndianDecoder::<CodecError>::new(buf)).unwrap();
+    assert_eq!(res, value);
+
+    let res = Outer::decode(LittleEndianDecoder::<CodecError>::new(Bytes::from_static(
+        &[0; 8],
+    )));
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_array_length() {
+    let value = Outer::default();
+    assert_eq!(value.speeds.len(), 2);
+    assert_eq!(value.try_into_bytes().unwrap().len(), Outer::SIZE);
+
+    let value = Outer {
+        speeds: vec![1.0; 3],
+        ..Default::default()
+    };
+    assert!(value.try_into_bytes().is_err());
+}
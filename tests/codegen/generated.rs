@@ -0,0 +1,144 @@
+// generated by rseip codegen, do not edit
+
+#![allow(clippy::all)]
+
+use bytes::{Buf, BufMut, BytesMut};
+use rseip::codec::{Decode, Decoder, Encode, Encoder, LittleEndianDecoder};
+use rseip::Error as _;
+
+/// UDT `Outer`, template instance id 0x100
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outer {
+    pub run: bool,
+    pub speeds: Vec<f32>,
+    pub inner: Inner,
+    pub delay: rseip::client::ab_eip::Timer,
+    pub stages: Vec<Inner>,
+}
+
+impl Outer {
+    /// structure size in bytes
+    pub const SIZE: usize = 36;
+}
+
+impl Default for Outer {
+    fn default() -> Self {
+        Self {
+            run: Default::default(),
+            speeds: vec![Default::default(); 2],
+            inner: Default::default(),
+            delay: Default::default(),
+            stages: vec![Default::default(); 2],
+        }
+    }
+}
+
+impl<'de> Decode<'de> for Outer {
+    fn decode<D>(mut decoder: D) -> Result<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        decoder.ensure_size(Self::SIZE)?;
+        let buf = decoder.buf_mut().copy_to_bytes(Self::SIZE);
+        Ok(Self {
+            run: buf[0] & (1 << 3) != 0,
+            speeds: (0..2).map(|i| (&buf[4 + i * 4..]).get_f32_le()).collect(),
+            inner: Inner::decode(LittleEndianDecoder::<D::Error>::new(buf.slice(12..16)))?,
+            delay: rseip::client::ab_eip::Timer::decode(LittleEndianDecoder::<D::Error>::new(buf.slice(16..28)))?,
+            stages: (0..2)
+                .map(|i| {
+                    let start = 28 + i * 4;
+                    Inner::decode(LittleEndianDecoder::<D::Error>::new(buf.slice(start..start + 4)))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+        })
+    }
+}
+
+impl Encode for Outer {
+    fn encode_by_ref<A: Encoder>(
+        &self,
+        buf: &mut BytesMut,
+        encoder: &mut A,
+    ) -> Result<(), A::Error> {
+        if self.speeds.len() != 2 {
+            return Err(A::Error::invalid_length(self.speeds.len(), 2));
+        }
+        if self.stages.len() != 2 {
+            return Err(A::Error::invalid_length(self.stages.len(), 2));
+        }
+        let mut data = vec![0_u8; Self::SIZE];
+        if self.run {
+            data[0] |= 1 << 3;
+        }
+        for (i, v) in self.speeds.iter().enumerate() {
+            (&mut data[4 + i * 4..]).put_f32_le(*v);
+        }
+        {
+            let mut tmp = BytesMut::new();
+            self.inner.encode_by_ref(&mut tmp, encoder)?;
+            let len = tmp.len().min(4);
+            data[12..12 + len].copy_from_slice(&tmp[..len]);
+        }
+        {
+            let mut tmp = BytesMut::new();
+            self.delay.encode_by_ref(&mut tmp, encoder)?;
+            let len = tmp.len().min(12);
+            data[16..16 + len].copy_from_slice(&tmp[..len]);
+        }
+        for (i, v) in self.stages.iter().enumerate() {
+            let mut tmp = BytesMut::new();
+            v.encode_by_ref(&mut tmp, encoder)?;
+            let start = 28 + i * 4;
+            let len = tmp.len().min(4);
+            data[start..start + len].copy_from_slice(&tmp[..len]);
+        }
+        buf.put_slice(&data);
+        Ok(())
+    }
+
+    fn bytes_count(&self) -> usize {
+        Self::SIZE
+    }
+}
+
+/// UDT `Inner`, template instance id 0x101
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Inner {
+    pub value: i32,
+}
+
+impl Inner {
+    /// structure size in bytes
+    pub const SIZE: usize = 4;
+}
+
+impl<'de> Decode<'de> for Inner {
+    fn decode<D>(mut decoder: D) -> Result<Self, D::Error>
+    where
+        D: Decoder<'de>,
+    {
+        decoder.ensure_size(Self::SIZE)?;
+        let buf = decoder.buf_mut().copy_to_bytes(Self::SIZE);
+        Ok(Self {
+            value: (&buf[0..]).get_i32_le(),
+        })
+    }
+}
+
+impl Encode for Inner {
+    fn encode_by_ref<A: Encoder>(
+        &self,
+        buf: &mut BytesMut,
+        _encoder: &mut A,
+    ) -> Result<(), A::Error> {
+        let mut data = vec![0_u8; Self::SIZE];
+        (&mut data[0..]).put_i32_le(self.value);
+        buf.put_slice(&data);
+        Ok(())
+    }
+
+    fn bytes_count(&self) -> usize {
+        Self::SIZE
+    }
+}